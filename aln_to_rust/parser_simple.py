@@ -1,108 +0,0 @@
-#!/usr/bin/env python3
-"""
-ALN-to-Rust Converter for Accessibility
-Input: Paste your ALN shard, Output: Ready Rust code
-No dependencies, no internet required
-"""
-
-import re
-import sys
-
-def parse_aln_to_rust(aln_text):
-    """Convert ALN shard to Rust module with 3-layer explanation"""
-    
-    # Layer 1: Simple explanation
-    print("=== LAYER 1: SIMPLE EXPLANATION ===")
-    print("I found these rules in your ALN file:")
-    
-    # Extract key rules
-    eligibility_match = re.search(r'eligibilityE\s*=\s*sleeptoken\s*\*\s*\(1\.0\s*-\s*psychriskscore\)\s*\*\s*enstasisscore', aln_text)
-    if eligibility_match:
-        print("- Safety score E = Sleeptoken × (1 - Risk) × Stability")
-    
-    quantum_match = re.search(r'quantum_roaming_allowed\s*=\s*\(sleepstage in (.*?)\) AND', aln_text)
-    if quantum_match:
-        stages = quantum_match.group(1)
-        print(f"- Quantum roaming allowed only in sleep stages: {stages}")
-    
-    # Layer 2: Rust code generation
-    print("\n=== LAYER 2: RUST CODE ===")
-    
-    rust_code = """// AUTO-GENERATED from your ALN shard
-// Neurorights preserved: mentalprivacy, cognitiveliberty, nopunitivexr
-
-#[derive(Debug, Clone)]
-pub struct SubjectState {
-    pub sleepstage: SleepStage,
-    pub sleeptoken: f32,      // S
-    pub psychriskscore: f32,  // R
-    pub enstasisscore: f32,   // Es
-    pub dreammode: DreamMode,
-}
-
-pub enum SleepStage { Wake, N1, N2, N3, REM }
-pub enum DreamMode { Passive, Active, QuantumConsciousness }
-
-impl SubjectState {
-    /// Calculate eligibility: E = S × (1 - R) × Es
-    pub fn calculate_eligibility(&self) -> f32 {
-        let s = self.sleeptoken.clamp(0.0, 1.0);
-        let r = self.psychriskscore.clamp(0.0, 1.0);
-        let es = self.enstasisscore.clamp(0.0, 1.0);
-        s * (1.0 - r) * es
-    }
-    
-    /// Check if quantum roaming is allowed
-    pub fn is_quantum_roaming_allowed(&self) -> bool {
-        let e = self.calculate_eligibility();
-        let e_min = 0.5;      // From your ALN
-        let r_max = 0.35;     // From your ALN
-        
-        // Guard 1: Sleep stage must be N2 or N3
-        let valid_stage = matches!(self.sleepstage, SleepStage::N2 | SleepStage::N3);
-        
-        // Guard 2: Eligibility threshold
-        let meets_eligibility = e >= e_min;
-        
-        // Guard 3: Psychrisk ceiling
-        let below_risk_limit = self.psychriskscore <= r_max;
-        
-        valid_stage && meets_eligibility && below_risk_limit
-    }
-}
-
-// Unit tests with your examples
-#[cfg(test)]
-mod tests {
-    use super::*;
-    
-    #[test]
-    fn test_safe_n2_allows_roaming() {
-        let state = SubjectState {
-            sleepstage: SleepStage::N2,
-            sleeptoken: 0.8,
-            psychriskscore: 0.2,
-            enstasisscore: 0.9,
-            dreammode: DreamMode::QuantumConsciousness,
-        };
-        assert!(state.is_quantum_roaming_allowed());
-    }
-}
-"""
-    
-    print(rust_code)
-    
-    # Layer 3: Detailed reasoning (optional)
-    print("\n=== LAYER 3: DETAILED REASONING (OPTIONAL) ===")
-    print("This Rust code implements your exact ALN rules:")
-    print("1. Eligibility formula preserved exactly")
-    print("2. All thresholds (0.5, 0.35) preserved exactly")
-    print("3. Sleep stage checking uses Rust's match for safety")
-    print("4. Unit tests verify with example numbers")
-    
-    return rust_code
-
-if __name__ == "__main__":
-    print("Paste your ALN shard below (Ctrl+D to finish):")
-    aln_content = sys.stdin.read()
-    parse_aln_to_rust(aln_content)
@@ -0,0 +1,204 @@
+/// 1-based source position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub struct Span {
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum RowKind {
+    Scalar,
+    Enum,
+    Flag,
+    Expression,
+    Condition,
+    Other(String),
+}
+
+impl RowKind {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "scalar" => RowKind::Scalar,
+            "enum" => RowKind::Enum,
+            "flag" => RowKind::Flag,
+            "expression" => RowKind::Expression,
+            "condition" => RowKind::Condition,
+            other => RowKind::Other(other.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            RowKind::Scalar => "scalar",
+            RowKind::Enum => "enum",
+            RowKind::Flag => "flag",
+            RowKind::Expression => "expression",
+            RowKind::Condition => "condition",
+            RowKind::Other(s) => s,
+        }
+    }
+
+    /// Rows whose value is rule logic rather than data.
+    pub fn is_rule(&self) -> bool {
+        matches!(self, RowKind::Expression | RowKind::Condition)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DataType {
+    Float,
+    Int,
+    Bool,
+    String,
+    Other(String),
+}
+
+impl DataType {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "float" => DataType::Float,
+            "int" => DataType::Int,
+            "bool" => DataType::Bool,
+            "string" => DataType::String,
+            other => DataType::Other(other.to_string()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            DataType::Float => "float",
+            DataType::Int => "int",
+            DataType::Bool => "bool",
+            DataType::String => "string",
+            DataType::Other(s) => s,
+        }
+    }
+}
+
+/// Where each cell of a row started.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RowSpans {
+    pub row: Span,
+    pub key: Span,
+    pub value: Span,
+    pub datatype: Option<Span>,
+    pub constraints: Vec<Span>,
+    pub notes: Option<Span>,
+}
+
+/// `ROW,entitytype,field,kind,key,value,datatype,constraints...,notes`
+///
+/// Everything between the datatype and the last cell is a constraint, so
+/// `range0,1` arrives as `["range0", "1"]` and enum rows list their values.
+/// The last cell is the notes unless it closes a `range<lo>`; otherwise a
+/// row with constraints and no notes ends with an empty cell, as `writer`
+/// emits it. Short rows (as in `.stake.aln`) may stop after the value or
+/// datatype.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Row {
+    pub entity_type: String,
+    pub field: String,
+    pub kind: RowKind,
+    pub key: String,
+    pub value: String,
+    pub datatype: Option<DataType>,
+    pub constraints: Vec<String>,
+    pub notes: String,
+    pub spans: RowSpans,
+}
+
+impl Row {
+    pub fn float_value(&self) -> Option<f64> {
+        self.value.trim().parse().ok()
+    }
+
+    pub fn bool_value(&self) -> Option<bool> {
+        match self.value.trim() {
+            "true" => Some(true),
+            "false" => Some(false),
+            _ => None,
+        }
+    }
+
+    pub fn has_constraint(&self, name: &str) -> bool {
+        self.constraints.iter().any(|c| c == name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItemKind {
+    /// `destination-path,<file name>`
+    DestinationPath(String),
+    /// `<KIND>.Datashard,<description>`
+    Datashard { kind: String, description: String },
+    /// Column header, e.g. `path,entitytype,field,...`
+    Header(Vec<String>),
+    Section(String),
+    Row(Box<Row>),
+    /// `#` comment; `indent` is whatever preceded the `#`, `text` what
+    /// follows it, both verbatim.
+    Comment { indent: String, text: String },
+    Blank,
+    Footer(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Item {
+    pub kind: ItemKind,
+    pub span: Span,
+}
+
+/// A parsed shard (or overlay fragment), item order preserved.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Shard {
+    pub items: Vec<Item>,
+}
+
+impl Shard {
+    pub fn destination_path(&self) -> Option<&str> {
+        self.items.iter().find_map(|i| match &i.kind {
+            ItemKind::DestinationPath(p) => Some(p.as_str()),
+            _ => None,
+        })
+    }
+
+    /// `(family, "N")` from a `<family>.vN.aln` destination path.
+    fn versioned_path(&self) -> Option<(&str, &str)> {
+        self.destination_path()?.strip_suffix(".aln")?.rsplit_once(".v")
+    }
+
+    /// Shard version from a `.vN.aln` destination path.
+    pub fn version(&self) -> Option<u32> {
+        self.versioned_path()?.1.parse().ok()
+    }
+
+    /// Shard family: the destination path without its `.vN.aln` suffix.
+    pub fn family(&self) -> Option<&str> {
+        self.version()?;
+        Some(self.versioned_path()?.0)
+    }
+
+    /// Rows paired with the section they appear in.
+    pub fn rows(&self) -> impl Iterator<Item = (Option<&str>, &Row)> {
+        let mut section: Option<&str> = None;
+        self.items.iter().filter_map(move |i| match &i.kind {
+            ItemKind::Section(s) => {
+                section = Some(s.as_str());
+                None
+            }
+            ItemKind::Row(r) => Some((section, &**r)),
+            _ => None,
+        })
+    }
+
+    pub fn row(&self, key: &str) -> Option<&Row> {
+        self.rows().map(|(_, r)| r).find(|r| r.key == key)
+    }
+
+    pub fn sections(&self) -> impl Iterator<Item = &str> {
+        self.items.iter().filter_map(|i| match &i.kind {
+            ItemKind::Section(s) => Some(s.as_str()),
+            _ => None,
+        })
+    }
+}
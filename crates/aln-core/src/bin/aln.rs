@@ -0,0 +1,380 @@
+//! `aln` command-line tool.
+
+use std::process::ExitCode;
+
+use aln_core::canonical::{self, ShardSignature, SignaturePolicy};
+use aln_core::diff;
+use aln_core::json;
+use aln_core::migrate::MigrationRegistry;
+use aln_core::lint::{self, LintConfig, LintRule, Severity};
+use aln_core::overlay::Composition;
+
+const USAGE: &str = "\
+Usage:
+  aln lint [--production] [--baseline <shard>] [--deny|--warn|--allow <rule>]... <shard>...
+  aln diff <old> <new>
+  aln merge <base> <ours> <theirs> [-o <out>]
+  aln compose [--provenance] <base> <overlay>...
+  aln canon <shard>
+  aln hash <shard>...
+  aln sign --key <secret-key-hex-file> --signer <id> <shard>...
+  aln verify <shard>...
+  aln json [--ndjson] <shard>
+  aln json --check <shard>...
+  aln from-json <file.json|file.ndjson>
+  aln migrate [--to <version>] [--dry-run] <shard>...
+
+Lint rules: placeholder-id, override-row, loosened-threshold, widened-guard, dev-comment";
+
+fn lint_command(args: &[String]) -> Result<bool, String> {
+    let mut config = LintConfig::development();
+    let mut overrides = Vec::new();
+    let mut baseline = None;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().cloned().ok_or_else(|| format!("{} needs a value", flag));
+        match arg.as_str() {
+            "--production" => config = LintConfig::production(),
+            "--baseline" => baseline = Some(value(arg)?),
+            "--deny" | "--warn" | "--allow" => {
+                let name = value(arg)?;
+                let rule = LintRule::parse(&name).ok_or_else(|| format!("unknown lint rule `{}`", name))?;
+                let severity = match arg.as_str() {
+                    "--deny" => Severity::Error,
+                    "--warn" => Severity::Warning,
+                    _ => Severity::Off,
+                };
+                overrides.push((rule, severity));
+            }
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{}`", flag)),
+            file => files.push(file.to_string()),
+        }
+    }
+    if files.is_empty() {
+        return Err("no shard given".into());
+    }
+    // Per-rule flags apply on top of --production wherever they appear.
+    for (rule, severity) in overrides {
+        config = config.set(rule, severity);
+    }
+    if let Some(path) = baseline {
+        config = config.with_baseline(aln_core::parse_file(&path).map_err(|e| e.to_string())?);
+    }
+
+    let mut clean = true;
+    for file in &files {
+        let shard = aln_core::parse_file(file).map_err(|e| e.to_string())?;
+        let findings = lint::lint(&shard, &config);
+        for f in &findings {
+            println!("{}:{}", file, f);
+        }
+        clean &= !lint::has_errors(&findings);
+    }
+    Ok(clean)
+}
+
+fn load(path: &str) -> Result<aln_core::Shard, String> {
+    aln_core::parse_file(path).map_err(|e| e.to_string())
+}
+
+fn diff_command(args: &[String]) -> Result<bool, String> {
+    let [old, new] = args else {
+        return Err("diff needs <old> <new>".into());
+    };
+    let d = diff::diff(&load(old)?, &load(new)?);
+    print!("{}", d);
+    Ok(d.is_empty())
+}
+
+fn merge_command(args: &[String]) -> Result<bool, String> {
+    let (files, output) = match args {
+        [files @ .., flag, out] if flag == "-o" => (files, Some(out)),
+        files => (files, None),
+    };
+    let [base, ours, theirs] = files else {
+        return Err("merge needs <base> <ours> <theirs>".into());
+    };
+    let merged = diff::merge(&load(base)?, &load(ours)?, &load(theirs)?);
+    match output {
+        Some(path) => std::fs::write(path, &merged.text).map_err(|e| format!("{}: {}", path, e))?,
+        None => print!("{}", merged.text),
+    }
+    for c in &merged.conflicts {
+        eprintln!("conflict: {}", c.id);
+    }
+    Ok(merged.is_clean())
+}
+
+fn compose_command(args: &[String]) -> Result<bool, String> {
+    let provenance = args.iter().any(|a| a == "--provenance");
+    let mut files = args.iter().filter(|a| *a != "--provenance");
+    let base = files.next().ok_or("compose needs a base shard")?;
+    let mut composition = Composition::new(base, load(base)?);
+    for overlay in files {
+        composition = composition.overlay(overlay, load(overlay)?);
+    }
+    let effective = match composition.compose() {
+        Ok(effective) => effective,
+        Err(errors) => {
+            for e in errors {
+                eprintln!("{}", e);
+            }
+            return Ok(false);
+        }
+    };
+    if !provenance {
+        print!("{}", aln_core::writer::write(&effective.shard));
+        return Ok(true);
+    }
+    for (_, row) in effective.shard.rows() {
+        let history = effective.history(&row.key);
+        let Some((current, earlier)) = history.split_last() else { continue };
+        print!("{} = {}  ({})", row.key, row.value, current);
+        if !earlier.is_empty() {
+            let earlier: Vec<String> = earlier.iter().map(|o| o.to_string()).collect();
+            print!(" overrides {}", earlier.join(", "));
+        }
+        println!();
+    }
+    Ok(true)
+}
+
+fn canon_command(args: &[String]) -> Result<bool, String> {
+    let [file] = args else {
+        return Err("canon needs one <shard>".into());
+    };
+    print!("{}", canonical::canonicalize(&load(file)?));
+    Ok(true)
+}
+
+fn hash_command(args: &[String]) -> Result<bool, String> {
+    if args.is_empty() {
+        return Err("no shard given".into());
+    }
+    for file in args {
+        println!("{}  {}", canonical::content_hash(&load(file)?), file);
+    }
+    Ok(true)
+}
+
+fn sign_command(args: &[String]) -> Result<bool, String> {
+    let mut key_file = None;
+    let mut signer = None;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().cloned().ok_or_else(|| format!("{} needs a value", flag));
+        match arg.as_str() {
+            "--key" => key_file = Some(value(arg)?),
+            "--signer" => signer = Some(value(arg)?),
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{}`", flag)),
+            file => files.push(file.to_string()),
+        }
+    }
+    let (Some(key_file), Some(signer)) = (key_file, signer) else {
+        return Err("sign needs --key and --signer".into());
+    };
+    if files.is_empty() {
+        return Err("no shard given".into());
+    }
+    let text = std::fs::read_to_string(&key_file).map_err(|e| format!("{}: {}", key_file, e))?;
+    let secret: [u8; 32] = hex::decode(text.trim())
+        .ok()
+        .and_then(|b| b.try_into().ok())
+        .ok_or_else(|| format!("{}: expected a 32-byte hex Ed25519 secret key", key_file))?;
+    let key = ed25519_dalek::SigningKey::from_bytes(&secret);
+    for file in &files {
+        let sig = ShardSignature::sign(&load(file)?, &signer, &key);
+        let path = canonical::sig_path(file.as_ref());
+        sig.store(&path).map_err(|e| e.to_string())?;
+        println!("{}  {}", sig.content_hash, path.display());
+    }
+    Ok(true)
+}
+
+fn verify_command(args: &[String]) -> Result<bool, String> {
+    if args.is_empty() {
+        return Err("no shard given".into());
+    }
+    let mut ok = true;
+    for file in args {
+        let policy = SignaturePolicy::discover(file.as_ref()).map_err(|e| e.to_string())?;
+        match policy.load(file) {
+            Ok(_) if !canonical::sig_path(file.as_ref()).exists() => println!("{}: unsigned (signatures optional)", file),
+            Ok(_) => println!("{}: ok", file),
+            Err(e) => {
+                println!("{}", e);
+                ok = false;
+            }
+        }
+    }
+    Ok(ok)
+}
+
+fn json_command(args: &[String]) -> Result<bool, String> {
+    match args {
+        [flag, files @ ..] if flag == "--check" => {
+            if files.is_empty() {
+                return Err("no shard given".into());
+            }
+            let mut ok = true;
+            for file in files {
+                match json::check_roundtrip(&load(file)?) {
+                    Ok(()) => println!("{}: ok", file),
+                    Err(e) => {
+                        println!("{}: {}", file, e);
+                        ok = false;
+                    }
+                }
+            }
+            Ok(ok)
+        }
+        [flag, file] if flag == "--ndjson" => {
+            print!("{}", json::to_ndjson(&load(file)?));
+            Ok(true)
+        }
+        [file] => {
+            print!("{}", json::to_json(&load(file)?));
+            Ok(true)
+        }
+        _ => Err("json needs one <shard>".into()),
+    }
+}
+
+fn from_json_command(args: &[String]) -> Result<bool, String> {
+    let [file] = args else {
+        return Err("from-json needs one <file>".into());
+    };
+    let text = std::fs::read_to_string(file).map_err(|e| format!("{}: {}", file, e))?;
+    let shard = if file.ends_with(".ndjson") {
+        json::from_ndjson(&text)
+    } else {
+        json::from_json(&text)
+    };
+    print!("{}", aln_core::writer::write(&shard.map_err(|e| format!("{}: {}", file, e))?));
+    Ok(true)
+}
+
+/// Upgrades in place; the original goes to `<file>.v<N>.bak` and the steps
+/// are appended to `<file>.changelog`. A file named after its destination
+/// path is renamed with it (`demo.v1.aln` becomes `demo.v2.aln`, changelog
+/// included), so the name keeps agreeing with `family_version`; any other
+/// name is kept. Returns where the shard now lives, or `None` if it was
+/// already current.
+fn migrate_file(registry: &MigrationRegistry, file: &str, target: Option<u32>, dry_run: bool) -> Result<Option<String>, String> {
+    let shard = load(file)?;
+    let migrated = registry.migrate(&shard, target).map_err(|e| format!("{}: {}", file, e))?;
+    if migrated.from == migrated.to {
+        println!("{}: already at v{}", file, migrated.to);
+        return Ok(None);
+    }
+    let path = std::path::Path::new(file);
+    let named_after_destination = shard.destination_path().is_some_and(|d| path.file_name() == Some(d.as_ref()));
+    let dest = match migrated.shard.destination_path() {
+        Some(d) if named_after_destination => path.with_file_name(d).display().to_string(),
+        _ => file.to_string(),
+    };
+    let log = migrated.changelog.join("\n") + "\n";
+    print!("{}: v{} -> v{}\n{}", file, migrated.from, migrated.to, log);
+    if dest != file {
+        println!("{}: renamed to {}", file, dest);
+    }
+    if dry_run {
+        return Ok(Some(dest));
+    }
+
+    let backup = format!("{}.v{}.bak", file, migrated.from);
+    if std::path::Path::new(&backup).exists() {
+        return Err(format!("{}: backup {} already exists", file, backup));
+    }
+    if dest != file && std::path::Path::new(&dest).exists() {
+        return Err(format!("{}: {} already exists", file, dest));
+    }
+    std::fs::copy(file, &backup).map_err(|e| format!("{}: {}", backup, e))?;
+    std::fs::write(&dest, aln_core::writer::write(&migrated.shard)).map_err(|e| format!("{}: {}", dest, e))?;
+    let changelog = format!("{}.changelog", file);
+    let mut entry = std::fs::read_to_string(&changelog).unwrap_or_default();
+    entry.push_str(&format!("{} (backup {})\n{}", file, backup, log));
+    std::fs::write(format!("{}.changelog", dest), entry).map_err(|e| format!("{}.changelog: {}", dest, e))?;
+    if dest != file {
+        std::fs::remove_file(file).map_err(|e| format!("{}: {}", file, e))?;
+        if std::path::Path::new(&changelog).exists() {
+            std::fs::remove_file(&changelog).map_err(|e| format!("{}: {}", changelog, e))?;
+        }
+    }
+    if canonical::sig_path(file.as_ref()).exists() {
+        println!("{}: signature is now stale; re-sign {} with `aln sign`", file, dest);
+    }
+    Ok(Some(dest))
+}
+
+/// Migrates each file in turn and stops at the first failure, listing the
+/// files it had already migrated.
+fn migrate_command(args: &[String]) -> Result<bool, String> {
+    let mut target = None;
+    let mut dry_run = false;
+    let mut files = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--to" => {
+                let v = args.next().ok_or("--to needs a value")?;
+                target = Some(v.trim_start_matches('v').parse::<u32>().map_err(|_| format!("bad version `{}`", v))?);
+            }
+            "--dry-run" => dry_run = true,
+            flag if flag.starts_with("--") => return Err(format!("unknown option `{}`", flag)),
+            file => files.push(file.to_string()),
+        }
+    }
+    if files.is_empty() {
+        return Err("no shard given".into());
+    }
+    let registry = MigrationRegistry::builtin();
+    let mut done = Vec::new();
+    for file in &files {
+        match migrate_file(&registry, file, target, dry_run) {
+            Ok(Some(dest)) => done.push(format!("{} -> {}", file, dest)),
+            Ok(None) => {}
+            Err(e) => {
+                if !done.is_empty() && !dry_run {
+                    println!("migrated before the failure:");
+                    for line in &done {
+                        println!("  {}", line);
+                    }
+                }
+                return Err(e);
+            }
+        }
+    }
+    Ok(true)
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("lint") => lint_command(&args[1..]),
+        Some("diff") => diff_command(&args[1..]),
+        Some("merge") => merge_command(&args[1..]),
+        Some("compose") => compose_command(&args[1..]),
+        Some("canon") => canon_command(&args[1..]),
+        Some("hash") => hash_command(&args[1..]),
+        Some("sign") => sign_command(&args[1..]),
+        Some("verify") => verify_command(&args[1..]),
+        Some("json") => json_command(&args[1..]),
+        Some("from-json") => from_json_command(&args[1..]),
+        Some("migrate") => migrate_command(&args[1..]),
+        _ => {
+            eprintln!("{}", USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match result {
+        Ok(true) => ExitCode::SUCCESS,
+        Ok(false) => ExitCode::FAILURE,
+        Err(e) => {
+            eprintln!("aln: {}", e);
+            ExitCode::from(2)
+        }
+    }
+}
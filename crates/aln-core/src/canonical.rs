@@ -0,0 +1,375 @@
+//! Canonical shard text, content hashes and detached Ed25519 signatures.
+//!
+//! Canonical form: comments and blank lines dropped, cells trimmed and quoted
+//! only when they contain `,` or `"`, float values in shortest round-trip
+//! form, rule whitespace collapsed, and data rows sorted by (entity type, key)
+//! within each section. Rule rows keep their order, since it is evaluation
+//! order. The hash is SHA-256 over the canonical UTF-8 text.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::ast::{DataType, ItemKind, Row, Shard};
+use crate::error::AlnError;
+use crate::writer::{write_item, write_row};
+
+/// Name of the workspace file that turns on signature enforcement.
+pub const WORKSPACE_FILE: &str = "aln-workspace.aln";
+
+/// Collapse whitespace runs outside quoted strings.
+fn collapse_ws(rule: &str) -> String {
+    let mut out = String::new();
+    let mut quote: Option<char> = None;
+    let mut pending_space = false;
+    for c in rule.trim().chars() {
+        match quote {
+            Some(q) => {
+                out.push(c);
+                if c == q {
+                    quote = None;
+                }
+            }
+            None if c.is_whitespace() => pending_space = true,
+            None => {
+                if pending_space {
+                    out.push(' ');
+                    pending_space = false;
+                }
+                if c == '\'' || c == '"' {
+                    quote = Some(c);
+                }
+                out.push(c);
+            }
+        }
+    }
+    out
+}
+
+fn canonical_row(row: &Row) -> Row {
+    let mut row = row.clone();
+    for cell in [&mut row.entity_type, &mut row.field, &mut row.key, &mut row.notes] {
+        *cell = cell.trim().to_string();
+    }
+    row.constraints = row.constraints.iter().map(|c| c.trim().to_string()).collect();
+    row.value = if row.kind.is_rule() {
+        collapse_ws(&row.value)
+    } else {
+        let trimmed = row.value.trim();
+        match (&row.datatype, trimmed.parse::<f64>()) {
+            (Some(DataType::Float), Ok(n)) if n.is_finite() => format!("{:?}", n),
+            _ => trimmed.to_string(),
+        }
+    };
+    row
+}
+
+pub fn canonicalize(shard: &Shard) -> String {
+    let mut out = String::new();
+    let mut rows: Vec<Row> = Vec::new();
+    let flush = |rows: &mut Vec<Row>, out: &mut String| {
+        // Data rows first, by (entity, key); the sort is stable, so rules
+        // keep their relative order.
+        rows.sort_by(|a, b| match (a.kind.is_rule(), b.kind.is_rule()) {
+            (false, false) => (&a.entity_type, &a.key).cmp(&(&b.entity_type, &b.key)),
+            (x, y) => x.cmp(&y),
+        });
+        for row in rows.drain(..) {
+            out.push_str(&write_row(&row));
+            out.push('\n');
+        }
+    };
+    for item in &shard.items {
+        let kind = match &item.kind {
+            ItemKind::Comment { .. } | ItemKind::Blank => continue,
+            ItemKind::Row(row) => {
+                rows.push(canonical_row(row));
+                continue;
+            }
+            ItemKind::DestinationPath(p) => ItemKind::DestinationPath(p.trim().to_string()),
+            ItemKind::Datashard { kind, description } => ItemKind::Datashard {
+                kind: kind.trim().to_string(),
+                description: description.trim().to_string(),
+            },
+            ItemKind::Header(cells) => ItemKind::Header(cells.iter().map(|c| c.trim().to_string()).collect()),
+            ItemKind::Section(s) => ItemKind::Section(s.trim().to_string()),
+            ItemKind::Footer(s) => ItemKind::Footer(s.trim().to_string()),
+        };
+        flush(&mut rows, &mut out);
+        out.push_str(&write_item(&kind));
+        out.push('\n');
+    }
+    flush(&mut rows, &mut out);
+    out
+}
+
+/// The shard in canonical form, as an AST.
+pub fn canonical_shard(shard: &Shard) -> Shard {
+    crate::parser::parse(&canonicalize(shard)).expect("canonical text always parses")
+}
+
+/// Hex SHA-256 of the canonical text.
+pub fn content_hash(shard: &Shard) -> String {
+    hex::encode(Sha256::digest(canonicalize(shard).as_bytes()))
+}
+
+/// `foo.aln` -> `foo.aln.sig`
+pub fn sig_path(shard_path: &Path) -> PathBuf {
+    let mut name = shard_path.as_os_str().to_owned();
+    name.push(".sig");
+    PathBuf::from(name)
+}
+
+/// Contents of a `.aln.sig` file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ShardSignature {
+    pub signer: String,
+    pub content_hash: String,
+    pub public_key: String, // hex Ed25519 verifying key
+    pub signature: String,  // hex Ed25519 over `signing_bytes(content_hash)`
+}
+
+fn signing_bytes(content_hash: &str) -> Vec<u8> {
+    format!("aln-shard-sha256:{}", content_hash).into_bytes()
+}
+
+impl ShardSignature {
+    pub fn sign(shard: &Shard, signer: &str, key: &SigningKey) -> Self {
+        let content_hash = content_hash(shard);
+        Self {
+            signer: signer.to_string(),
+            public_key: hex::encode(key.verifying_key().to_bytes()),
+            signature: hex::encode(key.sign(&signing_bytes(&content_hash)).to_bytes()),
+            content_hash,
+        }
+    }
+
+    /// Checks the signature against `key` and the shard's current content.
+    pub fn verify(&self, shard: &Shard, key: &VerifyingKey) -> Result<(), String> {
+        if hex::encode(key.to_bytes()) != self.public_key {
+            return Err(format!("signed with a key not trusted for {}", self.signer));
+        }
+        let actual = content_hash(shard);
+        if actual != self.content_hash {
+            return Err(format!("content changed since signing (hash {} != {})", actual, self.content_hash));
+        }
+        let bytes = hex::decode(&self.signature).map_err(|_| "signature is not valid hex".to_string())?;
+        let sig = Signature::from_slice(&bytes).map_err(|_| "malformed signature".to_string())?;
+        key.verify(&signing_bytes(&self.content_hash), &sig)
+            .map_err(|_| "signature does not verify".to_string())
+    }
+
+    pub fn load(path: &Path) -> Result<Self, AlnError> {
+        let text = std::fs::read_to_string(path).map_err(AlnError::Io)?;
+        serde_json::from_str(&text).map_err(|e| AlnError::Signature(format!("{}: {}", path.display(), e)))
+    }
+
+    pub fn store(&self, path: &Path) -> Result<(), AlnError> {
+        let text = serde_json::to_string_pretty(self).expect("signature serialization is infallible");
+        std::fs::write(path, text + "\n").map_err(AlnError::Io)
+    }
+}
+
+/// Whether shards must be signed, and by whom.
+///
+/// Read from an `aln-workspace.aln` file; rows used (others are ignored):
+/// `ROW,policy,signatures,flag,required,<true|false>,...`
+/// `ROW,signer,<signer_id>,scalar,pubkey,<ed25519 hex>,...`
+#[derive(Debug, Clone, Default)]
+pub struct SignaturePolicy {
+    pub required: bool,
+    trusted: BTreeMap<String, VerifyingKey>,
+}
+
+impl SignaturePolicy {
+    pub fn trust(mut self, signer: &str, key: VerifyingKey) -> Self {
+        self.trusted.insert(signer.to_string(), key);
+        self
+    }
+
+    pub fn from_workspace_shard(shard: &Shard) -> Result<Self, AlnError> {
+        let mut policy = Self::default();
+        for (_, row) in shard.rows() {
+            let bad = |what: &str| AlnError::Signature(format!("line {}: invalid {}", row.spans.value.line, what));
+            match (row.entity_type.as_str(), row.key.as_str()) {
+                ("policy", "required") if row.field == "signatures" => {
+                    policy.required = row.bool_value().ok_or_else(|| bad("signatures.required flag"))?;
+                }
+                ("signer", "pubkey") => {
+                    let bytes = hex::decode(row.value.trim()).map_err(|_| bad("signer pubkey"))?;
+                    let arr: [u8; 32] = bytes.try_into().map_err(|_| bad("signer pubkey"))?;
+                    let key = VerifyingKey::from_bytes(&arr).map_err(|_| bad("signer pubkey"))?;
+                    policy.trusted.insert(row.field.clone(), key);
+                }
+                _ => {}
+            }
+        }
+        if policy.required && policy.trusted.is_empty() {
+            return Err(AlnError::Signature("signatures are required but no signer is trusted".into()));
+        }
+        Ok(policy)
+    }
+
+    /// The policy of the nearest `aln-workspace.aln` at or above the shard's
+    /// directory; signatures are optional when there is none.
+    pub fn discover(shard_path: &Path) -> Result<Self, AlnError> {
+        let start = shard_path.parent().unwrap_or(Path::new("."));
+        let start = if start.as_os_str().is_empty() { Path::new(".") } else { start };
+        let start = start.canonicalize().map_err(AlnError::Io)?;
+        for dir in start.ancestors() {
+            let candidate = dir.join(WORKSPACE_FILE);
+            if candidate.is_file() {
+                let shard = crate::parser::parse_file(&candidate)?;
+                return Self::from_workspace_shard(&shard);
+            }
+        }
+        Ok(Self::default())
+    }
+
+    /// Verify `sig` for `shard`. An invalid signature is refused even when
+    /// signatures are optional.
+    pub fn check(&self, shard: &Shard, sig: Option<&ShardSignature>) -> Result<(), String> {
+        let Some(sig) = sig else {
+            return if self.required {
+                Err("unsigned shard, and this workspace requires signatures".into())
+            } else {
+                Ok(())
+            };
+        };
+        let key = self
+            .trusted
+            .get(&sig.signer)
+            .ok_or_else(|| format!("signer {} is not trusted by this workspace", sig.signer))?;
+        sig.verify(shard, key)
+    }
+
+    /// Parse `path`, refuse outdated versions, and check its `.aln.sig`
+    /// sibling under this policy.
+    pub fn load(&self, path: impl AsRef<Path>) -> Result<Shard, AlnError> {
+        let path = path.as_ref();
+        let shard = crate::migrate::load_checked(path)?;
+        let sig_file = sig_path(path);
+        let sig = if sig_file.exists() { Some(ShardSignature::load(&sig_file)?) } else { None };
+        self.check(&shard, sig.as_ref())
+            .map_err(|e| AlnError::Signature(format!("{}: {}", path.display(), e)))?;
+        Ok(shard)
+    }
+}
+
+/// Parse `path` under the policy of its workspace.
+pub fn load_verified(path: impl AsRef<Path>) -> Result<Shard, AlnError> {
+    let path = path.as_ref();
+    SignaturePolicy::discover(path)?.load(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const SHARD: &str = "\
+destination-path,demo.v1.aln
+SECTION,DERIVED
+ROW,derived,session,scalar,rmax,0.35,float,range0,1,Psych-risk ceiling
+ROW,derived,session,scalar,emin,0.5,float,range0,1,Eligibility threshold
+ROW,rule,rule,condition,guard,\"ok = emin <= rmax\",string,readonly,
+FOOTER,END-OF-SHARD
+";
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    /// Fresh directory holding `shard.aln`, and a signing workspace trusting
+    /// `key(1)` as `alice` when `signed_workspace` is set.
+    fn scratch(name: &str, signed_workspace: bool) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("aln-canonical-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("shard.aln"), SHARD).unwrap();
+        if signed_workspace {
+            let workspace = format!(
+                "ROW,policy,signatures,flag,required,true,bool,nonwaivable,\n\
+                 ROW,signer,alice,scalar,pubkey,{},string,,\n",
+                hex::encode(key(1).verifying_key().to_bytes())
+            );
+            std::fs::write(dir.join(WORKSPACE_FILE), workspace).unwrap();
+        }
+        dir
+    }
+
+    fn sign(dir: &Path, signer: &str, key: &SigningKey) {
+        let path = dir.join("shard.aln");
+        let shard = crate::parser::parse_file(&path).unwrap();
+        ShardSignature::sign(&shard, signer, key).store(&sig_path(&path)).unwrap();
+    }
+
+    #[test]
+    fn hash_ignores_comments_layout_and_data_order() {
+        let reordered = "\
+# reordered, with comments
+destination-path,demo.v1.aln
+SECTION,DERIVED
+ROW,derived,session,scalar,emin,0.50,float,range0,1,Eligibility threshold
+
+ROW,derived,session,scalar, rmax ,0.35,float,range0,1,Psych-risk ceiling
+ROW,rule,rule,condition,guard,\"ok =   emin <= rmax\",string,readonly,
+FOOTER,END-OF-SHARD
+";
+        let a = parse(SHARD).unwrap();
+        assert_eq!(content_hash(&a), content_hash(&parse(reordered).unwrap()));
+        assert_eq!(canonicalize(&canonical_shard(&a)), canonicalize(&a));
+        let changed = SHARD.replace("0.35", "0.45");
+        assert_ne!(content_hash(&a), content_hash(&parse(&changed).unwrap()));
+    }
+
+    #[test]
+    fn signed_shard_loads() {
+        let dir = scratch("signed", true);
+        sign(&dir, "alice", &key(1));
+        assert!(load_verified(dir.join("shard.aln")).is_ok());
+    }
+
+    #[test]
+    fn missing_signature_is_refused_when_required() {
+        let dir = scratch("missing", true);
+        match load_verified(dir.join("shard.aln")) {
+            Err(AlnError::Signature(e)) => assert!(e.contains("unsigned shard"), "{}", e),
+            other => panic!("expected a signature error, got {:?}", other.map(|_| ())),
+        }
+        // Without a workspace, signatures are optional.
+        assert!(load_verified(scratch("optional", false).join("shard.aln")).is_ok());
+    }
+
+    #[test]
+    fn bad_signatures_are_refused() {
+        // Edited after signing.
+        let dir = scratch("tampered", true);
+        sign(&dir, "alice", &key(1));
+        std::fs::write(dir.join("shard.aln"), SHARD.replace("0.35", "0.9")).unwrap();
+        assert!(matches!(load_verified(dir.join("shard.aln")), Err(AlnError::Signature(e)) if e.contains("content changed")));
+
+        // Signed by someone the workspace does not trust, or with the wrong key.
+        let dir = scratch("untrusted", true);
+        sign(&dir, "mallory", &key(2));
+        assert!(matches!(load_verified(dir.join("shard.aln")), Err(AlnError::Signature(e)) if e.contains("not trusted")));
+        sign(&dir, "alice", &key(2));
+        assert!(matches!(load_verified(dir.join("shard.aln")), Err(AlnError::Signature(_))));
+
+        // A garbled .sig is refused even where signatures are optional.
+        let dir = scratch("garbled", false);
+        std::fs::write(sig_path(&dir.join("shard.aln")), "{ not json").unwrap();
+        assert!(matches!(load_verified(dir.join("shard.aln")), Err(AlnError::Signature(_))));
+    }
+
+    #[test]
+    fn outdated_shards_are_refused_before_the_signature_check() {
+        let dir = scratch("outdated", false);
+        let v1 = SHARD.replace("demo.v1.aln", "quantum-roaming-debug.v1.aln");
+        std::fs::write(dir.join("shard.aln"), v1).unwrap();
+        assert!(matches!(load_verified(dir.join("shard.aln")), Err(AlnError::Outdated { version: 1, .. })));
+    }
+}
@@ -0,0 +1,649 @@
+//! Rust source generation from a shard, for `build.rs`.
+//!
+//! Every `enum` row becomes a Rust enum, every other data row a typed field
+//! on `<Entity>State`, constants become `const`s, and the rule rows compile
+//! to a plain `apply_rules` function with the same semantics as
+//! [`RuleSet::evaluate`](crate::expr::RuleSet::evaluate).
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Write as _;
+use std::path::Path;
+
+use crate::ast::{DataType, Row, RowKind, Shard};
+use crate::error::{AlnError, ParseError};
+use crate::expr::{BinOp, Expr, RuleSet};
+use crate::validate::validate_shard;
+
+const KEYWORDS: &[&str] = &[
+    "as", "async", "await", "break", "const", "continue", "crate", "dyn", "else", "enum", "extern", "false", "fn",
+    "for", "if", "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return", "static",
+    "struct", "super", "trait", "true", "type", "unsafe", "use", "where", "while",
+];
+
+/// `player_state` -> `PlayerState`, `REM` -> `Rem`.
+fn pascal_case(s: &str) -> String {
+    let mut out = String::new();
+    for part in s.split(|c: char| !c.is_alphanumeric()).filter(|p| !p.is_empty()) {
+        let shouting = !part.chars().any(|c| c.is_lowercase());
+        let mut chars = part.chars();
+        if let Some(first) = chars.next() {
+            out.extend(first.to_uppercase());
+            if shouting {
+                out.extend(chars.flat_map(char::to_lowercase));
+            } else {
+                out.extend(chars);
+            }
+        }
+    }
+    out
+}
+
+/// `eligibilityE` -> `eligibility_e`.
+fn snake_case(s: &str) -> String {
+    let mut out = String::new();
+    let mut prev_lower = false;
+    for c in s.chars() {
+        if !c.is_alphanumeric() {
+            if !out.ends_with('_') {
+                out.push('_');
+            }
+            prev_lower = false;
+            continue;
+        }
+        if c.is_uppercase() && prev_lower {
+            out.push('_');
+        }
+        prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+fn ident(s: &str) -> String {
+    if KEYWORDS.contains(&s) {
+        format!("r#{}", s)
+    } else {
+        s.to_string()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Ty {
+    Float,
+    Int,
+    Bool,
+    Str,
+    /// Index into `Model::enums`.
+    Enum(usize),
+}
+
+#[derive(Debug, Clone)]
+struct EnumDef {
+    name: String,
+    /// (shard value, variant)
+    variants: Vec<(String, String)>,
+    default: usize,
+}
+
+impl EnumDef {
+    fn variant(&self, value: &str) -> Option<String> {
+        self.variants
+            .iter()
+            .find(|(v, _)| v == value)
+            .map(|(_, variant)| format!("{}::{}", self.name, variant))
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Field {
+    entity: String,
+    name: String,
+    ty: Ty,
+    /// Rust expression for the declared value.
+    default: String,
+    /// Whether `default` equals `Default::default()` for the type.
+    zero: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Const {
+    name: String,
+    ty: Ty,
+    value: String,
+}
+
+/// How a compiled expression of string type is held.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Held {
+    /// A `&'static str` literal or const.
+    Literal,
+    /// A `String` field, borrowed.
+    Place,
+    /// A fresh `String`.
+    Owned,
+    /// Not a string.
+    Value,
+}
+
+#[derive(Debug, Clone)]
+struct Code {
+    text: String,
+    ty: Ty,
+    held: Held,
+    /// Safe to embed without parentheses.
+    atomic: bool,
+    /// `format!` arguments when this is a string concatenation.
+    concat: Vec<String>,
+}
+
+impl Code {
+    fn atom(text: String, ty: Ty, held: Held) -> Self {
+        Self {
+            text,
+            ty,
+            held,
+            atomic: true,
+            concat: Vec::new(),
+        }
+    }
+
+    fn concat_args(self) -> Vec<String> {
+        if self.concat.is_empty() {
+            vec![self.text]
+        } else {
+            self.concat
+        }
+    }
+
+    fn wrapped(&self) -> String {
+        if self.atomic {
+            self.text.clone()
+        } else {
+            format!("({})", self.text)
+        }
+    }
+
+    /// As an owned value, for assignments.
+    fn owned(&self) -> String {
+        match self.held {
+            Held::Literal => format!("String::from({})", self.text),
+            Held::Place => format!("{}.clone()", self.text),
+            Held::Owned | Held::Value => self.text.clone(),
+        }
+    }
+}
+
+struct Model<'a> {
+    enums: Vec<EnumDef>,
+    fields: BTreeMap<String, Field>,
+    consts: BTreeMap<String, Const>,
+    /// Entities in shard order, with their fields' keys in shard order.
+    entities: Vec<(String, Vec<String>)>,
+    shard: &'a Shard,
+    /// Rule being compiled, for runtime error messages.
+    rule: String,
+    uses_div: bool,
+}
+
+impl Model<'_> {
+    fn rust_type(&self, ty: &Ty, in_const: bool) -> String {
+        match ty {
+            Ty::Float => "f64".into(),
+            Ty::Int => "i64".into(),
+            Ty::Bool => "bool".into(),
+            Ty::Str if in_const => "&str".into(),
+            Ty::Str => "String".into(),
+            Ty::Enum(i) => self.enums[*i].name.clone(),
+        }
+    }
+
+    fn ident_code(&self, name: &str, expected: Option<&Ty>) -> Result<Code, String> {
+        if let Some(f) = self.fields.get(name) {
+            let path = format!("state.{}.{}", ident(&snake_case(&f.entity)), f.name);
+            return Ok(match f.ty {
+                Ty::Int => Code {
+                    atomic: false,
+                    ..Code::atom(format!("{} as f64", path), Ty::Float, Held::Value)
+                },
+                Ty::Str => Code::atom(path, Ty::Str, Held::Place),
+                _ => Code::atom(path, f.ty.clone(), Held::Value),
+            });
+        }
+        if let Some(c) = self.consts.get(name) {
+            return Ok(match c.ty {
+                Ty::Int => Code {
+                    atomic: false,
+                    ..Code::atom(format!("{} as f64", c.name), Ty::Float, Held::Value)
+                },
+                Ty::Str => Code::atom(c.name.clone(), Ty::Str, Held::Literal),
+                _ => Code::atom(c.name.clone(), c.ty.clone(), Held::Value),
+            });
+        }
+        match expected {
+            Some(Ty::Enum(i)) => self.enums[*i]
+                .variant(name)
+                .map(|v| Code::atom(v, Ty::Enum(*i), Held::Value))
+                .ok_or_else(|| format!("`{}` is not a value of {}", name, self.enums[*i].name)),
+            _ => Err(format!("unknown field `{}`", name)),
+        }
+    }
+
+    fn expr(&mut self, e: &Expr, expected: Option<&Ty>) -> Result<Code, String> {
+        Ok(match e {
+            Expr::Number(n) => Code::atom(format!("{:?}", n), Ty::Float, Held::Value),
+            Expr::Bool(b) => Code::atom(b.to_string(), Ty::Bool, Held::Value),
+            Expr::Str(s) => match expected {
+                Some(Ty::Enum(i)) => match self.enums[*i].variant(s) {
+                    Some(v) => Code::atom(v, Ty::Enum(*i), Held::Value),
+                    None => Code::atom(format!("{:?}", s), Ty::Str, Held::Literal),
+                },
+                _ => Code::atom(format!("{:?}", s), Ty::Str, Held::Literal),
+            },
+            Expr::Ident(name) => self.ident_code(name, expected)?,
+            Expr::Not(inner) => {
+                let c = self.expr(inner, Some(&Ty::Bool))?;
+                Code::atom(format!("!{}", c.wrapped()), Ty::Bool, Held::Value)
+            }
+            Expr::Neg(inner) => {
+                let c = self.expr(inner, Some(&Ty::Float))?;
+                Code::atom(format!("-{}", c.wrapped()), Ty::Float, Held::Value)
+            }
+            Expr::In(lhs, items) => {
+                let l = self.expr(lhs, None)?;
+                let items = items
+                    .iter()
+                    .map(|i| self.expr(i, Some(&l.ty)))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let all_variants = matches!(l.ty, Ty::Enum(_))
+                    && items.iter().all(|i| i.text.contains("::") && !i.text.starts_with("state."));
+                if all_variants {
+                    let pattern = items.iter().map(|i| i.text.as_str()).collect::<Vec<_>>().join(" | ");
+                    Code::atom(format!("matches!({}, {})", l.text, pattern), Ty::Bool, Held::Value)
+                } else {
+                    let tests = items
+                        .iter()
+                        .map(|i| format!("{} == {}", l.wrapped(), i.wrapped()))
+                        .collect::<Vec<_>>();
+                    Code {
+                        atomic: false,
+                        ..Code::atom(tests.join(" || "), Ty::Bool, Held::Value)
+                    }
+                }
+            }
+            Expr::Binary(op, l, r) => self.binary(*op, l, r, e)?,
+        })
+    }
+
+    fn binary(&mut self, op: BinOp, l: &Expr, r: &Expr, whole: &Expr) -> Result<Code, String> {
+        let code = |text: String, ty: Ty| Code {
+            atomic: false,
+            ..Code::atom(text, ty, Held::Value)
+        };
+        match op {
+            BinOp::And | BinOp::Or => {
+                let (lc, rc) = (self.expr(l, Some(&Ty::Bool))?, self.expr(r, Some(&Ty::Bool))?);
+                let sym = if op == BinOp::And { "&&" } else { "||" };
+                Ok(code(format!("{} {} {}", lc.wrapped(), sym, rc.wrapped()), Ty::Bool))
+            }
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                let (lc, rc) = match self.expr(l, None) {
+                    Ok(lc) => {
+                        let rc = self.expr(r, Some(&lc.ty))?;
+                        (lc, rc)
+                    }
+                    Err(_) => {
+                        let rc = self.expr(r, None)?;
+                        (self.expr(l, Some(&rc.ty))?, rc)
+                    }
+                };
+                // An enum compared against a literal that names one of its values.
+                let rc = match (&lc.ty, &rc.ty, r) {
+                    (Ty::Enum(_), Ty::Str, Expr::Str(_)) => self.expr(r, Some(&lc.ty))?,
+                    _ => rc,
+                };
+                let sym = match op {
+                    BinOp::Eq => "==",
+                    BinOp::Ne => "!=",
+                    other => other.as_str(),
+                };
+                Ok(code(format!("{} {} {}", lc.wrapped(), sym, rc.wrapped()), Ty::Bool))
+            }
+            BinOp::Add => {
+                let (lc, rc) = (self.expr(l, None)?, self.expr(r, None)?);
+                if lc.ty == Ty::Float && rc.ty == Ty::Float {
+                    return Ok(code(format!("{} + {}", lc.wrapped(), rc.wrapped()), Ty::Float));
+                }
+                let mut args = lc.concat_args();
+                args.extend(rc.concat_args());
+                let text = format!("format!(\"{}\", {})", "{}".repeat(args.len()), args.join(", "));
+                Ok(Code {
+                    concat: args,
+                    ..Code::atom(text, Ty::Str, Held::Owned)
+                })
+            }
+            BinOp::Sub | BinOp::Mul => {
+                let (lc, rc) = (self.expr(l, Some(&Ty::Float))?, self.expr(r, Some(&Ty::Float))?);
+                Ok(code(format!("{} {} {}", lc.wrapped(), op.as_str(), rc.wrapped()), Ty::Float))
+            }
+            BinOp::Div => {
+                let (lc, rc) = (self.expr(l, Some(&Ty::Float))?, self.expr(r, Some(&Ty::Float))?);
+                self.uses_div = true;
+                let context = format!("{}: division by zero in `{}`", self.rule, whole);
+                Ok(Code::atom(
+                    format!("div({}, {}, {:?})?", lc.text, rc.text, context),
+                    Ty::Float,
+                    Held::Value,
+                ))
+            }
+        }
+    }
+}
+
+/// Generator settings. Type names default to the PascalCase of the row key.
+#[derive(Debug, Clone, Default)]
+pub struct Codegen {
+    type_names: BTreeMap<String, String>,
+    constant_entities: BTreeSet<String>,
+}
+
+impl Codegen {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Name the enum generated for `key`, e.g. `sleepstage` -> `SleepStage`.
+    pub fn type_name(mut self, key: &str, name: &str) -> Self {
+        self.type_names.insert(key.to_string(), name.to_string());
+        self
+    }
+
+    /// Rows of `entity` that no rule assigns become `const`s (thresholds).
+    /// `immutable` and `nonwaivable` rows are always constants.
+    pub fn constants_from(mut self, entity: &str) -> Self {
+        self.constant_entities.insert(entity.to_string());
+        self
+    }
+
+    fn model<'a>(&self, shard: &'a Shard, rules: &RuleSet) -> Result<Model<'a>, Vec<ParseError>> {
+        let assigned: BTreeSet<&str> = rules
+            .rules
+            .iter()
+            .flat_map(|r| r.rule.assignments())
+            .map(|a| a.target.as_str())
+            .collect();
+        let mut model = Model {
+            enums: Vec::new(),
+            fields: BTreeMap::new(),
+            consts: BTreeMap::new(),
+            entities: Vec::new(),
+            shard,
+            rule: String::new(),
+            uses_div: false,
+        };
+        let mut errors = Vec::new();
+        let mut enum_names = BTreeSet::new();
+        for (_, row) in shard.rows().filter(|(_, r)| !r.kind.is_rule()) {
+            let err = |msg: String| ParseError::new(row.spans.key.line, row.spans.key.column, msg);
+            let ty = match (&row.kind, &row.datatype) {
+                (RowKind::Enum, _) => {
+                    let name = self.type_names.get(&row.key).cloned().unwrap_or_else(|| pascal_case(&row.key));
+                    if !enum_names.insert(name.clone()) {
+                        errors.push(err(format!("enum name `{}` is used twice", name)));
+                        continue;
+                    }
+                    let variants: Vec<(String, String)> =
+                        row.constraints.iter().map(|v| (v.clone(), pascal_case(v))).collect();
+                    let mut seen = BTreeSet::new();
+                    if let Some((v, _)) = variants.iter().find(|(_, var)| var.is_empty() || !seen.insert(var.clone())) {
+                        errors.push(err(format!("value `{}` does not map to a distinct Rust variant", v)));
+                        continue;
+                    }
+                    let Some(default) = variants.iter().position(|(v, _)| *v == row.value) else {
+                        errors.push(err(format!("default `{}` is not one of the enum values", row.value)));
+                        continue;
+                    };
+                    model.enums.push(EnumDef { name, variants, default });
+                    Ty::Enum(model.enums.len() - 1)
+                }
+                (_, Some(DataType::Float)) => Ty::Float,
+                (_, Some(DataType::Int)) => Ty::Int,
+                (_, Some(DataType::Bool)) => Ty::Bool,
+                _ => Ty::Str,
+            };
+            let (default, zero) = literal(&model, row, &ty);
+            let frozen = row.has_constraint("immutable") || row.has_constraint("nonwaivable");
+            let constant = !assigned.contains(row.key.as_str())
+                && (frozen || self.constant_entities.contains(&row.entity_type));
+            if constant {
+                let value = match ty {
+                    Ty::Str => format!("{:?}", row.value),
+                    _ => default,
+                };
+                let name = snake_case(&row.key).to_uppercase();
+                model.consts.insert(row.key.clone(), Const { name, ty, value });
+                continue;
+            }
+            let name = snake_case(&row.key);
+            if name.is_empty() || name.starts_with(|c: char| c.is_ascii_digit()) {
+                errors.push(err(format!("`{}` is not usable as a Rust field name", row.key)));
+                continue;
+            }
+            match model.entities.iter_mut().find(|(e, _)| *e == row.entity_type) {
+                Some((_, keys)) => keys.push(row.key.clone()),
+                None => model.entities.push((row.entity_type.clone(), vec![row.key.clone()])),
+            }
+            model.fields.insert(
+                row.key.clone(),
+                Field {
+                    entity: row.entity_type.clone(),
+                    name: ident(&name),
+                    ty,
+                    default,
+                    zero,
+                },
+            );
+        }
+        if errors.is_empty() {
+            Ok(model)
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Rust source for `shard`. Shards that break their own constraints or
+    /// whose rules do not type-check are refused with every error found.
+    pub fn generate(&self, shard: &Shard) -> Result<String, Vec<ParseError>> {
+        let violations: Vec<ParseError> = validate_shard(shard)
+            .into_iter()
+            .map(|v| {
+                let span = v.span.unwrap_or_default();
+                ParseError::new(span.line, span.column, v.to_string())
+            })
+            .collect();
+        if !violations.is_empty() {
+            return Err(violations);
+        }
+        let rules = RuleSet::from_shard(shard)?;
+        let mut model = self.model(shard, &rules)?;
+
+        let mut body = String::new();
+        let mut errors = Vec::new();
+        for rule in &rules.rules {
+            let err = |m: String| ParseError::new(rule.span.line, rule.span.column, format!("{}: {}", rule.name, m));
+            match compile_rule(&mut model, rule) {
+                Ok(code) => body.push_str(&code),
+                Err(m) => errors.push(err(m)),
+            }
+        }
+        if !errors.is_empty() {
+            return Err(errors);
+        }
+        Ok(render(&model, &body, rules.rules.is_empty()))
+    }
+
+    /// For `build.rs`: generate from `shard_path` into `$OUT_DIR/<out_name>`
+    /// and ask Cargo to rerun when the shard changes.
+    pub fn build(&self, shard_path: impl AsRef<Path>, out_name: &str) -> Result<(), AlnError> {
+        let shard_path = shard_path.as_ref();
+        println!("cargo:rerun-if-changed={}", shard_path.display());
+        let shard = crate::migrate::load_checked(shard_path)?;
+        let source = self.generate(&shard).map_err(|errors| {
+            let name = shard_path.display().to_string();
+            AlnError::Invalid(errors.into_iter().map(|e| e.with_source(&name)).collect())
+        })?;
+        let out_dir = std::env::var_os("OUT_DIR")
+            .ok_or_else(|| AlnError::Io(std::io::Error::other("OUT_DIR is not set; call from build.rs")))?;
+        std::fs::write(Path::new(&out_dir).join(out_name), source).map_err(AlnError::Io)
+    }
+}
+
+/// Rust literal for a row's declared value, and whether it is the type's zero.
+fn literal(model: &Model<'_>, row: &Row, ty: &Ty) -> (String, bool) {
+    let raw = row.value.trim();
+    match ty {
+        Ty::Float => {
+            let n: f64 = raw.parse().unwrap_or(0.0);
+            (format!("{:?}", n), n == 0.0)
+        }
+        Ty::Int => {
+            let n: i64 = raw.parse().unwrap_or(0);
+            (n.to_string(), n == 0)
+        }
+        Ty::Bool => (row.bool_value().unwrap_or(false).to_string(), row.bool_value() != Some(true)),
+        Ty::Str if row.value.is_empty() => ("String::new()".into(), true),
+        Ty::Str => (format!("String::from({:?})", row.value), false),
+        Ty::Enum(i) => {
+            let e = &model.enums[*i];
+            (format!("{}::{}", e.name, e.variants[e.default].1), true)
+        }
+    }
+}
+
+fn compile_rule(model: &mut Model<'_>, rule: &crate::expr::CompiledRule) -> Result<String, String> {
+    model.rule = rule.name.clone();
+    let mut out = String::new();
+    let _ = writeln!(out, "    // {}: {}", rule.name, rule.source);
+    let indent = match &rule.rule {
+        crate::expr::Rule::Conditional { condition, .. } => {
+            let c = model.expr(condition, Some(&Ty::Bool))?;
+            let _ = writeln!(out, "    if {} {{", c.text);
+            "        "
+        }
+        crate::expr::Rule::Assign(_) => "    ",
+    };
+    for a in rule.rule.assignments() {
+        let field = model
+            .fields
+            .get(&a.target)
+            .cloned()
+            .ok_or_else(|| format!("`{}` is a constant and cannot be assigned", a.target))?;
+        let c = model.expr(&a.value, Some(&field.ty))?;
+        let value = match field.ty {
+            Ty::Int => format!("{} as i64", c.wrapped()),
+            Ty::Str => c.owned(),
+            _ => c.text,
+        };
+        let _ = writeln!(
+            out,
+            "{}state.{}.{} = {};",
+            indent,
+            ident(&snake_case(&field.entity)),
+            field.name,
+            value
+        );
+    }
+    let _ = writeln!(out, "{}fired.push({:?});", indent, rule.name);
+    if indent.len() > 4 {
+        out.push_str("    }\n");
+    }
+    Ok(out)
+}
+
+fn render(model: &Model<'_>, rules: &str, no_rules: bool) -> String {
+    let mut out = String::new();
+    let source = model.shard.destination_path().unwrap_or("<unnamed shard>");
+    let _ = writeln!(out, "// @generated by aln_core::codegen from {}. Do not edit.\n", source);
+    let _ = writeln!(out, "/// Destination path of the shard this module was generated from.");
+    let _ = writeln!(out, "pub const SHARD: &str = {:?};\n", source);
+
+    for c in model.consts.values() {
+        let _ = writeln!(out, "pub const {}: {} = {};", c.name, model.rust_type(&c.ty, true), c.value);
+    }
+    if !model.consts.is_empty() {
+        out.push('\n');
+    }
+
+    for e in &model.enums {
+        let _ = writeln!(out, "#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]");
+        let _ = writeln!(out, "pub enum {} {{", e.name);
+        for (i, (_, variant)) in e.variants.iter().enumerate() {
+            if i == e.default {
+                let _ = writeln!(out, "    #[default]");
+            }
+            let _ = writeln!(out, "    {},", variant);
+        }
+        let _ = writeln!(out, "}}\n");
+        let _ = writeln!(out, "impl {} {{", e.name);
+        let all = e.variants.iter().map(|(_, v)| format!("{}::{}", e.name, v)).collect::<Vec<_>>();
+        let _ = writeln!(out, "    pub const ALL: [{}; {}] = [{}];\n", e.name, all.len(), all.join(", "));
+        let _ = writeln!(out, "    /// The value as written in the shard.");
+        let _ = writeln!(out, "    pub fn as_str(self) -> &'static str {{");
+        let _ = writeln!(out, "        match self {{");
+        for (value, variant) in &e.variants {
+            let _ = writeln!(out, "            {}::{} => {:?},", e.name, variant, value);
+        }
+        let _ = writeln!(out, "        }}\n    }}\n");
+        let _ = writeln!(out, "    pub fn parse(s: &str) -> Option<Self> {{");
+        let _ = writeln!(out, "        Self::ALL.into_iter().find(|v| v.as_str() == s)");
+        let _ = writeln!(out, "    }}\n}}\n");
+        let _ = writeln!(out, "impl std::fmt::Display for {} {{", e.name);
+        let _ = writeln!(out, "    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {{");
+        let _ = writeln!(out, "        f.write_str(self.as_str())\n    }}\n}}\n");
+    }
+
+    for (entity, keys) in &model.entities {
+        let name = format!("{}State", pascal_case(entity));
+        let fields: Vec<&Field> = keys.iter().map(|k| &model.fields[k]).collect();
+        let all_zero = fields.iter().all(|f| f.zero);
+        let derives = if all_zero { "Debug, Clone, PartialEq, Default" } else { "Debug, Clone, PartialEq" };
+        let _ = writeln!(out, "#[derive({})]", derives);
+        let _ = writeln!(out, "pub struct {} {{", name);
+        for f in &fields {
+            let _ = writeln!(out, "    pub {}: {},", f.name, model.rust_type(&f.ty, false));
+        }
+        let _ = writeln!(out, "}}\n");
+        if !all_zero {
+            let _ = writeln!(out, "impl Default for {} {{", name);
+            let _ = writeln!(out, "    fn default() -> Self {{\n        Self {{");
+            for f in &fields {
+                let _ = writeln!(out, "            {}: {},", f.name, f.default);
+            }
+            let _ = writeln!(out, "        }}\n    }}\n}}\n");
+        }
+    }
+
+    let _ = writeln!(out, "/// Every stateful field of the shard, grouped by entity type.");
+    let _ = writeln!(out, "#[derive(Debug, Clone, PartialEq, Default)]");
+    let _ = writeln!(out, "pub struct ShardState {{");
+    for (entity, _) in &model.entities {
+        let _ = writeln!(out, "    pub {}: {}State,", ident(&snake_case(entity)), pascal_case(entity));
+    }
+    let _ = writeln!(out, "}}\n");
+
+    if model.uses_div {
+        let _ = writeln!(out, "fn div(a: f64, b: f64, context: &str) -> Result<f64, String> {{");
+        let _ = writeln!(out, "    if b == 0.0 {{ Err(context.to_string()) }} else {{ Ok(a / b) }}\n}}\n");
+    }
+
+    let _ = writeln!(out, "/// The shard's rule rows, in order. Returns the names of the rules that fired.");
+    let _ = writeln!(out, "pub fn apply_rules(state: &mut ShardState) -> Result<Vec<&'static str>, String> {{");
+    if no_rules {
+        let _ = writeln!(out, "    let _ = state;\n    Ok(Vec::new())");
+    } else {
+        let _ = writeln!(out, "    let mut fired = Vec::new();");
+        out.push_str(rules);
+        let _ = writeln!(out, "    Ok(fired)");
+    }
+    let _ = writeln!(out, "}}");
+    out
+}
@@ -0,0 +1,502 @@
+//! Semantic diff and three-way merge of shards. Rows are matched by
+//! (section, entity type, key), not by line, and rule rows compare by their
+//! parsed rule so re-spacing an expression is not a change.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::ast::{ItemKind, Row, Shard};
+use crate::expr::parse_rule;
+use crate::writer::{write_item, write_row};
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RowId {
+    pub section: Option<String>,
+    pub entity_type: String,
+    pub key: String,
+}
+
+impl RowId {
+    pub fn of(section: Option<&str>, row: &Row) -> Self {
+        Self {
+            section: section.map(str::to_string),
+            entity_type: row.entity_type.clone(),
+            key: row.key.clone(),
+        }
+    }
+}
+
+impl fmt::Display for RowId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}/{}", self.section.as_deref().unwrap_or("-"), self.entity_type, self.key)
+    }
+}
+
+/// One column that differs between two versions of a row.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldChange {
+    /// `field`, `kind`, `value`, `rule`, `datatype`, `constraints` or `notes`.
+    pub column: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+fn columns(row: &Row) -> [(&'static str, String); 6] {
+    let value_column = if row.kind.is_rule() { "rule" } else { "value" };
+    [
+        ("field", row.field.clone()),
+        ("kind", row.kind.as_str().to_string()),
+        (value_column, row.value.clone()),
+        ("datatype", row.datatype.as_ref().map(|d| d.as_str().to_string()).unwrap_or_default()),
+        ("constraints", row.constraints.join(",")),
+        ("notes", row.notes.clone()),
+    ]
+}
+
+/// Rule text compares by parsed rule, so whitespace and keyword case do not count.
+fn same_value(old: &Row, new: &Row) -> bool {
+    if old.value == new.value {
+        return true;
+    }
+    if !(old.kind.is_rule() && new.kind.is_rule()) {
+        return false;
+    }
+    matches!((parse_rule(&old.value), parse_rule(&new.value)), (Ok(a), Ok(b)) if a == b)
+}
+
+pub fn row_changes(old: &Row, new: &Row) -> Vec<FieldChange> {
+    columns(old)
+        .into_iter()
+        .zip(columns(new))
+        .filter(|((column, a), (_, b))| {
+            if *column == "rule" || *column == "value" {
+                !same_value(old, new)
+            } else {
+                a != b
+            }
+        })
+        .map(|((column, old), (_, new))| FieldChange { column, old, new })
+        .collect()
+}
+
+/// Same content, ignoring where it was written.
+pub fn same_row(a: &Row, b: &Row) -> bool {
+    row_changes(a, b).is_empty()
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    DestinationPath { old: Option<String>, new: Option<String> },
+    Added { id: RowId, row: Box<Row> },
+    Removed { id: RowId, row: Box<Row> },
+    Modified { id: RowId, changes: Vec<FieldChange> },
+    /// Same entity and key under a different section.
+    Moved { from: RowId, to: RowId, changes: Vec<FieldChange> },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = |f: &mut fmt::Formatter<'_>, changes: &[FieldChange]| -> fmt::Result {
+            for c in changes {
+                write!(f, "\n    {}: {} -> {}", c.column, c.old, c.new)?;
+            }
+            Ok(())
+        };
+        match self {
+            Change::DestinationPath { old, new } => write!(
+                f,
+                "~ destination-path: {} -> {}",
+                old.as_deref().unwrap_or("-"),
+                new.as_deref().unwrap_or("-")
+            ),
+            Change::Added { id, row } => write!(f, "+ {} = {}", id, row.value),
+            Change::Removed { id, row } => write!(f, "- {} = {}", id, row.value),
+            Change::Modified { id, changes } => {
+                write!(f, "~ {}", id)?;
+                fields(f, changes)
+            }
+            Change::Moved { from, to, changes } => {
+                write!(f, "> {} moved to {}", from, to)?;
+                fields(f, changes)
+            }
+        }
+    }
+}
+
+/// Rows by id, in shard order. A repeated id keeps its first row.
+fn index(shard: &Shard) -> Vec<(RowId, &Row)> {
+    let mut seen = std::collections::BTreeSet::new();
+    shard
+        .rows()
+        .map(|(section, row)| (RowId::of(section, row), row))
+        .filter(|(id, _)| seen.insert(id.clone()))
+        .collect()
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ShardDiff {
+    pub changes: Vec<Change>,
+}
+
+impl ShardDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for ShardDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in &self.changes {
+            writeln!(f, "{}", c)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn diff(old: &Shard, new: &Shard) -> ShardDiff {
+    let mut changes = Vec::new();
+    if old.destination_path() != new.destination_path() {
+        changes.push(Change::DestinationPath {
+            old: old.destination_path().map(str::to_string),
+            new: new.destination_path().map(str::to_string),
+        });
+    }
+    let (old_rows, new_rows) = (index(old), index(new));
+    let old_map: BTreeMap<&RowId, &Row> = old_rows.iter().map(|(id, r)| (id, *r)).collect();
+    let new_map: BTreeMap<&RowId, &Row> = new_rows.iter().map(|(id, r)| (id, *r)).collect();
+
+    let mut removed: Vec<(&RowId, &Row)> = Vec::new();
+    for (id, row) in &old_rows {
+        match new_map.get(id) {
+            Some(new_row) => {
+                let fields = row_changes(row, new_row);
+                if !fields.is_empty() {
+                    changes.push(Change::Modified {
+                        id: id.clone(),
+                        changes: fields,
+                    });
+                }
+            }
+            None => removed.push((id, row)),
+        }
+    }
+    for (id, row) in new_rows.iter().filter(|(id, _)| !old_map.contains_key(id)) {
+        let moved_from = removed
+            .iter()
+            .position(|(old_id, _)| old_id.entity_type == id.entity_type && old_id.key == id.key);
+        match moved_from {
+            Some(i) => {
+                let (from, old_row) = removed.remove(i);
+                changes.push(Change::Moved {
+                    from: from.clone(),
+                    to: id.clone(),
+                    changes: row_changes(old_row, row),
+                });
+            }
+            None => changes.push(Change::Added {
+                id: id.clone(),
+                row: Box::new((*row).clone()),
+            }),
+        }
+    }
+    for (id, row) in removed {
+        changes.push(Change::Removed {
+            id: id.clone(),
+            row: Box::new(row.clone()),
+        });
+    }
+    ShardDiff { changes }
+}
+
+// ---------------------------------------------------------------- merge
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Conflict {
+    pub id: RowId,
+    /// `None` where that side deleted the row.
+    pub ours: Option<Row>,
+    pub theirs: Option<Row>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Resolved {
+    Row(Box<Row>),
+    Deleted,
+    Conflict(Box<Conflict>),
+}
+
+/// Per-column three-way merge of one row.
+fn merge_row(id: &RowId, base: Option<&Row>, ours: Option<&Row>, theirs: Option<&Row>) -> Resolved {
+    let same = |a: Option<&Row>, b: Option<&Row>| match (a, b) {
+        (Some(a), Some(b)) => same_row(a, b),
+        (None, None) => true,
+        _ => false,
+    };
+    let pick = |r: Option<&Row>| r.map_or(Resolved::Deleted, |r| Resolved::Row(Box::new(r.clone())));
+    if same(ours, theirs) {
+        return pick(ours);
+    }
+    if same(base, ours) {
+        return pick(theirs);
+    }
+    if same(base, theirs) {
+        return pick(ours);
+    }
+    let conflict = || {
+        Resolved::Conflict(Box::new(Conflict {
+            id: id.clone(),
+            ours: ours.cloned(),
+            theirs: theirs.cloned(),
+        }))
+    };
+    let (Some(base), Some(o), Some(t)) = (base, ours, theirs) else {
+        return conflict();
+    };
+    // Both sides edited: fine as long as they touched different columns.
+    let (ours_changed, theirs_changed) = (row_changes(base, o), row_changes(base, t));
+    if ours_changed.iter().any(|c| theirs_changed.iter().any(|d| d.column == c.column)) {
+        return conflict();
+    }
+    let mut merged = o.clone();
+    for c in &theirs_changed {
+        match c.column {
+            "field" => merged.field = t.field.clone(),
+            "kind" => merged.kind = t.kind.clone(),
+            "value" | "rule" => merged.value = t.value.clone(),
+            "datatype" => merged.datatype = t.datatype.clone(),
+            "constraints" => merged.constraints = t.constraints.clone(),
+            _ => merged.notes = t.notes.clone(),
+        }
+    }
+    Resolved::Row(Box::new(merged))
+}
+
+enum Out {
+    Item(ItemKind),
+    Row(RowId, Box<Row>),
+    Conflict(Box<Conflict>),
+}
+
+impl Out {
+    fn id(&self) -> Option<&RowId> {
+        match self {
+            Out::Row(id, _) => Some(id),
+            Out::Conflict(c) => Some(&c.id),
+            Out::Item(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MergeResult {
+    /// Merged shard text; conflicting rows are wrapped in
+    /// `<<<<<<< ours` / `=======` / `>>>>>>> theirs` markers.
+    pub text: String,
+    pub conflicts: Vec<Conflict>,
+}
+
+impl MergeResult {
+    pub fn is_clean(&self) -> bool {
+        self.conflicts.is_empty()
+    }
+}
+
+/// Three-way merge. Layout and comments follow `ours`; rows only `theirs`
+/// added go after their predecessor in `theirs`, or open their section.
+pub fn merge(base: &Shard, ours: &Shard, theirs: &Shard) -> MergeResult {
+    let base_map: BTreeMap<RowId, &Row> = index(base).into_iter().collect();
+    let ours_map: BTreeMap<RowId, &Row> = index(ours).into_iter().collect();
+    let theirs_rows = index(theirs);
+    let theirs_map: BTreeMap<&RowId, &Row> = theirs_rows.iter().map(|(id, r)| (id, *r)).collect();
+
+    let dest = match (base.destination_path(), ours.destination_path(), theirs.destination_path()) {
+        (b, o, t) if b == o && t.is_some() => t,
+        (_, o, _) => o,
+    };
+
+    let mut out = Vec::new();
+    let mut section: Option<&str> = None;
+    for item in &ours.items {
+        match &item.kind {
+            ItemKind::Row(row) => {
+                let id = RowId::of(section, row);
+                if out.iter().any(|o: &Out| o.id() == Some(&id)) {
+                    continue;
+                }
+                let resolved = merge_row(&id, base_map.get(&id).copied(), Some(row), theirs_map.get(&id).copied());
+                match resolved {
+                    Resolved::Row(r) => out.push(Out::Row(id, r)),
+                    Resolved::Deleted => {}
+                    Resolved::Conflict(c) => out.push(Out::Conflict(c)),
+                }
+            }
+            ItemKind::Section(s) => {
+                section = Some(s);
+                out.push(Out::Item(item.kind.clone()));
+            }
+            ItemKind::DestinationPath(_) => {
+                out.push(Out::Item(ItemKind::DestinationPath(dest.unwrap_or_default().to_string())));
+            }
+            other => out.push(Out::Item(other.clone())),
+        }
+    }
+
+    for (pos, (id, row)) in theirs_rows.iter().enumerate() {
+        if ours_map.contains_key(id) {
+            continue;
+        }
+        let placed = match merge_row(id, base_map.get(id).copied(), None, Some(row)) {
+            Resolved::Row(r) => Out::Row(id.clone(), r),
+            Resolved::Conflict(c) => Out::Conflict(c),
+            Resolved::Deleted => continue,
+        };
+        let after_prev = theirs_rows[..pos]
+            .iter()
+            .rev()
+            .filter(|(prev, _)| prev.section == id.section)
+            .find_map(|(prev, _)| out.iter().position(|o| o.id() == Some(prev)))
+            .map(|i| i + 1);
+        let after_section = || {
+            out.iter()
+                .position(|o| matches!(o, Out::Item(ItemKind::Section(s)) if Some(s.as_str()) == id.section.as_deref()))
+                .map(|i| i + 1)
+        };
+        match after_prev.or_else(after_section) {
+            Some(i) => out.insert(i, placed),
+            None => {
+                let at = out
+                    .iter()
+                    .position(|o| matches!(o, Out::Item(ItemKind::Footer(_))))
+                    .unwrap_or(out.len());
+                let mut block = vec![Out::Item(ItemKind::Blank)];
+                if let Some(s) = &id.section {
+                    block.push(Out::Item(ItemKind::Section(s.clone())));
+                }
+                block.push(placed);
+                out.splice(at..at, block);
+            }
+        }
+    }
+
+    let mut text = String::new();
+    let mut conflicts = Vec::new();
+    for o in out {
+        match o {
+            Out::Item(kind) => text.push_str(&write_item(&kind)),
+            Out::Row(_, row) => text.push_str(&write_row(&row)),
+            Out::Conflict(c) => {
+                text.push_str("<<<<<<< ours\n");
+                if let Some(r) = &c.ours {
+                    text.push_str(&write_row(r));
+                    text.push('\n');
+                }
+                text.push_str("=======\n");
+                if let Some(r) = &c.theirs {
+                    text.push_str(&write_row(r));
+                    text.push('\n');
+                }
+                text.push_str(">>>>>>> theirs");
+                conflicts.push(*c);
+            }
+        }
+        text.push('\n');
+    }
+    MergeResult { text, conflicts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const BASE: &str = "\
+destination-path,demo.v1.aln
+SECTION,DERIVED
+ROW,derived,session,scalar,emin,0.5,float,range0,1,Eligibility floor
+ROW,derived,session,scalar,rmax,0.35,float,range0,1,Risk ceiling
+SECTION,RULES
+ROW,rule,rule,condition,guard,\"ok = (e >= emin) AND (r <= rmax)\",string,readonly,Guard
+FOOTER,END-OF-SHARD
+";
+
+    fn edited(edits: &[(&str, &str)]) -> Shard {
+        let mut text = BASE.to_string();
+        for (from, to) in edits {
+            assert!(text.contains(from), "{}", from);
+            text = text.replacen(from, to, 1);
+        }
+        parse(&text).unwrap()
+    }
+
+    fn shown(edits: &[(&str, &str)]) -> String {
+        diff(&parse(BASE).unwrap(), &edited(edits)).to_string()
+    }
+
+    #[test]
+    fn added_and_removed_rows() {
+        let added = "ROW,derived,session,scalar,emax,0.9,float,range0,1,Cap\n";
+        assert_eq!(
+            shown(&[("SECTION,RULES\n", &format!("{}SECTION,RULES\n", added))]),
+            "+ DERIVED/derived/emax = 0.9\n"
+        );
+        assert_eq!(
+            shown(&[("ROW,derived,session,scalar,rmax,0.35,float,range0,1,Risk ceiling\n", "")]),
+            "- DERIVED/derived/rmax = 0.35\n"
+        );
+    }
+
+    #[test]
+    fn changed_columns_are_listed() {
+        let d = diff(&parse(BASE).unwrap(), &edited(&[("emin,0.5", "emin,0.4"), ("Eligibility floor", "Lowered")]));
+        assert_eq!(
+            d.changes,
+            [Change::Modified {
+                id: RowId { section: Some("DERIVED".into()), entity_type: "derived".into(), key: "emin".into() },
+                changes: vec![
+                    FieldChange { column: "value", old: "0.5".into(), new: "0.4".into() },
+                    FieldChange { column: "notes", old: "Eligibility floor".into(), new: "Lowered".into() },
+                ],
+            }]
+        );
+        assert_eq!(
+            shown(&[("(r <= rmax)", "(r < rmax)")]),
+            "~ RULES/rule/guard\n    rule: ok = (e >= emin) AND (r <= rmax) -> ok = (e >= emin) AND (r < rmax)\n"
+        );
+        assert_eq!(shown(&[("destination-path,demo.v1.aln", "destination-path,demo.v2.aln")]), "~ destination-path: demo.v1.aln -> demo.v2.aln\n");
+    }
+
+    #[test]
+    fn layout_alone_is_not_a_change() {
+        let emin = "ROW,derived,session,scalar,emin,0.5,float,range0,1,Eligibility floor\n";
+        let reordered = [(emin, ""), ("Risk ceiling\n", &format!("Risk ceiling\n{}", emin) as &str)];
+        assert_eq!(shown(&reordered), "");
+        assert_eq!(shown(&[("(e >= emin) AND (r <= rmax)", "(e>=emin)   and (r<=rmax)")]), "");
+        assert_eq!(shown(&[("SECTION,RULES\n", "# reviewed\n\nSECTION,RULES\n")]), "");
+    }
+
+    #[test]
+    fn a_row_under_another_section_is_moved() {
+        let rmax = "ROW,derived,session,scalar,rmax,0.35,float,range0,1,Risk ceiling\n";
+        let d = diff(
+            &parse(BASE).unwrap(),
+            &edited(&[(rmax, ""), ("FOOTER", &format!("SECTION,LIMITS\n{}FOOTER", rmax.replace("0.35", "0.3")))]),
+        );
+        assert_eq!(d.to_string(), "> DERIVED/derived/rmax moved to LIMITS/derived/rmax\n    value: 0.35 -> 0.3\n");
+    }
+
+    #[test]
+    fn merges_combine_columns_and_flag_overlaps() {
+        let base = parse(BASE).unwrap();
+        let ours = edited(&[("emin,0.5", "emin,0.6")]);
+        let theirs = edited(&[("Eligibility floor", "Raised floor")]);
+        let merged = merge(&base, &ours, &theirs);
+        assert!(merged.is_clean());
+        assert!(merged.text.contains("emin,0.6,float,range0,1,Raised floor"), "{}", merged.text);
+
+        let theirs = edited(&[("emin,0.5", "emin,0.4")]);
+        let merged = merge(&base, &ours, &theirs);
+        assert_eq!(merged.conflicts.len(), 1);
+        assert_eq!(merged.conflicts[0].id.key, "emin");
+        assert!(merged.text.contains("<<<<<<< ours\nROW,derived,session,scalar,emin,0.6"), "{}", merged.text);
+    }
+}
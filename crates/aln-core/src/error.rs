@@ -0,0 +1,81 @@
+use std::fmt;
+
+/// A parse failure pinned to a 1-based line and column.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub source_name: Option<String>,
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            source_name: None,
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    pub fn with_source(mut self, name: &str) -> Self {
+        self.source_name = Some(name.to_string());
+        self
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(name) = &self.source_name {
+            write!(f, "{}:", name)?;
+        }
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+#[derive(Debug)]
+pub enum AlnError {
+    Io(std::io::Error),
+    Parse(ParseError),
+    /// A shard that parses but is refused, with every reason.
+    Invalid(Vec<ParseError>),
+    /// Missing, untrusted or non-matching `.aln.sig`.
+    Signature(String),
+    /// Shard older than the oldest version its loaders still accept.
+    Outdated { name: String, version: u32, minimum: u32 },
+    Migration(String),
+}
+
+impl fmt::Display for AlnError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlnError::Io(e) => write!(f, "IO error: {}", e),
+            AlnError::Parse(e) => write!(f, "Parse error: {}", e),
+            AlnError::Invalid(errors) => {
+                f.write_str("Invalid shard:")?;
+                for e in errors {
+                    write!(f, "\n  {}", e)?;
+                }
+                Ok(())
+            }
+            AlnError::Signature(msg) => write!(f, "Signature error: {}", msg),
+            AlnError::Outdated { name, version, minimum } => write!(
+                f,
+                "{} is version {}, older than the minimum supported version {}; upgrade it with `aln migrate`",
+                name, version, minimum
+            ),
+            AlnError::Migration(msg) => write!(f, "Migration error: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for AlnError {}
+
+impl From<ParseError> for AlnError {
+    fn from(e: ParseError) -> Self {
+        AlnError::Parse(e)
+    }
+}
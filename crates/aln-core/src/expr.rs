@@ -0,0 +1,922 @@
+//! Rule and condition rows: parsing, static type checks and a sandboxed
+//! evaluator. Rules are pure: they read and assign shard fields, nothing else.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::ast::{DataType, RowKind, Shard, Span};
+use crate::error::ParseError;
+
+/// Nesting limit so hostile input cannot exhaust the stack. Each link of an
+/// operator chain nests the tree one level deeper, so links count too.
+const MAX_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BinOp {
+    Or,
+    And,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl BinOp {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BinOp::Or => "OR",
+            BinOp::And => "AND",
+            BinOp::Eq => "==",
+            BinOp::Ne => "!=",
+            BinOp::Lt => "<",
+            BinOp::Le => "<=",
+            BinOp::Gt => ">",
+            BinOp::Ge => ">=",
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Number(f64),
+    Str(String),
+    Bool(bool),
+    /// A field, or an enum value when the context expects one.
+    Ident(String),
+    Not(Box<Expr>),
+    Neg(Box<Expr>),
+    Binary(BinOp, Box<Expr>, Box<Expr>),
+    In(Box<Expr>, Vec<Expr>),
+}
+
+impl Expr {
+    /// Top-level `AND` operands, left to right.
+    pub fn conjuncts(&self) -> Vec<&Expr> {
+        match self {
+            Expr::Binary(BinOp::And, l, r) => {
+                let mut out = l.conjuncts();
+                out.extend(r.conjuncts());
+                out
+            }
+            other => vec![other],
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Number(n) => write!(f, "{:?}", n),
+            Expr::Str(s) => write!(f, "'{}'", s),
+            Expr::Bool(b) => write!(f, "{}", b),
+            Expr::Ident(i) => f.write_str(i),
+            Expr::Not(e) => write!(f, "not {}", e),
+            Expr::Neg(e) => write!(f, "-{}", e),
+            Expr::Binary(op, l, r) => write!(f, "({} {} {})", l, op.as_str(), r),
+            Expr::In(e, items) => {
+                write!(f, "({} in ", e)?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(",")?;
+                    }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment {
+    pub target: String,
+    pub value: Expr,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rule {
+    /// `a = expr` (several separated by `,`)
+    Assign(Vec<Assignment>),
+    /// `if cond then a = expr, b = expr`
+    Conditional { condition: Expr, then: Vec<Assignment> },
+}
+
+impl Rule {
+    pub fn assignments(&self) -> &[Assignment] {
+        match self {
+            Rule::Assign(a) => a,
+            Rule::Conditional { then, .. } => then,
+        }
+    }
+}
+
+/// An error inside a rule, located by character offset into the rule text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExprError {
+    pub offset: usize,
+    pub message: String,
+}
+
+impl ExprError {
+    fn new(offset: usize, message: impl Into<String>) -> Self {
+        Self {
+            offset,
+            message: message.into(),
+        }
+    }
+
+    /// Place the error in the shard, given where the rule text starts.
+    pub fn at(&self, start: Span) -> ParseError {
+        ParseError::new(start.line, start.column + self.offset, self.message.clone())
+    }
+}
+
+impl fmt::Display for ExprError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "offset {}: {}", self.offset, self.message)
+    }
+}
+
+// ---------------------------------------------------------------- lexer
+
+#[derive(Debug, Clone, PartialEq)]
+enum Tok {
+    Num(f64),
+    Str(String),
+    Ident(String),
+    Sym(&'static str),
+    Eof,
+}
+
+fn lex(src: &str) -> Result<Vec<(Tok, usize)>, ExprError> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+        if c.is_ascii_digit() || (c == '.' && chars.get(i + 1).is_some_and(|d| d.is_ascii_digit())) {
+            while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                i += 1;
+            }
+            let text: String = chars[start..i].iter().collect();
+            let n = text
+                .parse()
+                .map_err(|_| ExprError::new(start, format!("bad number `{}`", text)))?;
+            out.push((Tok::Num(n), start));
+        } else if c.is_alphabetic() || c == '_' {
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            out.push((Tok::Ident(chars[start..i].iter().collect()), start));
+        } else if c == '\'' || c == '"' {
+            i += 1;
+            let mut text = String::new();
+            loop {
+                match chars.get(i) {
+                    None => return Err(ExprError::new(start, "unterminated string")),
+                    Some(q) if *q == c => break,
+                    Some(ch) => text.push(*ch),
+                }
+                i += 1;
+            }
+            i += 1;
+            out.push((Tok::Str(text), start));
+        } else {
+            let two: String = chars[i..(i + 2).min(chars.len())].iter().collect();
+            let sym = ["==", "!=", ">=", "<="]
+                .into_iter()
+                .find(|s| *s == two)
+                .or_else(|| ["=", ">", "<", "+", "-", "*", "/", "(", ")", ","].into_iter().find(|s| s.starts_with(c)))
+                .ok_or_else(|| ExprError::new(start, format!("unexpected character `{}`", c)))?;
+            i += sym.len();
+            out.push((Tok::Sym(sym), start));
+        }
+    }
+    out.push((Tok::Eof, chars.len()));
+    Ok(out)
+}
+
+/// `src` with every identifier `from` replaced by `to`; string literals and
+/// longer names containing `from` are left alone. Layout is preserved.
+pub fn rename_ident(src: &str, from: &str, to: &str) -> Result<String, ExprError> {
+    let chars: Vec<char> = src.chars().collect();
+    let mut out = String::new();
+    let mut copied = 0;
+    for (tok, start) in lex(src)? {
+        if matches!(&tok, Tok::Ident(name) if name == from) {
+            out.extend(&chars[copied..start]);
+            out.push_str(to);
+            copied = start + from.chars().count();
+        }
+    }
+    out.extend(&chars[copied..]);
+    Ok(out)
+}
+
+// ---------------------------------------------------------------- parser
+
+struct RuleParser {
+    toks: Vec<(Tok, usize)>,
+    pos: usize,
+    depth: usize,
+}
+
+fn is_kw(tok: &Tok, kw: &str) -> bool {
+    matches!(tok, Tok::Ident(s) if s.eq_ignore_ascii_case(kw))
+}
+
+impl RuleParser {
+    fn peek(&self) -> &Tok {
+        &self.toks[self.pos].0
+    }
+
+    fn peek_at(&self, n: usize) -> &Tok {
+        &self.toks[(self.pos + n).min(self.toks.len() - 1)].0
+    }
+
+    fn offset(&self) -> usize {
+        self.toks[self.pos].1
+    }
+
+    fn bump(&mut self) -> Tok {
+        let t = self.toks[self.pos].0.clone();
+        if self.pos + 1 < self.toks.len() {
+            self.pos += 1;
+        }
+        t
+    }
+
+    fn eat_sym(&mut self, sym: &str) -> bool {
+        if matches!(self.peek(), Tok::Sym(s) if *s == sym) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_kw(&mut self, kw: &str) -> bool {
+        if is_kw(self.peek(), kw) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_sym(&mut self, sym: &str) -> Result<(), ExprError> {
+        if self.eat_sym(sym) {
+            Ok(())
+        } else {
+            Err(ExprError::new(self.offset(), format!("expected `{}`", sym)))
+        }
+    }
+
+    fn rule(&mut self) -> Result<Rule, ExprError> {
+        let rule = if self.eat_kw("if") {
+            let condition = self.expr()?;
+            if !self.eat_kw("then") {
+                return Err(ExprError::new(self.offset(), "expected `then`"));
+            }
+            Rule::Conditional {
+                condition,
+                then: self.assignments()?,
+            }
+        } else {
+            Rule::Assign(self.assignments()?)
+        };
+        if self.peek() != &Tok::Eof {
+            return Err(ExprError::new(self.offset(), "unexpected trailing input"));
+        }
+        Ok(rule)
+    }
+
+    fn assignments(&mut self) -> Result<Vec<Assignment>, ExprError> {
+        let mut out = Vec::new();
+        loop {
+            let target = match self.bump() {
+                Tok::Ident(name) => name,
+                _ => return Err(ExprError::new(self.toks[self.pos.saturating_sub(1)].1, "expected a field to assign")),
+            };
+            self.expect_sym("=")?;
+            out.push(Assignment {
+                target,
+                value: self.expr()?,
+            });
+            if !self.eat_sym(",") {
+                return Ok(out);
+            }
+        }
+    }
+
+    fn enter(&mut self) -> Result<(), ExprError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(ExprError::new(self.offset(), "expression nested too deeply"));
+        }
+        Ok(())
+    }
+
+    fn expr(&mut self) -> Result<Expr, ExprError> {
+        self.enter()?;
+        let e = self.or();
+        self.depth -= 1;
+        e
+    }
+
+    // Chains below `enter` once per link and release the links when they end;
+    // an error abandons the whole parse, so only the success path unwinds.
+
+    fn or(&mut self) -> Result<Expr, ExprError> {
+        let mut lhs = self.and()?;
+        let mut links = 0;
+        while self.eat_kw("or") {
+            self.enter()?;
+            links += 1;
+            lhs = Expr::Binary(BinOp::Or, Box::new(lhs), Box::new(self.and()?));
+        }
+        self.depth -= links;
+        Ok(lhs)
+    }
+
+    fn and(&mut self) -> Result<Expr, ExprError> {
+        let mut lhs = self.not()?;
+        let mut links = 0;
+        while self.eat_kw("and") {
+            self.enter()?;
+            links += 1;
+            lhs = Expr::Binary(BinOp::And, Box::new(lhs), Box::new(self.not()?));
+        }
+        self.depth -= links;
+        Ok(lhs)
+    }
+
+    fn not(&mut self) -> Result<Expr, ExprError> {
+        if self.eat_kw("not") {
+            self.enter()?;
+            let e = self.not().map(|e| Expr::Not(Box::new(e)));
+            self.depth -= 1;
+            return e;
+        }
+        self.comparison()
+    }
+
+    fn comparison(&mut self) -> Result<Expr, ExprError> {
+        let lhs = self.additive()?;
+        if self.eat_kw("in") {
+            // `x in A,B,C`: items run until one is followed by `=` (next assignment).
+            let mut items = vec![self.additive()?];
+            while self.peek() == &Tok::Sym(",") && self.peek_at(2) != &Tok::Sym("=") {
+                self.bump();
+                items.push(self.additive()?);
+            }
+            return Ok(Expr::In(Box::new(lhs), items));
+        }
+        let op = match self.peek() {
+            Tok::Sym("==") => BinOp::Eq,
+            Tok::Sym("!=") => BinOp::Ne,
+            Tok::Sym("<") => BinOp::Lt,
+            Tok::Sym("<=") => BinOp::Le,
+            Tok::Sym(">") => BinOp::Gt,
+            Tok::Sym(">=") => BinOp::Ge,
+            _ => return Ok(lhs),
+        };
+        self.bump();
+        Ok(Expr::Binary(op, Box::new(lhs), Box::new(self.additive()?)))
+    }
+
+    fn additive(&mut self) -> Result<Expr, ExprError> {
+        let mut lhs = self.multiplicative()?;
+        let mut links = 0;
+        loop {
+            let op = match self.peek() {
+                Tok::Sym("+") => BinOp::Add,
+                Tok::Sym("-") => BinOp::Sub,
+                _ => break,
+            };
+            self.bump();
+            self.enter()?;
+            links += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.multiplicative()?));
+        }
+        self.depth -= links;
+        Ok(lhs)
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr, ExprError> {
+        let mut lhs = self.unary()?;
+        let mut links = 0;
+        loop {
+            let op = match self.peek() {
+                Tok::Sym("*") => BinOp::Mul,
+                Tok::Sym("/") => BinOp::Div,
+                _ => break,
+            };
+            self.bump();
+            self.enter()?;
+            links += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.unary()?));
+        }
+        self.depth -= links;
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr, ExprError> {
+        if self.eat_sym("-") {
+            self.enter()?;
+            let e = self.unary().map(|e| Expr::Neg(Box::new(e)));
+            self.depth -= 1;
+            return e;
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr, ExprError> {
+        let offset = self.offset();
+        match self.bump() {
+            Tok::Num(n) => Ok(Expr::Number(n)),
+            Tok::Str(s) => Ok(Expr::Str(s)),
+            Tok::Ident(s) if s == "true" || s == "false" => Ok(Expr::Bool(s == "true")),
+            Tok::Ident(s) => Ok(Expr::Ident(s)),
+            Tok::Sym("(") => {
+                let e = self.expr()?;
+                self.expect_sym(")")?;
+                Ok(e)
+            }
+            Tok::Eof => Err(ExprError::new(offset, "unexpected end of rule")),
+            Tok::Sym(s) => Err(ExprError::new(offset, format!("unexpected `{}`", s))),
+        }
+    }
+}
+
+pub fn parse_rule(src: &str) -> Result<Rule, ExprError> {
+    let toks = lex(src)?;
+    RuleParser { toks, pos: 0, depth: 0 }.rule()
+}
+
+// ---------------------------------------------------------------- types
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    Number,
+    Bool,
+    Str,
+    Enum(BTreeSet<String>),
+}
+
+impl FieldType {
+    fn describe(&self) -> String {
+        match self {
+            FieldType::Number => "number".into(),
+            FieldType::Bool => "bool".into(),
+            FieldType::Str => "string".into(),
+            FieldType::Enum(values) => format!("enum {{{}}}", values.iter().cloned().collect::<Vec<_>>().join(",")),
+        }
+    }
+}
+
+/// Declared fields of a shard and their types.
+#[derive(Debug, Clone, Default)]
+pub struct TypeEnv {
+    fields: BTreeMap<String, FieldType>,
+}
+
+impl TypeEnv {
+    pub fn from_shard(shard: &Shard) -> Self {
+        let mut fields = BTreeMap::new();
+        for (_, row) in shard.rows().filter(|(_, r)| !r.kind.is_rule()) {
+            let ty = match (&row.kind, &row.datatype) {
+                (RowKind::Enum, _) => FieldType::Enum(row.constraints.iter().cloned().collect()),
+                (_, Some(DataType::Float | DataType::Int)) => FieldType::Number,
+                (_, Some(DataType::Bool)) => FieldType::Bool,
+                _ => FieldType::Str,
+            };
+            fields.insert(row.key.clone(), ty);
+        }
+        Self { fields }
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldType> {
+        self.fields.get(name)
+    }
+
+    /// Type of `e`. `expected` lets a bare identifier resolve to an enum value.
+    fn type_of(&self, e: &Expr, expected: Option<&FieldType>) -> Result<FieldType, String> {
+        match e {
+            Expr::Number(_) => Ok(FieldType::Number),
+            Expr::Str(_) => Ok(FieldType::Str),
+            Expr::Bool(_) => Ok(FieldType::Bool),
+            Expr::Ident(name) => {
+                if let Some(ty) = self.fields.get(name) {
+                    return Ok(ty.clone());
+                }
+                match expected {
+                    Some(FieldType::Enum(values)) if values.contains(name) => Ok(FieldType::Enum(values.clone())),
+                    Some(FieldType::Enum(values)) => Err(format!(
+                        "`{}` is neither a field nor one of {{{}}}",
+                        name,
+                        values.iter().cloned().collect::<Vec<_>>().join(",")
+                    )),
+                    _ => Err(format!("unknown field `{}`", name)),
+                }
+            }
+            Expr::Not(inner) => self.expect(inner, &FieldType::Bool).map(|_| FieldType::Bool),
+            Expr::Neg(inner) => self.expect(inner, &FieldType::Number).map(|_| FieldType::Number),
+            Expr::In(lhs, items) => {
+                let lt = self.type_of(lhs, None)?;
+                if !matches!(lt, FieldType::Enum(_) | FieldType::Str) {
+                    return Err(format!("`in` needs an enum or string, found {}", lt.describe()));
+                }
+                for item in items {
+                    self.expect(item, &lt)?;
+                }
+                Ok(FieldType::Bool)
+            }
+            Expr::Binary(op, l, r) => match op {
+                BinOp::And | BinOp::Or => {
+                    self.expect(l, &FieldType::Bool)?;
+                    self.expect(r, &FieldType::Bool)?;
+                    Ok(FieldType::Bool)
+                }
+                BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => {
+                    self.expect(l, &FieldType::Number)?;
+                    self.expect(r, &FieldType::Number)?;
+                    Ok(FieldType::Bool)
+                }
+                BinOp::Eq | BinOp::Ne => {
+                    let lt = self.type_of(l, None).or_else(|_| {
+                        let rt = self.type_of(r, None)?;
+                        self.type_of(l, Some(&rt))
+                    })?;
+                    self.expect(r, &lt)?;
+                    Ok(FieldType::Bool)
+                }
+                BinOp::Add => {
+                    let (lt, rt) = (self.type_of(l, None)?, self.type_of(r, None)?);
+                    match (&lt, &rt) {
+                        (FieldType::Number, FieldType::Number) => Ok(FieldType::Number),
+                        // String concatenation, for diagnostic messages.
+                        (FieldType::Str, _) | (_, FieldType::Str) => Ok(FieldType::Str),
+                        _ => Err(format!("cannot add {} and {}", lt.describe(), rt.describe())),
+                    }
+                }
+                BinOp::Sub | BinOp::Mul | BinOp::Div => {
+                    self.expect(l, &FieldType::Number)?;
+                    self.expect(r, &FieldType::Number)?;
+                    Ok(FieldType::Number)
+                }
+            },
+        }
+    }
+
+    fn expect(&self, e: &Expr, want: &FieldType) -> Result<(), String> {
+        let got = self.type_of(e, Some(want))?;
+        let ok = match (want, &got) {
+            (FieldType::Enum(values), FieldType::Enum(_)) => match e {
+                Expr::Ident(name) if !self.fields.contains_key(name) => values.contains(name),
+                _ => got == *want,
+            },
+            // A string field compared or assigned to a literal is fine.
+            (FieldType::Enum(values), FieldType::Str) => matches!(e, Expr::Str(s) if values.contains(s)),
+            _ => got == *want,
+        };
+        if ok {
+            Ok(())
+        } else {
+            Err(format!("`{}` is {}, expected {}", e, got.describe(), want.describe()))
+        }
+    }
+
+    /// Static check of a whole rule against the declared fields.
+    pub fn check_rule(&self, rule: &Rule) -> Result<(), String> {
+        if let Rule::Conditional { condition, .. } = rule {
+            self.expect(condition, &FieldType::Bool)
+                .map_err(|e| format!("condition: {}", e))?;
+        }
+        for a in rule.assignments() {
+            let target = self
+                .fields
+                .get(&a.target)
+                .ok_or_else(|| format!("assignment to undeclared field `{}`", a.target))?;
+            self.expect(&a.value, target)
+                .map_err(|e| format!("assignment to `{}`: {}", a.target, e))?;
+        }
+        Ok(())
+    }
+}
+
+// ---------------------------------------------------------------- evaluation
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Number(f64),
+    Bool(bool),
+    Str(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Bool(b) => write!(f, "{}", b),
+            Value::Str(s) => f.write_str(s),
+        }
+    }
+}
+
+pub type State = BTreeMap<String, Value>;
+
+pub fn eval(e: &Expr, state: &State, env: &TypeEnv) -> Result<Value, String> {
+    let eval = |e: &Expr, state: &State| eval(e, state, env);
+    Ok(match e {
+        Expr::Number(n) => Value::Number(*n),
+        Expr::Str(s) => Value::Str(s.clone()),
+        Expr::Bool(b) => Value::Bool(*b),
+        Expr::Ident(name) => match state.get(name) {
+            Some(v) => v.clone(),
+            None if env.field(name).is_some() => return Err(format!("field `{}` has no value", name)),
+            // Not a field: type checking accepted it as an enum value.
+            None => Value::Str(name.clone()),
+        },
+        Expr::Not(inner) => Value::Bool(!as_bool(&eval(inner, state)?)?),
+        Expr::Neg(inner) => Value::Number(-as_number(&eval(inner, state)?)?),
+        Expr::In(lhs, items) => {
+            let v = eval(lhs, state)?;
+            let mut found = false;
+            for item in items {
+                found |= eval(item, state)? == v;
+            }
+            Value::Bool(found)
+        }
+        Expr::Binary(op, l, r) => {
+            let lv = eval(l, state)?;
+            match op {
+                BinOp::And if !as_bool(&lv)? => return Ok(Value::Bool(false)),
+                BinOp::Or if as_bool(&lv)? => return Ok(Value::Bool(true)),
+                _ => {}
+            }
+            let rv = eval(r, state)?;
+            match op {
+                BinOp::And | BinOp::Or => Value::Bool(as_bool(&rv)?),
+                BinOp::Eq => Value::Bool(lv == rv),
+                BinOp::Ne => Value::Bool(lv != rv),
+                BinOp::Lt => Value::Bool(as_number(&lv)? < as_number(&rv)?),
+                BinOp::Le => Value::Bool(as_number(&lv)? <= as_number(&rv)?),
+                BinOp::Gt => Value::Bool(as_number(&lv)? > as_number(&rv)?),
+                BinOp::Ge => Value::Bool(as_number(&lv)? >= as_number(&rv)?),
+                BinOp::Add => match (&lv, &rv) {
+                    (Value::Number(a), Value::Number(b)) => Value::Number(a + b),
+                    _ => Value::Str(format!("{}{}", lv, rv)),
+                },
+                BinOp::Sub => Value::Number(as_number(&lv)? - as_number(&rv)?),
+                BinOp::Mul => Value::Number(as_number(&lv)? * as_number(&rv)?),
+                BinOp::Div => {
+                    let d = as_number(&rv)?;
+                    if d == 0.0 {
+                        return Err(format!("division by zero in `{}`", e));
+                    }
+                    Value::Number(as_number(&lv)? / d)
+                }
+            }
+        }
+    })
+}
+
+fn as_bool(v: &Value) -> Result<bool, String> {
+    match v {
+        Value::Bool(b) => Ok(*b),
+        other => Err(format!("expected bool, found `{}`", other)),
+    }
+}
+
+fn as_number(v: &Value) -> Result<f64, String> {
+    match v {
+        Value::Number(n) => Ok(*n),
+        other => Err(format!("expected number, found `{}`", other)),
+    }
+}
+
+/// A rule row, parsed and type-checked.
+#[derive(Debug, Clone)]
+pub struct CompiledRule {
+    pub name: String,
+    pub source: String,
+    pub span: Span,
+    pub rule: Rule,
+}
+
+impl CompiledRule {
+    /// Apply the rule to `state`; returns whether it fired.
+    pub fn apply(&self, state: &mut State, env: &TypeEnv) -> Result<bool, String> {
+        if let Rule::Conditional { condition, .. } = &self.rule {
+            if !as_bool(&eval(condition, state, env)?)? {
+                return Ok(false);
+            }
+        }
+        for a in self.rule.assignments() {
+            let v = eval(&a.value, state, env)?;
+            state.insert(a.target.clone(), v);
+        }
+        Ok(true)
+    }
+}
+
+/// Every `expression` / `condition` row of a shard, in shard order.
+#[derive(Debug, Clone)]
+pub struct RuleSet {
+    pub env: TypeEnv,
+    pub rules: Vec<CompiledRule>,
+    defaults: State,
+}
+
+impl RuleSet {
+    /// Parse and type-check every rule row; all errors are reported together.
+    pub fn from_shard(shard: &Shard) -> Result<Self, Vec<ParseError>> {
+        let env = TypeEnv::from_shard(shard);
+        let mut rules = Vec::new();
+        let mut errors = Vec::new();
+        for (_, row) in shard.rows().filter(|(_, r)| r.kind.is_rule()) {
+            let compiled = parse_rule(&row.value)
+                .map_err(|e| e.at(row.spans.value))
+                .and_then(|rule| {
+                    env.check_rule(&rule)
+                        .map(|_| rule)
+                        .map_err(|m| ParseError::new(row.spans.value.line, row.spans.value.column, format!("{}: {}", row.key, m)))
+                });
+            match compiled {
+                Ok(rule) => rules.push(CompiledRule {
+                    name: row.key.clone(),
+                    source: row.value.clone(),
+                    span: row.spans.value,
+                    rule,
+                }),
+                Err(e) => errors.push(e),
+            }
+        }
+
+        let mut defaults = State::new();
+        for (_, row) in shard.rows().filter(|(_, r)| !r.kind.is_rule()) {
+            let value = match env.field(&row.key) {
+                Some(FieldType::Number) => row.float_value().map(Value::Number),
+                Some(FieldType::Bool) => row.bool_value().map(Value::Bool),
+                _ => Some(Value::Str(row.value.clone())),
+            };
+            if let Some(v) = value {
+                defaults.insert(row.key.clone(), v);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Self { env, rules, defaults })
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Field values declared in the shard.
+    pub fn default_state(&self) -> State {
+        self.defaults.clone()
+    }
+
+    pub fn rule(&self, name: &str) -> Option<&CompiledRule> {
+        self.rules.iter().find(|r| r.name == name)
+    }
+
+    /// Run every rule once, in order. Returns the names of rules that fired.
+    pub fn evaluate(&self, state: &mut State) -> Result<Vec<String>, String> {
+        let mut fired = Vec::new();
+        for rule in &self.rules {
+            if rule.apply(state, &self.env).map_err(|e| format!("{}: {}", rule.name, e))? {
+                fired.push(rule.name.clone());
+            }
+        }
+        Ok(fired)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const FIELDS: &str = "\
+SECTION,FIELDS
+ROW,subject,subject,enum,stage,wake,string,wake,N2,N3,Sleep stage
+ROW,subject,subject,scalar,risk,0.2,float,range0,1,Risk
+ROW,subject,subject,scalar,token,0.5,float,range0,1,Token
+ROW,derived,session,scalar,score,0.0,float,,Score
+ROW,derived,session,flag,ok,false,bool,nonnull,Guard
+SECTION,RULES
+";
+
+    fn rules(rows: &[(&str, &str)]) -> Result<RuleSet, Vec<ParseError>> {
+        let mut source = FIELDS.to_string();
+        for (name, rule) in rows {
+            source.push_str(&format!("ROW,rule,rule,condition,{},\"{}\",string,readonly,\n", name, rule));
+        }
+        RuleSet::from_shard(&parse(&source).unwrap())
+    }
+
+    fn shown(src: &str) -> String {
+        parse_rule(src).unwrap().assignments()[0].value.to_string()
+    }
+
+    #[test]
+    fn operators_bind_by_precedence() {
+        assert_eq!(shown("x = a + b * c - d"), "((a + (b * c)) - d)");
+        assert_eq!(shown("x = -a * b / c"), "((-a * b) / c)");
+        assert_eq!(shown("x = not a and b or c"), "((not a AND b) OR c)");
+        assert_eq!(shown("x = a < b + 1 AND c"), "((a < (b + 1.0)) AND c)");
+        assert_eq!(shown("x = (a + b) * c"), "((a + b) * c)");
+
+        let set = rules(&[("calc", "score = 1 + 2 * 3 - 4 / 2")]).unwrap();
+        let mut state = set.default_state();
+        set.evaluate(&mut state).unwrap();
+        assert_eq!(state["score"], Value::Number(5.0));
+    }
+
+    #[test]
+    fn type_errors_name_the_rule_and_its_cell() {
+        let errors = rules(&[
+            ("good", "score = token * (1.0 - risk)"),
+            ("badAdd", "ok = score + 1"),
+            ("badCond", "if risk then ok = true"),
+        ])
+        .unwrap_err();
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].line, errors[0].column), (9, 33));
+        assert_eq!(errors[0].message, "badAdd: assignment to `ok`: `(score + 1.0)` is number, expected bool");
+        assert_eq!(errors[1].message, "badCond: condition: `risk` is number, expected bool");
+    }
+
+    #[test]
+    fn syntax_errors_point_into_the_rule_text() {
+        let e = parse_rule("x = (a + b").unwrap_err();
+        assert_eq!((e.offset, e.message.as_str()), (10, "expected `)`"));
+        assert_eq!(e.at(Span { line: 3, column: 5 }).column, 15);
+        assert_eq!(parse_rule("x = a # b").unwrap_err().message, "unexpected character `#`");
+        assert_eq!(parse_rule("if a x = 1").unwrap_err().message, "expected `then`");
+    }
+
+    #[test]
+    fn long_chains_count_toward_the_nesting_limit() {
+        for sep in [" + ", " * ", " and ", " or "] {
+            let long = format!("x = {}", vec!["a"; 100_000].join(sep));
+            assert_eq!(parse_rule(&long).unwrap_err().message, "expression nested too deeply", "{}", sep);
+            let short = format!("x = {}", vec!["a"; 32].join(sep));
+            assert!(parse_rule(&short).is_ok(), "{}", sep);
+        }
+        let parens = format!("x = {}a{}", "(".repeat(100_000), ")".repeat(100_000));
+        assert_eq!(parse_rule(&parens).unwrap_err().message, "expression nested too deeply");
+    }
+
+    #[test]
+    fn unknown_identifiers_are_refused() {
+        let errors = rules(&[("typo", "score = missing * 2")]).unwrap_err();
+        assert!(errors[0].message.contains("unknown field `missing`"), "{}", errors[0]);
+
+        let errors = rules(&[("badStage", "ok = stage == N7")]).unwrap_err();
+        assert!(errors[0].message.contains("`N7` is neither a field nor one of {N2,N3,wake}"), "{}", errors[0]);
+
+        let errors = rules(&[("ghost", "undeclared = 1")]).unwrap_err();
+        assert!(errors[0].message.contains("undeclared field `undeclared`"), "{}", errors[0]);
+
+        // Enum values resolve against the field they are compared with.
+        let set = rules(&[("deep", "ok = stage in N2,N3")]).unwrap();
+        let mut state = set.default_state();
+        state.insert("stage".into(), Value::Str("N3".into()));
+        set.evaluate(&mut state).unwrap();
+        assert_eq!(state["ok"], Value::Bool(true));
+    }
+
+    #[test]
+    fn division_by_zero_is_an_evaluation_error() {
+        let set = rules(&[("ratio", "score = token / risk")]).unwrap();
+        let mut state = set.default_state();
+        set.evaluate(&mut state).unwrap();
+        assert_eq!(state["score"], Value::Number(2.5));
+
+        state.insert("risk".into(), Value::Number(0.0));
+        let err = set.evaluate(&mut state).unwrap_err();
+        assert_eq!(err, "ratio: division by zero in `(token / risk)`");
+
+        state.remove("risk");
+        let err = set.evaluate(&mut state).unwrap_err();
+        assert_eq!(err, "ratio: field `risk` has no value");
+    }
+}
@@ -0,0 +1,360 @@
+//! Lossless conversion between the ALN AST and JSON / NDJSON, so web tools
+//! and agents can read shards without an ALN parser.
+//!
+//! A JSON document is `{"format": "aln-json/1", "items": [...]}`; NDJSON is
+//! the same items, one per line, with no wrapper. Items keep file order,
+//! comments and blank lines included, and are tagged by `type`:
+//!
+//! | `type`             | other members                                   |
+//! |--------------------|-------------------------------------------------|
+//! | `destination-path` | `path`                                          |
+//! | `datashard`        | `kind`, `description`                           |
+//! | `header`           | `cells`                                         |
+//! | `section`          | `name`                                          |
+//! | `row`              | `entitytype`, `field`, `kind`, `key`, `value`, `datatype`, `constraints`, `notes`, `spans` |
+//! | `comment`          | `text` (after the `#`, verbatim)                |
+//! | `blank`            |                                                 |
+//! | `footer`           | `text`                                          |
+//!
+//! Every item also has `line`. Row cells stay strings exactly as written
+//! (`"0.35"`, not `0.35`); `datatype` says how to read them. `spans` holds
+//! `[line, column]` pairs for `key`, `value`, `datatype`, `constraints` and
+//! `notes`. Hand-written input may leave out `line` and `spans`; positions
+//! are then taken from the shard as `writer::write` would lay it out.
+
+use serde::{Deserialize, Serialize};
+
+use crate::ast::{DataType, Item, ItemKind, Row, RowKind, RowSpans, Shard, Span};
+use crate::error::{AlnError, ParseError};
+
+pub const FORMAT: &str = "aln-json/1";
+
+type Pos = (usize, usize);
+
+fn pos(span: Span) -> Pos {
+    (span.line, span.column)
+}
+
+fn span((line, column): Pos) -> Span {
+    Span { line, column }
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonSpans {
+    key: Pos,
+    value: Pos,
+    #[serde(default)]
+    datatype: Option<Pos>,
+    #[serde(default)]
+    constraints: Vec<Pos>,
+    #[serde(default)]
+    notes: Option<Pos>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonRow {
+    entitytype: String,
+    field: String,
+    kind: String,
+    key: String,
+    value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    datatype: Option<String>,
+    #[serde(default)]
+    constraints: Vec<String>,
+    #[serde(default)]
+    notes: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    spans: Option<JsonSpans>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+enum JsonKind {
+    DestinationPath { path: String },
+    Datashard { kind: String, description: String },
+    Header { cells: Vec<String> },
+    Section { name: String },
+    Row(Box<JsonRow>),
+    Comment {
+        #[serde(default, skip_serializing_if = "String::is_empty")]
+        indent: String,
+        text: String,
+    },
+    Blank,
+    Footer { text: String },
+}
+
+#[derive(Serialize, Deserialize)]
+struct JsonItem {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(flatten)]
+    kind: JsonKind,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct JsonDocument {
+    format: String,
+    items: Vec<JsonItem>,
+}
+
+fn to_item(item: &Item) -> JsonItem {
+    let kind = match &item.kind {
+        ItemKind::DestinationPath(path) => JsonKind::DestinationPath { path: path.clone() },
+        ItemKind::Datashard { kind, description } => JsonKind::Datashard {
+            kind: kind.clone(),
+            description: description.clone(),
+        },
+        ItemKind::Header(cells) => JsonKind::Header { cells: cells.clone() },
+        ItemKind::Section(name) => JsonKind::Section { name: name.clone() },
+        ItemKind::Row(row) => JsonKind::Row(Box::new(JsonRow {
+            entitytype: row.entity_type.clone(),
+            field: row.field.clone(),
+            kind: row.kind.as_str().to_string(),
+            key: row.key.clone(),
+            value: row.value.clone(),
+            datatype: row.datatype.as_ref().map(|d| d.as_str().to_string()),
+            constraints: row.constraints.clone(),
+            notes: row.notes.clone(),
+            spans: Some(JsonSpans {
+                key: pos(row.spans.key),
+                value: pos(row.spans.value),
+                datatype: row.spans.datatype.map(pos),
+                constraints: row.spans.constraints.iter().copied().map(pos).collect(),
+                notes: row.spans.notes.map(pos),
+            }),
+        })),
+        ItemKind::Comment { indent, text } => JsonKind::Comment {
+            indent: indent.clone(),
+            text: text.clone(),
+        },
+        ItemKind::Blank => JsonKind::Blank,
+        ItemKind::Footer(text) => JsonKind::Footer { text: text.clone() },
+    };
+    JsonItem {
+        line: Some(item.span.line),
+        kind,
+    }
+}
+
+/// Returns the item and whether its positions were given in full.
+fn from_item(item: JsonItem) -> (Item, bool) {
+    let mut complete = item.line.is_some();
+    let line = item.line.unwrap_or_default();
+    let kind = match item.kind {
+        JsonKind::DestinationPath { path } => ItemKind::DestinationPath(path),
+        JsonKind::Datashard { kind, description } => ItemKind::Datashard { kind, description },
+        JsonKind::Header { cells } => ItemKind::Header(cells),
+        JsonKind::Section { name } => ItemKind::Section(name),
+        JsonKind::Row(row) => {
+            let spans = match row.spans {
+                Some(s) => RowSpans {
+                    row: Span { line, column: 1 },
+                    key: span(s.key),
+                    value: span(s.value),
+                    datatype: s.datatype.map(span),
+                    constraints: s.constraints.into_iter().map(span).collect(),
+                    notes: s.notes.map(span),
+                },
+                None => {
+                    complete = false;
+                    RowSpans::default()
+                }
+            };
+            ItemKind::Row(Box::new(Row {
+                entity_type: row.entitytype,
+                field: row.field,
+                kind: RowKind::parse(&row.kind),
+                key: row.key,
+                value: row.value,
+                datatype: row.datatype.as_deref().filter(|d| !d.is_empty()).map(DataType::parse),
+                constraints: row.constraints,
+                notes: row.notes,
+                spans,
+            }))
+        }
+        JsonKind::Comment { indent, text } => ItemKind::Comment { indent, text },
+        JsonKind::Blank => ItemKind::Blank,
+        JsonKind::Footer { text } => ItemKind::Footer(text),
+    };
+    let item = Item {
+        kind,
+        span: Span { line, column: 1 },
+    };
+    (item, complete)
+}
+
+fn from_items(items: Vec<JsonItem>) -> Result<Shard, AlnError> {
+    let mut complete = true;
+    let items = items
+        .into_iter()
+        .map(|i| {
+            let (item, full) = from_item(i);
+            complete &= full;
+            item
+        })
+        .collect();
+    let shard = Shard { items };
+    if complete {
+        Ok(shard)
+    } else {
+        Ok(crate::parser::parse(&crate::writer::write(&shard))?)
+    }
+}
+
+fn json_error(e: serde_json::Error, line_offset: usize) -> ParseError {
+    // The position goes in the ParseError; drop serde's own " at line N column M".
+    let message = e.to_string();
+    let message = message.rsplit_once(" at line ").map_or(message.as_str(), |(m, _)| m);
+    ParseError::new(e.line() + line_offset, e.column(), message)
+}
+
+pub fn to_json(shard: &Shard) -> String {
+    let doc = JsonDocument {
+        format: FORMAT.to_string(),
+        items: shard.items.iter().map(to_item).collect(),
+    };
+    serde_json::to_string_pretty(&doc).expect("shard serialization is infallible") + "\n"
+}
+
+pub fn from_json(text: &str) -> Result<Shard, AlnError> {
+    let doc: JsonDocument = serde_json::from_str(text).map_err(|e| json_error(e, 0))?;
+    if doc.format != FORMAT {
+        return Err(ParseError::new(1, 1, format!("unsupported format `{}` (expected `{}`)", doc.format, FORMAT)).into());
+    }
+    from_items(doc.items)
+}
+
+pub fn to_ndjson(shard: &Shard) -> String {
+    let mut out = String::new();
+    for item in &shard.items {
+        out.push_str(&serde_json::to_string(&to_item(item)).expect("shard serialization is infallible"));
+        out.push('\n');
+    }
+    out
+}
+
+pub fn from_ndjson(text: &str) -> Result<Shard, AlnError> {
+    let mut items = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        items.push(serde_json::from_str(line).map_err(|e| json_error(e, idx))?);
+    }
+    from_items(items)
+}
+
+/// Convert `shard` to JSON and NDJSON and back, and report the first item
+/// that does not come back identical.
+pub fn check_roundtrip(shard: &Shard) -> Result<(), String> {
+    let via_json = from_json(&to_json(shard)).map_err(|e| format!("json: {}", e))?;
+    let via_ndjson = from_ndjson(&to_ndjson(shard)).map_err(|e| format!("ndjson: {}", e))?;
+    for (name, back) in [("json", via_json), ("ndjson", via_ndjson)] {
+        if back.items.len() != shard.items.len() {
+            return Err(format!("{}: {} items in, {} out", name, shard.items.len(), back.items.len()));
+        }
+        if let Some((orig, _)) = shard.items.iter().zip(&back.items).find(|(a, b)| a != b) {
+            return Err(format!("{}: line {} does not survive the round trip", name, orig.span.line));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::writer::write;
+
+    /// Every shard checked into the repository.
+    const REPO_SHARDS: &[(&str, &str)] = &[
+        ("108ce7", include_str!("../../../deepseek_aln_20260125_108ce7.txt")),
+        ("149f27", include_str!("../../../deepseek_aln_20260125_149f27.txt")),
+        ("b4a839", include_str!("../../../deepseek_aln_20260125_b4a839.txt")),
+        ("e1235a", include_str!("../../../deepseek_aln_20260125_e1235a.txt")),
+        ("eab790", include_str!("../../../deepseek_aln_20260125_eab790.txt")),
+        ("ec2347", include_str!("../../../deepseek_aln_20260125_ec2347.txt")),
+    ];
+
+    #[test]
+    fn repo_shards_survive_json() {
+        for (name, text) in REPO_SHARDS {
+            let shard = parse(text).unwrap();
+            let back = from_json(&to_json(&shard)).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert_eq!(back, shard, "{}", name);
+            assert_eq!(write(&back), write(&shard), "{}", name);
+        }
+    }
+
+    #[test]
+    fn repo_shards_survive_ndjson() {
+        for (name, text) in REPO_SHARDS {
+            let shard = parse(text).unwrap();
+            let ndjson = to_ndjson(&shard);
+            assert_eq!(ndjson.lines().count(), shard.items.len(), "{}", name);
+            let back = from_ndjson(&ndjson).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert_eq!(back, shard, "{}", name);
+            assert!(check_roundtrip(&shard).is_ok(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn comments_blanks_and_order_are_kept() {
+        let text = "\
+# leading comment
+destination-path,demo.v1.aln
+SECTION,B
+ROW,derived,session,scalar,zeta,1,int,,last key first
+#   indented comment text
+
+ROW,derived,session,scalar,alpha,0.50,float,range0,1,\"quoted, with comma\"
+SECTION,A
+FOOTER,END-OF-SHARD
+";
+        let shard = parse(text).unwrap();
+        for back in [from_json(&to_json(&shard)).unwrap(), from_ndjson(&to_ndjson(&shard)).unwrap()] {
+            assert_eq!(write(&back), write(&shard));
+            assert_eq!(back.items, shard.items);
+        }
+        let json = to_json(&shard);
+        assert!(json.contains("\"text\": \"   indented comment text\""), "{}", json);
+        assert!(json.contains("\"value\": \"0.50\""), "{}", json);
+        assert!(json.find("zeta").unwrap() < json.find("alpha").unwrap());
+    }
+
+    #[test]
+    fn notes_without_datatype_and_indented_comments_are_exact() {
+        let text = "  # indented comment\n\t#tabbed\nROW,derived,session,scalar,k,v,,,notes only\n";
+        let shard = parse(text).unwrap();
+        let row = shard.row("k").unwrap();
+        assert_eq!((row.datatype.as_ref(), row.notes.as_str()), (None, "notes only"));
+        assert_eq!(write(&shard), text);
+        assert_eq!(parse(&write(&shard)).unwrap(), shard);
+        for back in [from_json(&to_json(&shard)).unwrap(), from_ndjson(&to_ndjson(&shard)).unwrap()] {
+            assert_eq!(back, shard);
+        }
+    }
+
+    #[test]
+    fn hand_written_json_gets_positions() {
+        let json = r#"{"format": "aln-json/1", "items": [
+            {"type": "destination-path", "path": "demo.v1.aln"},
+            {"type": "comment", "text": " note"},
+            {"type": "row", "entitytype": "derived", "field": "session", "kind": "scalar",
+             "key": "rmax", "value": "0.35", "datatype": "float"}
+        ]}"#;
+        let shard = from_json(json).unwrap();
+        assert_eq!(shard, parse(&write(&shard)).unwrap());
+        assert_eq!(shard.row("rmax").unwrap().spans.value.line, 3);
+    }
+
+    #[test]
+    fn errors_point_at_the_bad_line() {
+        let err = from_ndjson("{\"type\": \"blank\"}\n{\"type\": \"nope\"}\n").unwrap_err();
+        assert!(err.to_string().starts_with("Parse error: 2:"), "{}", err);
+        assert!(from_json(r#"{"format": "aln-json/9", "items": []}"#).is_err());
+    }
+}
@@ -0,0 +1,19 @@
+pub mod ast;
+pub mod parser;
+pub mod expr;
+pub mod validate;
+pub mod codegen;
+pub mod lint;
+pub mod diff;
+pub mod overlay;
+pub mod writer;
+pub mod canonical;
+pub mod json;
+pub mod migrate;
+pub mod error;
+
+pub use ast::{DataType, Item, ItemKind, Row, RowKind, Shard, Span};
+pub use canonical::{canonicalize, content_hash, load_verified, ShardSignature, SignaturePolicy};
+pub use error::{AlnError, ParseError};
+pub use parser::{parse, parse_file, parse_named};
+pub use validate::{validate_shard, Schema, Violation, ViolationKind};
@@ -0,0 +1,483 @@
+//! Safety lints for shards: the patterns that turn a debug patch into a
+//! production hazard. Severities are configurable per rule; production
+//! builds promote everything to errors.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::ast::{DataType, ItemKind, Row, Shard, Span};
+use crate::expr::{parse_rule, BinOp, Expr, Rule};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Off,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Off => "off",
+            Severity::Warning => "warning",
+            Severity::Error => "error",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LintRule {
+    /// `YOUR_DEV_ID`, `CHANGEME`, `<subject>` and friends.
+    PlaceholderId,
+    /// Override rows, and rules keyed on one identity.
+    OverrideRow,
+    /// A threshold moved in the permissive direction relative to the baseline.
+    LoosenedThreshold,
+    /// A guard's `in` list grew relative to the baseline.
+    WidenedGuard,
+    /// "Temporarily", "DEV ONLY", "for testing" in comments or notes.
+    DevComment,
+}
+
+impl LintRule {
+    pub const ALL: [LintRule; 5] = [
+        LintRule::PlaceholderId,
+        LintRule::OverrideRow,
+        LintRule::LoosenedThreshold,
+        LintRule::WidenedGuard,
+        LintRule::DevComment,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LintRule::PlaceholderId => "placeholder-id",
+            LintRule::OverrideRow => "override-row",
+            LintRule::LoosenedThreshold => "loosened-threshold",
+            LintRule::WidenedGuard => "widened-guard",
+            LintRule::DevComment => "dev-comment",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|r| r.as_str() == s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub rule: LintRule,
+    pub severity: Severity,
+    pub span: Span,
+    pub message: String,
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{}: {}[{}]: {}",
+            self.span.line,
+            self.span.column,
+            self.severity.as_str(),
+            self.rule.as_str(),
+            self.message
+        )
+    }
+}
+
+const DEV_MARKERS: &[&str] = &[
+    "temporarily",
+    "dev only",
+    "development testing",
+    "for testing",
+    "debug only",
+    "do not ship",
+];
+
+const PLACEHOLDER_MARKERS: &[&str] = &["PLACEHOLDER", "CHANGEME", "CHANGE_ME", "REPLACE_ME", "TODO", "FIXME", "XXX"];
+
+#[derive(Debug, Clone)]
+pub struct LintConfig {
+    severities: BTreeMap<LintRule, Severity>,
+    baseline: Option<Shard>,
+    dev_markers: Vec<String>,
+}
+
+impl Default for LintConfig {
+    fn default() -> Self {
+        Self::development()
+    }
+}
+
+impl LintConfig {
+    /// Every rule reports as a warning.
+    pub fn development() -> Self {
+        Self {
+            severities: LintRule::ALL.into_iter().map(|r| (r, Severity::Warning)).collect(),
+            baseline: None,
+            dev_markers: DEV_MARKERS.iter().map(|m| m.to_string()).collect(),
+        }
+    }
+
+    /// Every rule reports as an error.
+    pub fn production() -> Self {
+        let mut config = Self::development();
+        for s in config.severities.values_mut() {
+            *s = Severity::Error;
+        }
+        config
+    }
+
+    pub fn set(mut self, rule: LintRule, severity: Severity) -> Self {
+        self.severities.insert(rule, severity);
+        self
+    }
+
+    /// The reviewed shard that thresholds and guards are compared against.
+    pub fn with_baseline(mut self, baseline: Shard) -> Self {
+        self.baseline = Some(baseline);
+        self
+    }
+
+    /// Extra phrases for `dev-comment`, matched case-insensitively.
+    pub fn dev_marker(mut self, phrase: &str) -> Self {
+        self.dev_markers.push(phrase.to_lowercase());
+        self
+    }
+
+    pub fn severity(&self, rule: LintRule) -> Severity {
+        self.severities.get(&rule).copied().unwrap_or(Severity::Warning)
+    }
+}
+
+fn is_placeholder(s: &str) -> bool {
+    let s = s.trim();
+    let upper = s.to_uppercase();
+    (s.starts_with('<') && s.ends_with('>') && s.len() > 2)
+        || ["YOUR_", "YOUR-", "MY_"].iter().any(|p| upper.starts_with(p))
+        || PLACEHOLDER_MARKERS.iter().any(|m| upper.contains(m))
+}
+
+fn walk<'e>(e: &'e Expr, f: &mut impl FnMut(&'e Expr)) {
+    f(e);
+    match e {
+        Expr::Not(inner) | Expr::Neg(inner) => walk(inner, f),
+        Expr::Binary(_, l, r) => {
+            walk(l, f);
+            walk(r, f);
+        }
+        Expr::In(lhs, items) => {
+            walk(lhs, f);
+            for i in items {
+                walk(i, f);
+            }
+        }
+        Expr::Number(_) | Expr::Str(_) | Expr::Bool(_) | Expr::Ident(_) => {}
+    }
+}
+
+fn rule_exprs(rule: &Rule) -> Vec<&Expr> {
+    let mut out: Vec<&Expr> = rule.assignments().iter().map(|a| &a.value).collect();
+    if let Rule::Conditional { condition, .. } = rule {
+        out.push(condition);
+    }
+    out
+}
+
+fn parsed_rules(shard: &Shard) -> Vec<(&Row, Rule)> {
+    shard
+        .rows()
+        .filter(|(_, r)| r.kind.is_rule())
+        .filter_map(|(_, r)| parse_rule(&r.value).ok().map(|rule| (r, rule)))
+        .collect()
+}
+
+/// Which way a threshold moves to become more permissive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Permissive {
+    /// A floor (`x >= t`): lowering it lets more through.
+    Lower,
+    /// A ceiling (`x <= t`): raising it lets more through.
+    Higher,
+}
+
+/// Threshold directions from unconditional guard rules such as
+/// `allowed = (x >= emin) AND (r <= rmax)`.
+fn threshold_directions<'a>(rules: impl Iterator<Item = &'a Rule>) -> BTreeMap<String, Permissive> {
+    let mut out = BTreeMap::new();
+    for rule in rules {
+        let Rule::Assign(assignments) = rule else { continue };
+        for a in assignments {
+            walk(&a.value, &mut |e| {
+                let Expr::Binary(op, l, r) = e else { return };
+                let floor_if_right = match op {
+                    BinOp::Ge | BinOp::Gt => true,
+                    BinOp::Le | BinOp::Lt => false,
+                    _ => return,
+                };
+                // `value >= threshold` is the usual order; `threshold <= value` also works.
+                let (t, floor) = match (&**l, &**r) {
+                    (_, Expr::Ident(t)) => (t, floor_if_right),
+                    (Expr::Ident(t), _) => (t, !floor_if_right),
+                    _ => return,
+                };
+                let dir = if floor { Permissive::Lower } else { Permissive::Higher };
+                out.entry(t.clone()).or_insert(dir);
+            });
+        }
+    }
+    out
+}
+
+/// `in` lists per left-hand field, keyed by the assignment target.
+fn guard_sets(rules: &[(&Row, Rule)]) -> BTreeMap<(String, String), BTreeSet<String>> {
+    let mut out: BTreeMap<(String, String), BTreeSet<String>> = BTreeMap::new();
+    for (_, rule) in rules {
+        for a in rule.assignments() {
+            walk(&a.value, &mut |e| {
+                if let Expr::In(lhs, items) = e {
+                    if let Expr::Ident(field) = &**lhs {
+                        out.entry((a.target.clone(), field.clone()))
+                            .or_default()
+                            .extend(items.iter().map(|i| i.to_string()));
+                    }
+                }
+            });
+        }
+    }
+    out
+}
+
+pub fn lint(shard: &Shard, config: &LintConfig) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    let mut report = |rule: LintRule, span: Span, message: String| {
+        let severity = config.severity(rule);
+        if severity != Severity::Off {
+            findings.push(Finding {
+                rule,
+                severity,
+                span,
+                message,
+            });
+        }
+    };
+    let dev_marker = |text: &str| {
+        let lower = text.to_lowercase();
+        config.dev_markers.iter().find(|m| lower.contains(m.as_str())).cloned()
+    };
+
+    for item in &shard.items {
+        if let ItemKind::Comment { text, .. } = &item.kind {
+            if let Some(m) = dev_marker(text) {
+                report(LintRule::DevComment, item.span, format!("comment says \"{}\": {}", m, text.trim()));
+            }
+        }
+    }
+
+    let baseline = config.baseline.as_ref();
+    let primary_keys: BTreeSet<&str> = shard
+        .rows()
+        .chain(baseline.into_iter().flat_map(|b| b.rows()))
+        .filter(|(_, r)| r.has_constraint("primarykey"))
+        .map(|(_, r)| r.key.as_str())
+        .collect();
+    let is_identity = |field: &str| {
+        primary_keys.contains(field) || (shard.row(field).is_none() && field.to_lowercase().ends_with("id"))
+    };
+
+    let rules = parsed_rules(shard);
+    for (_, row) in shard.rows() {
+        if let Some(m) = dev_marker(&row.notes) {
+            report(LintRule::DevComment, row.spans.notes.unwrap_or(row.spans.row), format!("{}: notes say \"{}\"", row.key, m));
+        }
+        let lower = |s: &str| s.to_lowercase();
+        if lower(&row.field) == "override"
+            || lower(&row.entity_type) == "override"
+            || ["override", "bypass"].iter().any(|w| lower(&row.key).contains(w))
+        {
+            report(LintRule::OverrideRow, row.spans.key, format!("{} is an override row", row.key));
+        }
+        if !row.kind.is_rule() && row.datatype.as_ref().is_none_or(|d| *d == DataType::String) && is_placeholder(&row.value) {
+            report(LintRule::PlaceholderId, row.spans.value, format!("{} = `{}` is a placeholder", row.key, row.value));
+        }
+    }
+
+    for (row, rule) in &rules {
+        for e in rule_exprs(rule) {
+            walk(e, &mut |e| match e {
+                Expr::Str(s) if is_placeholder(s) => {
+                    report(LintRule::PlaceholderId, row.spans.value, format!("{}: placeholder literal '{}'", row.key, s));
+                }
+                Expr::Binary(BinOp::Eq, l, r) => {
+                    let keyed = match (&**l, &**r) {
+                        (Expr::Ident(f), Expr::Str(v)) | (Expr::Str(v), Expr::Ident(f)) if is_identity(f) => Some((f, v)),
+                        _ => None,
+                    };
+                    if let Some((f, v)) = keyed {
+                        report(
+                            LintRule::OverrideRow,
+                            row.spans.value,
+                            format!("{}: applies only when {} == '{}'", row.key, f, v),
+                        );
+                    }
+                }
+                _ => {}
+            });
+        }
+    }
+
+    let Some(baseline) = baseline else {
+        findings.sort_by_key(|f| f.span);
+        return findings;
+    };
+    let baseline_rules = parsed_rules(baseline);
+    let directions = threshold_directions(baseline_rules.iter().chain(&rules).map(|(_, r)| r));
+    for (_, row) in shard.rows().filter(|(_, r)| !r.kind.is_rule()) {
+        let (Some(new), Some(old)) = (row.float_value(), baseline.row(&row.key).and_then(Row::float_value)) else {
+            continue;
+        };
+        if new == old {
+            continue;
+        }
+        let message = match directions.get(&row.key) {
+            Some(Permissive::Lower) if new < old => format!("{} lowered from {} to {} (floor)", row.key, old, new),
+            Some(Permissive::Higher) if new > old => format!("{} raised from {} to {} (ceiling)", row.key, old, new),
+            Some(_) => continue,
+            None => format!("{} changed from {} to {}; no guard shows which way is safer", row.key, old, new),
+        };
+        report(LintRule::LoosenedThreshold, row.spans.value, message);
+    }
+
+    let before = guard_sets(&baseline_rules);
+    for (row, rule) in &rules {
+        for ((target, field), items) in guard_sets(&[(*row, rule.clone())]) {
+            let Some(old) = before.get(&(target.clone(), field.clone())) else { continue };
+            let added: Vec<&String> = items.difference(old).collect();
+            if !added.is_empty() {
+                let added = added.iter().map(|s| s.as_str()).collect::<Vec<_>>().join(",");
+                report(
+                    LintRule::WidenedGuard,
+                    row.spans.value,
+                    format!("{}: {} for {} now also admits {}", row.key, field, target, added),
+                );
+            }
+        }
+    }
+
+    findings.sort_by_key(|f| f.span);
+    findings
+}
+
+pub fn has_errors(findings: &[Finding]) -> bool {
+    findings.iter().any(|f| f.severity == Severity::Error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const BASELINE: &str = "\
+SECTION,SUBJECT
+ROW,subject,subject,scalar,subjectid,u-1029,string,primarykey,ID
+ROW,subject,subject,enum,stage,wake,string,wake,N1,N2,N3,Stage
+ROW,subject,subject,scalar,risk,0.0,float,range0,1,Risk
+ROW,subject,subject,scalar,score,0.0,float,range0,1,Score
+ROW,derived,session,scalar,emin,0.5,float,range0,1,Floor
+ROW,derived,session,scalar,rmax,0.35,float,range0,1,Ceiling
+ROW,derived,session,scalar,gain,1.0,float,,Gain
+ROW,derived,session,flag,allowed,false,bool,nonnull,Guard
+SECTION,RULES
+ROW,rule,rule,condition,guard,\"allowed = (stage in N2,N3) AND (score >= emin) AND (risk <= rmax)\",string,readonly,Roaming guard
+";
+
+    /// Findings for `BASELINE` with `from` replaced by `to`, linted against the baseline.
+    fn findings(from: &str, to: &str) -> Vec<Finding> {
+        assert!(BASELINE.contains(from), "{}", from);
+        let shard = parse(&BASELINE.replacen(from, to, 1)).unwrap();
+        lint(&shard, &LintConfig::development().with_baseline(parse(BASELINE).unwrap()))
+    }
+
+    fn rules_of(findings: &[Finding]) -> Vec<LintRule> {
+        findings.iter().map(|f| f.rule).collect()
+    }
+
+    #[test]
+    fn the_baseline_is_clean() {
+        assert_eq!(findings("u-1029", "u-1029"), []);
+        assert_eq!(lint(&parse(BASELINE).unwrap(), &LintConfig::production()), []);
+    }
+
+    #[test]
+    fn placeholder_ids() {
+        let found = findings("u-1029", "YOUR_DEV_ID");
+        assert_eq!(rules_of(&found), [LintRule::PlaceholderId]);
+        assert_eq!(found[0].to_string(), "2:38: warning[placeholder-id]: subjectid = `YOUR_DEV_ID` is a placeholder");
+        assert_eq!(
+            rules_of(&findings("(stage in N2,N3)", "(stage in N2,N3) AND (subjectid != '<subject>')")),
+            [LintRule::PlaceholderId]
+        );
+
+        assert_eq!(findings("u-1029", "u-2001"), []);
+    }
+
+    #[test]
+    fn override_rows() {
+        let found = findings("Roaming guard\n", "Roaming guard\nROW,rule,rule,condition,devBypass,\"if risk > rmax then allowed = true\",string,readonly,\n");
+        assert_eq!(rules_of(&found), [LintRule::OverrideRow]);
+        assert!(found[0].message.contains("devBypass is an override row"), "{}", found[0]);
+
+        let found = findings("(stage in N2,N3)", "(subjectid == 'u-1029' OR stage in N2,N3)");
+        assert_eq!(rules_of(&found), [LintRule::OverrideRow]);
+        assert!(found[0].message.contains("applies only when subjectid == 'u-1029'"), "{}", found[0]);
+
+        // Comparing a declared, non-key field to a literal is ordinary logic.
+        assert_eq!(findings("(stage in N2,N3)", "(stage == 'N2')"), []);
+    }
+
+    #[test]
+    fn loosened_thresholds() {
+        let found = findings("emin,0.5", "emin,0.4");
+        assert_eq!(rules_of(&found), [LintRule::LoosenedThreshold]);
+        assert_eq!(found[0].message, "emin lowered from 0.5 to 0.4 (floor)");
+        let found = findings("rmax,0.35", "rmax,0.5");
+        assert_eq!(found[0].message, "rmax raised from 0.35 to 0.5 (ceiling)");
+        let found = findings("gain,1.0", "gain,2.0");
+        assert!(found[0].message.contains("no guard shows which way is safer"), "{}", found[0]);
+
+        assert_eq!(findings("emin,0.5", "emin,0.6"), []);
+        assert_eq!(findings("rmax,0.35", "rmax,0.3"), []);
+    }
+
+    #[test]
+    fn widened_guards() {
+        let found = findings("stage in N2,N3", "stage in N1,N2,N3");
+        assert_eq!(rules_of(&found), [LintRule::WidenedGuard]);
+        assert_eq!(found[0].message, "guard: stage for allowed now also admits N1");
+
+        assert_eq!(findings("stage in N2,N3", "stage in N3"), []);
+    }
+
+    #[test]
+    fn dev_comments() {
+        let found = findings("SECTION,RULES\n", "SECTION,RULES\n# Temporarily allow N1 for the demo\n");
+        assert_eq!(rules_of(&found), [LintRule::DevComment]);
+        assert_eq!(found[0].span, Span { line: 11, column: 1 });
+        let found = findings("Gain\n", "DEV ONLY gain\n");
+        assert_eq!(found[0].message, "gain: notes say \"dev only\"");
+
+        assert_eq!(findings("SECTION,RULES\n", "SECTION,RULES\n# Reviewed 2026-01-25\n"), []);
+        let shard = parse(&BASELINE.replacen("Gain\n", "Lab bench gain\n", 1)).unwrap();
+        let custom = LintConfig::development().dev_marker("Lab Bench");
+        assert_eq!(rules_of(&lint(&shard, &custom)), [LintRule::DevComment]);
+    }
+
+    #[test]
+    fn severities_follow_the_config() {
+        let shard = parse(&BASELINE.replacen("u-1029", "CHANGEME", 1)).unwrap();
+        assert!(!has_errors(&lint(&shard, &LintConfig::development())));
+        assert!(has_errors(&lint(&shard, &LintConfig::production())));
+        let quiet = LintConfig::production().set(LintRule::PlaceholderId, Severity::Off);
+        assert_eq!(lint(&shard, &quiet), []);
+    }
+}
@@ -0,0 +1,446 @@
+//! Shard version migrations.
+//!
+//! A shard's family and version come from its destination path
+//! (`xr-grid.quantum-roaming-debug.v2.aln` is family
+//! `xr-grid.quantum-roaming-debug`, version 2). Each `Migration` takes one
+//! family from version N to N+1, either declaratively (rename, add, remove,
+//! split) or with a Rust transform; the registry chains them and keeps the
+//! minimum version loaders accept per family.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::ast::{Item, ItemKind, Row, Shard, Span};
+use crate::error::AlnError;
+use crate::expr::rename_ident;
+use crate::overlay::insert_row;
+
+/// One edit inside a migration.
+#[derive(Clone)]
+pub enum Step {
+    /// Rename a row key and every reference to it in rule rows.
+    RenameKey { from: String, to: String },
+    /// Add a row (full `ROW,...` text) at the end of `section`, unless the key
+    /// is already present.
+    AddRow { section: String, row: String },
+    RemoveRow { key: String },
+    /// Replace one rule row with several, keeping its other columns.
+    SplitRule { key: String, into: Vec<(String, String)> },
+    /// Move the shard to a new family name.
+    RenameShard(String),
+    Transform {
+        description: String,
+        apply: fn(&mut Shard) -> Result<(), String>,
+    },
+}
+
+impl fmt::Debug for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Step::RenameKey { from, to } => write!(f, "RenameKey({} -> {})", from, to),
+            Step::AddRow { section, row } => write!(f, "AddRow({}: {})", section, row),
+            Step::RemoveRow { key } => write!(f, "RemoveRow({})", key),
+            Step::SplitRule { key, into } => write!(f, "SplitRule({} -> {:?})", key, into),
+            Step::RenameShard(name) => write!(f, "RenameShard({})", name),
+            Step::Transform { description, .. } => write!(f, "Transform({})", description),
+        }
+    }
+}
+
+/// Upgrades a family from version `from` to `from + 1`.
+#[derive(Debug, Clone)]
+pub struct Migration {
+    pub from: u32,
+    pub description: String,
+    pub steps: Vec<Step>,
+}
+
+impl Migration {
+    pub fn new(from: u32, description: &str) -> Self {
+        Self {
+            from,
+            description: description.to_string(),
+            steps: Vec::new(),
+        }
+    }
+
+    pub fn rename_key(mut self, from: &str, to: &str) -> Self {
+        self.steps.push(Step::RenameKey {
+            from: from.to_string(),
+            to: to.to_string(),
+        });
+        self
+    }
+
+    pub fn add_row(mut self, section: &str, row: &str) -> Self {
+        self.steps.push(Step::AddRow {
+            section: section.to_string(),
+            row: row.to_string(),
+        });
+        self
+    }
+
+    pub fn remove_row(mut self, key: &str) -> Self {
+        self.steps.push(Step::RemoveRow { key: key.to_string() });
+        self
+    }
+
+    pub fn split_rule(mut self, key: &str, into: &[(&str, &str)]) -> Self {
+        self.steps.push(Step::SplitRule {
+            key: key.to_string(),
+            into: into.iter().map(|(k, e)| (k.to_string(), e.to_string())).collect(),
+        });
+        self
+    }
+
+    pub fn rename_shard(mut self, family: &str) -> Self {
+        self.steps.push(Step::RenameShard(family.to_string()));
+        self
+    }
+
+    pub fn transform(mut self, description: &str, apply: fn(&mut Shard) -> Result<(), String>) -> Self {
+        self.steps.push(Step::Transform {
+            description: description.to_string(),
+            apply,
+        });
+        self
+    }
+}
+
+/// Family and version from a `<family>.v<N>.aln` destination path.
+pub fn family_version(shard: &Shard) -> Option<(String, u32)> {
+    Some((shard.family()?.to_string(), shard.version()?))
+}
+
+fn set_destination(shard: &mut Shard, family: &str, version: u32) {
+    for item in &mut shard.items {
+        if let ItemKind::DestinationPath(p) = &mut item.kind {
+            *p = format!("{}.v{}.aln", family, version);
+        }
+    }
+}
+
+fn row_index(shard: &Shard, key: &str) -> Option<usize> {
+    shard
+        .items
+        .iter()
+        .position(|i| matches!(&i.kind, ItemKind::Row(r) if r.key == key))
+}
+
+fn rule_rows(shard: &mut Shard) -> impl Iterator<Item = &mut Row> {
+    shard.items.iter_mut().filter_map(|i| match &mut i.kind {
+        ItemKind::Row(r) if r.kind.is_rule() => Some(&mut **r),
+        _ => None,
+    })
+}
+
+/// Apply one step; returns the changelog line, or an error naming the step.
+fn apply_step(shard: &mut Shard, family: &mut String, step: &Step) -> Result<String, String> {
+    match step {
+        Step::RenameKey { from, to } => {
+            let idx = row_index(shard, from).ok_or_else(|| format!("no row `{}` to rename", from))?;
+            if row_index(shard, to).is_some() {
+                return Err(format!("cannot rename `{}`: `{}` already exists", from, to));
+            }
+            if let ItemKind::Row(r) = &mut shard.items[idx].kind {
+                r.key = to.clone();
+            }
+            let mut rewritten = 0;
+            for rule in rule_rows(shard) {
+                let renamed = rename_ident(&rule.value, from, to).map_err(|e| format!("rule `{}`: {}", rule.key, e))?;
+                if renamed != rule.value {
+                    rule.value = renamed;
+                    rewritten += 1;
+                }
+            }
+            Ok(format!("renamed `{}` to `{}` ({} rule(s) updated)", from, to, rewritten))
+        }
+        Step::AddRow { section, row } => {
+            let parsed = crate::parser::parse(row).map_err(|e| format!("row `{}`: {}", row, e))?;
+            let Some(ItemKind::Row(new)) = parsed.items.into_iter().next().map(|i| i.kind) else {
+                return Err(format!("`{}` is not a ROW line", row));
+            };
+            if row_index(shard, &new.key).is_some() {
+                return Ok(format!("kept existing `{}`", new.key));
+            }
+            let section_item = Item {
+                kind: ItemKind::Section(section.clone()),
+                span: Span::default(),
+            };
+            insert_row(&mut shard.items, Some(&section_item), &new);
+            Ok(format!("added `{}` = {} in {}", new.key, new.value, section))
+        }
+        Step::RemoveRow { key } => {
+            let idx = row_index(shard, key).ok_or_else(|| format!("no row `{}` to remove", key))?;
+            shard.items.remove(idx);
+            Ok(format!("removed `{}`", key))
+        }
+        Step::SplitRule { key, into } => {
+            let idx = row_index(shard, key).ok_or_else(|| format!("no rule `{}` to split", key))?;
+            let ItemKind::Row(rule) = &shard.items[idx].kind else { unreachable!() };
+            if !rule.kind.is_rule() {
+                return Err(format!("`{}` is not a rule row", key));
+            }
+            let parts: Vec<Item> = into
+                .iter()
+                .map(|(k, expr)| Item {
+                    kind: ItemKind::Row(Box::new(Row {
+                        key: k.clone(),
+                        value: expr.clone(),
+                        ..(**rule).clone()
+                    })),
+                    span: shard.items[idx].span,
+                })
+                .collect();
+            shard.items.splice(idx..=idx, parts);
+            let names: Vec<&str> = into.iter().map(|(k, _)| k.as_str()).collect();
+            Ok(format!("split rule `{}` into {}", key, names.join(", ")))
+        }
+        Step::RenameShard(name) => {
+            let old = std::mem::replace(family, name.clone());
+            Ok(format!("renamed shard family `{}` to `{}`", old, name))
+        }
+        Step::Transform { description, apply } => {
+            apply(shard)?;
+            Ok(description.clone())
+        }
+    }
+}
+
+/// Parse `path` and refuse it if it is older than the built-in minimum for
+/// its family. Every loader goes through this.
+pub fn load_checked(path: impl AsRef<std::path::Path>) -> Result<Shard, AlnError> {
+    let shard = crate::parser::parse_file(path)?;
+    MigrationRegistry::builtin().check_version(&shard)?;
+    Ok(shard)
+}
+
+/// A migrated shard and what was done to it, oldest migration first.
+#[derive(Debug, Clone)]
+pub struct Migrated {
+    pub shard: Shard,
+    pub from: u32,
+    pub to: u32,
+    pub changelog: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MigrationRegistry {
+    migrations: BTreeMap<(String, u32), Migration>,
+    minimum: BTreeMap<String, u32>,
+}
+
+impl MigrationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(mut self, family: &str, migration: Migration) -> Self {
+        self.migrations.insert((family.to_string(), migration.from), migration);
+        self
+    }
+
+    /// Oldest version of `family` that loaders accept.
+    pub fn minimum(mut self, family: &str, version: u32) -> Self {
+        self.minimum.insert(family.to_string(), version);
+        self
+    }
+
+    /// Migrations for the shards that ship with this repository.
+    pub fn builtin() -> Self {
+        const QRD: &str = "xr-grid.quantum-roaming-debug";
+        let v1_to_v2 = Migration::new(1, "xr-safety template to the logged quantum-roaming shard")
+            .rename_shard(QRD)
+            .add_row("SUBJECT-STATE", "ROW,subject,subject,scalar,subjectid,,string,primarykey,Augmented user ID")
+            .add_row(
+                "SUBJECT-STATE",
+                "ROW,subject,subject,enum,player_state,observer_only,string,observer_only,active_roaming,quantum_roaming,Logical player state",
+            )
+            .add_row("DERIVED", "ROW,derived,session,scalar,eligibilityE,0.0,float,range0,1,E = S(1-R)Es")
+            .add_row(
+                "DERIVED",
+                "ROW,derived,session,flag,quantum_roaming_allowed,false,bool,nonnull,True when roaming guard passes",
+            )
+            .add_row("LOGGING", "ROW,log,log,scalar,logid,0,int,auto,Log entry ID")
+            .add_row("LOGGING", "ROW,log,log,enum,logtype,debug,string,debug,info,warn,error,Log type")
+            .add_row("LOGGING", "ROW,log,log,scalar,message,,string,nonnull,Log message")
+            .add_row(
+                "RUNTIME-RULES",
+                "ROW,rule,rule,expression,computeE,\"eligibilityE = sleeptoken * (1.0 - psychriskscore) * enstasisscore\",string,readonly,Safety vector",
+            )
+            .add_row(
+                "RUNTIME-RULES",
+                "ROW,rule,rule,condition,allowQuantumRoam,\"quantum_roaming_allowed = (sleepstage in N2,N3) AND (eligibilityE >= emin) AND (psychriskscore <= rmax)\",string,readonly,Guard for roaming",
+            )
+            .add_row(
+                "RUNTIME-RULES",
+                "ROW,rule,rule,condition,forceObserverOnHighRisk,\"if psychriskscore > rmax then player_state = observer_only\",string,readonly,High-risk clamp",
+            )
+            .add_row(
+                "RUNTIME-RULES",
+                "ROW,rule,rule,condition,exitObserverOnSafe,\"if quantum_roaming_allowed and dreammode == quantum_consciousness then player_state = quantum_roaming\",string,readonly,Exit from observer_only when safe",
+            )
+            .add_row(
+                "RUNTIME-RULES",
+                "ROW,rule,rule,condition,logRoamingFailure,\"if dreammode == quantum_consciousness and player_state == observer_only and not quantum_roaming_allowed then logtype=debug, message='Quantum roaming disallowed: sleepstage=' + sleepstage + ', eligibilityE=' + eligibilityE + ', psychriskscore=' + psychriskscore\",string,readonly,Log why roaming is disallowed",
+            )
+            .add_row("NEURORIGHTS-GUARDS", "ROW,guard,policy,flag,mentalprivacy,true,bool,nonwaivable,No dream text/audio/images here")
+            .add_row(
+                "NEURORIGHTS-GUARDS",
+                "ROW,guard,policy,flag,cognitiveliberty,true,bool,nonwaivable,No coercive state forcing beyond safety",
+            )
+            .add_row("NEURORIGHTS-GUARDS", "ROW,guard,policy,flag,nopunitivexr,true,bool,nonwaivable,States not used for punishment")
+            .add_row("NEURORIGHTS-GUARDS", "ROW,guard,policy,flag,soulnonaddressable,true,bool,nonwaivable,No soul or belief fields");
+        Self::new()
+            .register("quantum-roaming-debug", v1_to_v2)
+            .minimum("quantum-roaming-debug", 2)
+            .minimum(QRD, 2)
+    }
+
+    /// Refuse shards older than their family's minimum. Shards without a
+    /// versioned destination path, or of unknown families, pass.
+    pub fn check_version(&self, shard: &Shard) -> Result<(), AlnError> {
+        let Some((family, version)) = family_version(shard) else {
+            return Ok(());
+        };
+        match self.minimum.get(&family) {
+            Some(&minimum) if version < minimum => Err(AlnError::Outdated {
+                name: shard.destination_path().unwrap_or_default().to_string(),
+                version,
+                minimum,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Upgrade `shard` one version at a time up to `target` (or as far as
+    /// registered migrations go). The destination path follows the version.
+    pub fn migrate(&self, shard: &Shard, target: Option<u32>) -> Result<Migrated, AlnError> {
+        let (mut family, from) = family_version(shard)
+            .ok_or_else(|| AlnError::Migration("shard has no `<name>.v<N>.aln` destination path".into()))?;
+        let mut shard = shard.clone();
+        let mut version = from;
+        let mut changelog = Vec::new();
+        while target.is_none_or(|t| version < t) {
+            let Some(m) = self.migrations.get(&(family.clone(), version)) else {
+                break;
+            };
+            changelog.push(format!("v{} -> v{}: {}", version, version + 1, m.description));
+            for step in &m.steps {
+                let line = apply_step(&mut shard, &mut family, step)
+                    .map_err(|e| AlnError::Migration(format!("v{} -> v{}: {}", version, version + 1, e)))?;
+                changelog.push(format!("  {}", line));
+            }
+            version += 1;
+            set_destination(&mut shard, &family, version);
+        }
+        if let Some(t) = target.filter(|t| version < *t) {
+            return Err(AlnError::Migration(format!(
+                "no migration from {} v{} (wanted v{})",
+                family, version, t
+            )));
+        }
+        // Fresh positions for the rows the steps added or moved.
+        let shard = crate::parser::parse(&crate::writer::write(&shard))?;
+        Ok(Migrated {
+            shard,
+            from,
+            to: version,
+            changelog,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const V1: &str = "\
+destination-path,quantum-roaming-debug.v1.aln
+QPU.Datashard,Quantum roaming vs observer_only state debug
+path,entitytype,field,key,value,datatype,constraints,notes
+SECTION,SUBJECT-STATE
+ROW,subject,subject,enum,sleepstage,wake,string,wake,N1,N2,N3,REM,Validated sleep stage
+ROW,subject,subject,scalar,sleeptoken,0.0,float,range0,1,S
+ROW,subject,subject,scalar,psychriskscore,0.0,float,range0,1,R
+ROW,subject,subject,scalar,enstasisscore,1.0,float,range0,1,Es
+ROW,subject,subject,enum,dreammode,passive,string,passive,active,quantum_consciousness,XR-only dream mode
+SECTION,DERIVED
+ROW,derived,session,scalar,emin,0.5,float,range0,1,Eligibility threshold
+ROW,derived,session,scalar,rmax,0.35,float,range0,1,Psych-risk roaming ceiling
+FOOTER,END-OF-SHARD
+";
+
+    const REPO_V2: &str = include_str!("../../../deepseek_aln_20260125_eab790.txt");
+
+    fn keys(shard: &Shard) -> Vec<String> {
+        let mut keys: Vec<String> = shard.rows().map(|(_, r)| r.key.clone()).collect();
+        keys.sort();
+        keys
+    }
+
+    #[test]
+    fn family_version_follows_shard_version() {
+        let shard = parse(REPO_V2).unwrap();
+        assert_eq!(
+            family_version(&shard),
+            Some(("xr-grid.quantum-roaming-debug".to_string(), shard.version().unwrap()))
+        );
+        assert_eq!(family_version(&parse("ROW,a,a,scalar,x,1,int,,\n").unwrap()), None);
+    }
+
+    #[test]
+    fn builtin_rejects_v1_and_accepts_the_repo_shard() {
+        let registry = MigrationRegistry::builtin();
+        match registry.check_version(&parse(V1).unwrap()) {
+            Err(AlnError::Outdated { version: 1, minimum: 2, .. }) => {}
+            other => panic!("expected Outdated, got {:?}", other),
+        }
+        registry.check_version(&parse(REPO_V2).unwrap()).unwrap();
+    }
+
+    #[test]
+    fn builtin_migrates_v1_to_the_repo_shard() {
+        let registry = MigrationRegistry::builtin();
+        let migrated = registry.migrate(&parse(V1).unwrap(), None).unwrap();
+        assert_eq!((migrated.from, migrated.to), (1, 2));
+        assert_eq!(migrated.shard.destination_path(), Some("xr-grid.quantum-roaming-debug.v2.aln"));
+        assert_eq!(keys(&migrated.shard), keys(&parse(REPO_V2).unwrap()));
+        registry.check_version(&migrated.shard).unwrap();
+    }
+
+    #[test]
+    fn chains_migrations_in_order() {
+        let registry = MigrationRegistry::new()
+            .register("demo", Migration::new(1, "rename limit").rename_key("limit", "ceiling"))
+            .register(
+                "demo",
+                Migration::new(2, "split the guard")
+                    .split_rule("guard", &[("guardLow", "ok = x >= 0"), ("guardHigh", "ok = x <= ceiling")])
+                    .remove_row("legacy"),
+            )
+            .minimum("demo", 3);
+        let v1 = parse(
+            "destination-path,demo.v1.aln\n\
+             ROW,derived,session,scalar,limit,5,int,,\n\
+             ROW,derived,session,scalar,legacy,0,int,,\n\
+             ROW,rule,rule,condition,guard,\"ok = x <= limit\",string,readonly,\n",
+        )
+        .unwrap();
+        assert!(registry.check_version(&v1).is_err());
+
+        let migrated = registry.migrate(&v1, None).unwrap();
+        assert_eq!((migrated.from, migrated.to), (1, 3));
+        assert_eq!(migrated.shard.destination_path(), Some("demo.v3.aln"));
+        assert_eq!(keys(&migrated.shard), ["ceiling", "guardHigh", "guardLow"]);
+        assert_eq!(migrated.shard.row("guardHigh").unwrap().value, "ok = x <= ceiling");
+        assert!(migrated.changelog[0].starts_with("v1 -> v2"));
+        assert!(migrated.changelog.iter().any(|l| l.starts_with("v2 -> v3")));
+        registry.check_version(&migrated.shard).unwrap();
+
+        // Stopping part-way, and asking for a version nothing migrates to.
+        assert_eq!(registry.migrate(&v1, Some(2)).unwrap().shard.destination_path(), Some("demo.v2.aln"));
+        assert!(matches!(registry.migrate(&v1, Some(4)), Err(AlnError::Migration(_))));
+    }
+}
@@ -0,0 +1,311 @@
+//! Overlay composition: a base shard plus ordered overlay fragments gives the
+//! effective shard. Rows are matched by key (keys are unique within a shard),
+//! later layers win, and every effective row remembers which file and line
+//! set it. Rows marked `immutable` or `nonwaivable` cannot be overridden.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::ast::{Item, ItemKind, Row, Shard, Span};
+use crate::diff::same_row;
+
+/// Where a row was set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Origin {
+    pub source: String,
+    pub line: usize,
+}
+
+impl fmt::Display for Origin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.source, self.line)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OverlayError {
+    pub origin: Origin,
+    pub key: String,
+    pub message: String,
+}
+
+impl fmt::Display for OverlayError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}: {}", self.origin, self.key, self.message)
+    }
+}
+
+/// The composed shard and, per key, every layer that set it, oldest first.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Effective {
+    pub shard: Shard,
+    provenance: BTreeMap<String, Vec<Origin>>,
+}
+
+impl Effective {
+    /// The layer whose value is in effect.
+    pub fn origin(&self, key: &str) -> Option<&Origin> {
+        self.provenance.get(key).and_then(|h| h.last())
+    }
+
+    /// Every layer that set `key`, oldest first.
+    pub fn history(&self, key: &str) -> &[Origin] {
+        self.provenance.get(key).map_or(&[], Vec::as_slice)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Composition {
+    layers: Vec<(String, Shard)>,
+}
+
+fn locked_by(row: &Row) -> Option<&'static str> {
+    ["immutable", "nonwaivable"].into_iter().find(|c| row.has_constraint(c))
+}
+
+fn row_index(items: &[Item], key: &str) -> Option<usize> {
+    items.iter().position(|i| matches!(&i.kind, ItemKind::Row(r) if r.key == key))
+}
+
+/// Put a new row at the end of `section`, opening the section before the
+/// footer if the effective shard does not have it yet.
+pub(crate) fn insert_row(items: &mut Vec<Item>, section: Option<&Item>, row: &Row) {
+    let item = Item {
+        kind: ItemKind::Row(Box::new(row.clone())),
+        span: row.spans.row,
+    };
+    let footer = items
+        .iter()
+        .position(|i| matches!(i.kind, ItemKind::Footer(_)))
+        .unwrap_or(items.len());
+    let last_row_before = |items: &[Item], end: usize| items[..end].iter().rposition(|i| matches!(i.kind, ItemKind::Row(_)));
+
+    let Some(section) = section else {
+        // Fragments without a SECTION line join rows of the same entity type.
+        let same_entity = items
+            .iter()
+            .rposition(|i| matches!(&i.kind, ItemKind::Row(r) if r.entity_type == row.entity_type));
+        let at = same_entity
+            .or_else(|| last_row_before(items, footer))
+            .map_or(footer, |i| i + 1);
+        items.insert(at, item);
+        return;
+    };
+    let start = items.iter().position(|i| i.kind == section.kind);
+    match start {
+        Some(start) => {
+            let end = items[start + 1..]
+                .iter()
+                .position(|i| matches!(i.kind, ItemKind::Section(_) | ItemKind::Footer(_)))
+                .map_or(items.len(), |p| start + 1 + p);
+            let at = last_row_before(items, end).filter(|i| *i > start).unwrap_or(start) + 1;
+            items.insert(at, item);
+        }
+        None => {
+            let block = [
+                Item {
+                    kind: ItemKind::Blank,
+                    span: Span::default(),
+                },
+                section.clone(),
+                item,
+            ];
+            items.splice(footer..footer, block);
+        }
+    }
+}
+
+impl Composition {
+    pub fn new(name: &str, base: Shard) -> Self {
+        Self {
+            layers: vec![(name.to_string(), base)],
+        }
+    }
+
+    /// Add the next overlay; later overlays take precedence.
+    pub fn overlay(mut self, name: &str, shard: Shard) -> Self {
+        self.layers.push((name.to_string(), shard));
+        self
+    }
+
+    /// Header and layout come from the base; overlay comments stay in their
+    /// own files. All refused overrides are reported together.
+    pub fn compose(&self) -> Result<Effective, Vec<OverlayError>> {
+        let (base_name, base) = &self.layers[0];
+        let mut items = base.items.clone();
+        let mut provenance: BTreeMap<String, Vec<Origin>> = BTreeMap::new();
+        for (_, row) in base.rows() {
+            provenance.entry(row.key.clone()).or_default().push(Origin {
+                source: base_name.clone(),
+                line: row.spans.row.line,
+            });
+        }
+
+        let mut errors = Vec::new();
+        for (name, overlay) in &self.layers[1..] {
+            let mut section: Option<&Item> = None;
+            for item in &overlay.items {
+                let row = match &item.kind {
+                    ItemKind::Section(_) => {
+                        section = Some(item);
+                        continue;
+                    }
+                    ItemKind::Row(row) => row,
+                    _ => continue,
+                };
+                let origin = Origin {
+                    source: name.clone(),
+                    line: row.spans.row.line,
+                };
+                match row_index(&items, &row.key) {
+                    Some(idx) => {
+                        let ItemKind::Row(current) = &items[idx].kind else { unreachable!() };
+                        if same_row(current, row) {
+                            continue;
+                        }
+                        if let Some(constraint) = locked_by(current) {
+                            let set_by = provenance.get(&row.key).and_then(|h| h.last());
+                            errors.push(OverlayError {
+                                origin,
+                                key: row.key.clone(),
+                                message: format!(
+                                    "cannot override {} row (set at {})",
+                                    constraint,
+                                    set_by.map(|o| o.to_string()).unwrap_or_default()
+                                ),
+                            });
+                            continue;
+                        }
+                        items[idx] = Item {
+                            kind: ItemKind::Row(row.clone()),
+                            span: row.spans.row,
+                        };
+                    }
+                    None => insert_row(&mut items, section, row),
+                }
+                provenance.entry(row.key.clone()).or_default().push(origin);
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(Effective {
+                shard: Shard { items },
+                provenance,
+            })
+        } else {
+            Err(errors)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+    use crate::writer::write;
+
+    const BASE: &str = "\
+destination-path,demo.v1.aln
+SECTION,DERIVED
+ROW,derived,session,scalar,emin,0.5,float,range0,1,Floor
+ROW,derived,session,scalar,rmax,0.35,float,range0,1,Ceiling
+SECTION,GUARDS
+ROW,guard,policy,flag,mentalprivacy,true,bool,nonwaivable,Guard
+FOOTER,END-OF-SHARD
+";
+
+    fn compose(overlays: &[(&str, &str)]) -> Result<Effective, Vec<OverlayError>> {
+        let mut c = Composition::new("base.aln", parse(BASE).unwrap());
+        for (name, text) in overlays {
+            c = c.overlay(name, parse(text).unwrap());
+        }
+        c.compose()
+    }
+
+    fn value(e: &Effective, key: &str) -> String {
+        e.shard.row(key).unwrap().value.clone()
+    }
+
+    #[test]
+    fn later_layers_take_precedence() {
+        let e = compose(&[
+            ("site.aln", "SECTION,DERIVED\nROW,derived,session,scalar,emin,0.6,float,range0,1,Floor"),
+            ("ward.aln", "# ward\nSECTION,DERIVED\nROW,derived,session,scalar,emin,0.7,float,range0,1,Floor"),
+        ])
+        .unwrap();
+        assert_eq!(value(&e, "emin"), "0.7");
+        assert_eq!(value(&e, "rmax"), "0.35");
+        assert_eq!(e.origin("emin").unwrap().to_string(), "ward.aln:3");
+        let history: Vec<String> = e.history("emin").iter().map(|o| o.to_string()).collect();
+        assert_eq!(history, ["base.aln:3", "site.aln:2", "ward.aln:3"]);
+        assert_eq!(e.origin("rmax").unwrap().to_string(), "base.aln:4");
+        assert!(e.history("missing").is_empty());
+
+        // Restating a row unchanged does not take it over.
+        let e = compose(&[("same.aln", "ROW,derived,session,scalar,rmax,0.35,float,range0,1,Ceiling")]).unwrap();
+        assert_eq!(e.history("rmax").len(), 1);
+    }
+
+    #[test]
+    fn locked_rows_conflict_with_overrides() {
+        let errors = compose(&[(
+            "site.aln",
+            "\
+ROW,guard,policy,flag,mentalprivacy,false,bool,,Waived
+ROW,derived,session,scalar,emin,0.4,float,range0,1,Floor",
+        )])
+        .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].to_string(),
+            "site.aln:1: mentalprivacy: cannot override nonwaivable row (set at base.aln:6)"
+        );
+
+        // A lock added by one overlay binds every later one; all refusals are reported.
+        let errors = compose(&[
+            ("lock.aln", "ROW,derived,session,scalar,rmax,0.3,float,immutable,range0,1,Locked"),
+            ("late.aln", "ROW,derived,session,scalar,rmax,0.5,float,range0,1,Ceiling\nROW,guard,policy,flag,mentalprivacy,false,bool,nonwaivable,Guard"),
+        ])
+        .unwrap_err();
+        let shown: Vec<String> = errors.iter().map(|e| e.to_string()).collect();
+        assert_eq!(
+            shown,
+            [
+                "late.aln:1: rmax: cannot override immutable row (set at lock.aln:1)",
+                "late.aln:2: mentalprivacy: cannot override nonwaivable row (set at base.aln:6)",
+            ]
+        );
+    }
+
+    #[test]
+    fn new_rows_land_in_their_section() {
+        let e = compose(&[(
+            "site.aln",
+            "\
+SECTION,DERIVED
+ROW,derived,session,scalar,emax,0.9,float,range0,1,Cap
+SECTION,LOGGING
+ROW,log,log,scalar,logid,0,int,auto,Log ID
+ROW,guard,policy,flag,nopunitivexr,true,bool,nonwaivable,Guard",
+        )])
+        .unwrap();
+        let keys: Vec<(Option<&str>, &str)> = e.shard.rows().map(|(section, r)| (section, r.key.as_str())).collect();
+        assert_eq!(
+            keys,
+            [
+                (Some("DERIVED"), "emin"),
+                (Some("DERIVED"), "rmax"),
+                (Some("DERIVED"), "emax"),
+                (Some("GUARDS"), "mentalprivacy"),
+                (Some("LOGGING"), "logid"),
+                (Some("LOGGING"), "nopunitivexr"),
+            ]
+        );
+        assert!(write(&e.shard).ends_with("SECTION,LOGGING\nROW,log,log,scalar,logid,0,int,auto,Log ID\nROW,guard,policy,flag,nopunitivexr,true,bool,nonwaivable,Guard\nFOOTER,END-OF-SHARD\n"));
+
+        // Without a SECTION line a row joins its entity type.
+        let e = compose(&[("frag.aln", "ROW,derived,session,scalar,emax,0.9,float,range0,1,Cap")]).unwrap();
+        let keys: Vec<&str> = e.shard.rows().map(|(_, r)| r.key.as_str()).collect();
+        assert_eq!(keys, ["emin", "rmax", "emax", "mentalprivacy"]);
+    }
+}
@@ -0,0 +1,279 @@
+use std::path::Path;
+
+use crate::ast::{DataType, Item, ItemKind, Row, RowKind, RowSpans, Shard, Span};
+use crate::error::{AlnError, ParseError};
+
+/// One comma-separated cell and the column it started at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cell {
+    pub text: String,
+    pub column: usize,
+    pub quoted: bool,
+}
+
+/// Split one line into cells. `"..."` cells may contain commas; `""` is a
+/// literal quote inside them.
+pub fn split_cells(line: &str, lineno: usize) -> Result<Vec<Cell>, ParseError> {
+    let chars: Vec<char> = line.chars().collect();
+    let mut cells = Vec::new();
+    let mut i = 0;
+    loop {
+        let column = i + 1;
+        let mut text = String::new();
+        let quoted = chars.get(i) == Some(&'"');
+        if quoted {
+            i += 1;
+            loop {
+                match chars.get(i) {
+                    None => return Err(ParseError::new(lineno, column, "unterminated quoted field")),
+                    Some('"') if chars.get(i + 1) == Some(&'"') => {
+                        text.push('"');
+                        i += 2;
+                    }
+                    Some('"') => {
+                        i += 1;
+                        break;
+                    }
+                    Some(c) => {
+                        text.push(*c);
+                        i += 1;
+                    }
+                }
+            }
+            if !matches!(chars.get(i), None | Some(',')) {
+                return Err(ParseError::new(lineno, i + 1, "expected `,` after closing quote"));
+            }
+        } else {
+            while let Some(c) = chars.get(i).filter(|c| **c != ',') {
+                if *c == '"' {
+                    return Err(ParseError::new(lineno, i + 1, "stray `\"` inside unquoted field"));
+                }
+                text.push(*c);
+                i += 1;
+            }
+        }
+        cells.push(Cell { text, column, quoted });
+        if chars.get(i).is_none() {
+            return Ok(cells);
+        }
+        i += 1; // the comma
+    }
+}
+
+/// Whether the last constraint is a `range<lo>` whose `<hi>` cell is missing.
+fn ends_with_open_range(constraints: &[Cell]) -> bool {
+    let mut open = false;
+    for c in constraints {
+        open = !open && c.text.strip_prefix("range").is_some_and(|lo| lo.parse::<f64>().is_ok());
+    }
+    open
+}
+
+fn parse_row(cells: &[Cell], lineno: usize) -> Result<Row, ParseError> {
+    // cells[0] is `ROW`
+    let body = &cells[1..];
+    if body.len() < 5 {
+        let col = cells.last().map_or(1, |c| c.column + c.text.chars().count());
+        return Err(ParseError::new(
+            lineno,
+            col,
+            format!("ROW needs at least entitytype,field,kind,key,value (found {} cells)", body.len()),
+        ));
+    }
+    // Spans point at the cell content, past any opening quote.
+    let span = |c: &Cell| Span {
+        line: lineno,
+        column: c.column + usize::from(c.quoted),
+    };
+    for (idx, name) in [(0, "entitytype"), (1, "field"), (2, "kind")] {
+        if body[idx].text.trim().is_empty() {
+            return Err(ParseError::new(lineno, body[idx].column, format!("empty {}", name)));
+        }
+    }
+    if body[3].text.trim().is_empty() {
+        return Err(ParseError::new(lineno, body[3].column, "empty key"));
+    }
+
+    let kind = RowKind::parse(&body[2].text);
+    // Rows with notes but no datatype carry an empty datatype cell.
+    let (datatype, constraints, notes) = match body.len() {
+        5 => (None, &body[5..], None),
+        6 => (Some(&body[5]), &body[6..], None),
+        // A `range<lo>` still waiting for its bound takes the last cell:
+        // the row has constraints but no notes.
+        n if kind != RowKind::Enum && ends_with_open_range(&body[6..n - 1]) => (Some(&body[5]), &body[6..], None),
+        n => (Some(&body[5]), &body[6..n - 1], Some(&body[n - 1])),
+    };
+    let datatype = datatype.filter(|c| !c.text.is_empty());
+    let constraints: Vec<&Cell> = constraints.iter().filter(|c| !c.text.is_empty()).collect();
+    Ok(Row {
+        entity_type: body[0].text.clone(),
+        field: body[1].text.clone(),
+        kind,
+        key: body[3].text.clone(),
+        value: body[4].text.clone(),
+        datatype: datatype.map(|c| DataType::parse(&c.text)),
+        constraints: constraints.iter().map(|c| c.text.clone()).collect(),
+        notes: notes.map(|c| c.text.clone()).unwrap_or_default(),
+        spans: RowSpans {
+            row: Span { line: lineno, column: 1 },
+            key: span(&body[3]),
+            value: span(&body[4]),
+            datatype: datatype.map(span),
+            constraints: constraints.iter().map(|c| span(c)).collect(),
+            notes: notes.map(span),
+        },
+    })
+}
+
+fn parse_line(line: &str, lineno: usize) -> Result<ItemKind, ParseError> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() {
+        return Ok(ItemKind::Blank);
+    }
+    if let Some(text) = trimmed.strip_prefix('#') {
+        let indent = &line[..line.len() - trimmed.len()];
+        return Ok(ItemKind::Comment {
+            indent: indent.to_string(),
+            text: text.to_string(),
+        });
+    }
+    let cells = split_cells(line, lineno)?;
+    let first = cells[0].text.as_str();
+    let rest = |n: usize| -> Result<&Cell, ParseError> {
+        cells
+            .get(n)
+            .ok_or_else(|| ParseError::new(lineno, line.chars().count() + 1, format!("`{}` needs a value", first)))
+    };
+    match first {
+        "ROW" => parse_row(&cells, lineno).map(|r| ItemKind::Row(Box::new(r))),
+        "SECTION" => Ok(ItemKind::Section(rest(1)?.text.clone())),
+        "FOOTER" => Ok(ItemKind::Footer(rest(1)?.text.clone())),
+        "destination-path" => Ok(ItemKind::DestinationPath(rest(1)?.text.clone())),
+        "path" => Ok(ItemKind::Header(cells.iter().map(|c| c.text.clone()).collect())),
+        _ if first.ends_with(".Datashard") => {
+            let description = rest(1)?;
+            Ok(ItemKind::Datashard {
+                kind: first.trim_end_matches(".Datashard").to_string(),
+                description: line.chars().skip(description.column - 1).collect(),
+            })
+        }
+        _ => Err(ParseError::new(
+            lineno,
+            cells[0].column,
+            format!("unknown line type `{}` (expected ROW, SECTION, FOOTER, a header or a comment)", first),
+        )),
+    }
+}
+
+pub fn parse(source: &str) -> Result<Shard, ParseError> {
+    let mut items = Vec::new();
+    for (idx, line) in source.lines().enumerate() {
+        let lineno = idx + 1;
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        items.push(Item {
+            kind: parse_line(line, lineno)?,
+            span: Span { line: lineno, column: 1 },
+        });
+    }
+    Ok(Shard { items })
+}
+
+pub fn parse_named(name: &str, source: &str) -> Result<Shard, ParseError> {
+    parse(source).map_err(|e| e.with_source(name))
+}
+
+pub fn parse_file(path: impl AsRef<Path>) -> Result<Shard, AlnError> {
+    let path = path.as_ref();
+    let source = std::fs::read_to_string(path).map_err(AlnError::Io)?;
+    Ok(parse_named(&path.display().to_string(), &source)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(line: &str) -> Row {
+        match parse(line).unwrap().items.remove(0).kind {
+            ItemKind::Row(r) => *r,
+            other => panic!("expected a row, got {:?}", other),
+        }
+    }
+
+    fn error(source: &str) -> ParseError {
+        parse(source).unwrap_err()
+    }
+
+    #[test]
+    fn errors_carry_line_and_column() {
+        let e = error("SECTION,A\nROW,subject,subject,scalar");
+        assert_eq!((e.line, e.column), (2, 27));
+        assert!(e.message.contains("found 3 cells"), "{}", e);
+
+        let e = error("# header\nBOGUS,x");
+        assert_eq!((e.line, e.column), (2, 1));
+
+        let e = error("ROW,a,b,scalar,k,v,float,,\"open");
+        assert_eq!((e.line, e.column, e.message.as_str()), (1, 27, "unterminated quoted field"));
+
+        let e = error("ROW,a,b,scalar,k,\"v\"x,float");
+        assert_eq!((e.line, e.column), (1, 21));
+        let e = error("ROW,a,b,scalar,k,v\"x,float");
+        assert_eq!((e.line, e.column), (1, 19));
+
+        let named = parse_named("demo.aln", "ROW,,b,scalar,k,v").unwrap_err();
+        assert_eq!(named.to_string(), "demo.aln:1:5: empty entitytype");
+    }
+
+    #[test]
+    fn quoted_cells_keep_commas_and_escaped_quotes() {
+        let cells = split_cells(r#"a,"b,c","say ""hi""","""#, 1).unwrap();
+        let texts: Vec<&str> = cells.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["a", "b,c", r#"say "hi""#, ""]);
+        assert_eq!(cells.iter().map(|c| c.column).collect::<Vec<_>>(), [1, 3, 9, 22]);
+        assert_eq!(cells.iter().map(|c| c.quoted).collect::<Vec<_>>(), [false, true, true, true]);
+
+        let r = row(r#"ROW,rule,rule,condition,guard,"ok = a in N2,N3",string,readonly,"Guard, quoted""#);
+        assert_eq!(r.value, "ok = a in N2,N3");
+        assert_eq!(r.notes, "Guard, quoted");
+        // Spans point past the opening quote.
+        assert_eq!(r.spans.value.column, 32);
+    }
+
+    #[test]
+    fn ranges_split_into_two_constraint_cells() {
+        let r = row("ROW,subject,subject,scalar,sleeptoken,0.0,float,range0,1,S in E");
+        assert_eq!(r.constraints, ["range0", "1"]);
+        assert_eq!(r.notes, "S in E");
+        assert_eq!(r.spans.constraints.iter().map(|s| s.column).collect::<Vec<_>>(), [49, 56]);
+
+        let r = row("ROW,subject,subject,enum,stage,wake,string,wake,N1,N2,Stage");
+        assert_eq!(r.constraints, ["wake", "N1", "N2"]);
+        assert_eq!(r.notes, "Stage");
+    }
+
+    #[test]
+    fn a_range_without_notes_keeps_its_bound() {
+        let r = row("ROW,derived,session,scalar,emin,0.5,float,range0,1");
+        assert_eq!(r.constraints, ["range0", "1"]);
+        assert_eq!(r.notes, "");
+        assert!(r.spans.notes.is_none());
+
+        let r = row("ROW,derived,session,scalar,emin,0.5,float,nonnull,range0,1");
+        assert_eq!(r.constraints, ["nonnull", "range0", "1"]);
+
+        // Other rows without notes end with an empty cell, as the writer emits them.
+        let r = row("ROW,derived,session,flag,ok,false,bool,nonnull,");
+        assert_eq!(r.constraints, ["nonnull"]);
+        assert_eq!(crate::writer::write_row(&r), "ROW,derived,session,flag,ok,false,bool,nonnull,");
+    }
+
+    #[test]
+    fn short_rows_stop_after_value_or_datatype() {
+        let r = row("ROW,stake,stake,scalar,owner,alice");
+        assert_eq!((r.datatype, r.constraints.len(), r.notes.as_str()), (None, 0, ""));
+        let r = row("ROW,stake,stake,scalar,owner,alice,string");
+        assert_eq!(r.datatype, Some(DataType::parse("string")));
+        assert!(r.spans.notes.is_none());
+    }
+}
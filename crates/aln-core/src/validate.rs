@@ -0,0 +1,479 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::ast::{DataType, Row, RowKind, Shard, Span};
+use crate::expr::{parse_rule, State, Value};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Constraint {
+    /// `range<lo>,<hi>`, inclusive.
+    Range(f64, f64),
+    /// Enum rows: the allowed values.
+    OneOf(Vec<String>),
+    PrimaryKey,
+    Immutable,
+    ReadOnly,
+    NonNull,
+    /// Guard flags that may never be switched off.
+    NonWaivable,
+    Auto,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSchema {
+    pub key: String,
+    pub entity_type: String,
+    pub datatype: DataType,
+    pub constraints: Vec<Constraint>,
+    pub span: Span,
+}
+
+impl FieldSchema {
+    pub fn has(&self, c: &Constraint) -> bool {
+        self.constraints.contains(c)
+    }
+
+    /// Constraint that forbids changing the value after declaration, if any.
+    pub fn frozen_by(&self) -> Option<&'static str> {
+        if self.has(&Constraint::Immutable) {
+            Some("immutable")
+        } else if self.has(&Constraint::ReadOnly) {
+            Some("readonly")
+        } else if self.has(&Constraint::NonWaivable) {
+            Some("nonwaivable")
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ViolationKind {
+    TypeMismatch { expected: DataType, value: String },
+    OutOfRange { value: f64, min: f64, max: f64 },
+    NotInEnum { value: String, allowed: Vec<String> },
+    Missing,
+    UnknownField,
+    DuplicatePrimaryKey { value: String },
+    DuplicateDeclaration { first: Span },
+    /// Assignment to a field that may not change.
+    FrozenAssignment { constraint: &'static str, from: String, to: String },
+    UnknownConstraint(String),
+    MalformedConstraint(String),
+}
+
+/// One violation, pinned to the field and, when known, the shard location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Violation {
+    pub key: String,
+    pub span: Option<Span>,
+    /// Index of the runtime record, for `validate_records`.
+    pub record: Option<usize>,
+    pub kind: ViolationKind,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(s) = self.span {
+            write!(f, "{}:{}: ", s.line, s.column)?;
+        }
+        if let Some(r) = self.record {
+            write!(f, "record {}: ", r)?;
+        }
+        write!(f, "{}: ", self.key)?;
+        match &self.kind {
+            ViolationKind::TypeMismatch { expected, value } => write!(f, "`{}` is not a valid {}", value, expected.as_str()),
+            ViolationKind::OutOfRange { value, min, max } => write!(f, "{} outside [{}, {}]", value, min, max),
+            ViolationKind::NotInEnum { value, allowed } => write!(f, "`{}` not one of {}", value, allowed.join(",")),
+            ViolationKind::Missing => f.write_str("nonnull field has no value"),
+            ViolationKind::UnknownField => f.write_str("not declared by the shard"),
+            ViolationKind::DuplicatePrimaryKey { value } => write!(f, "duplicate primary key `{}`", value),
+            ViolationKind::DuplicateDeclaration { first } => write!(f, "declared again (first at {}:{})", first.line, first.column),
+            ViolationKind::FrozenAssignment { constraint, from, to } => {
+                write!(f, "{} field changed from `{}` to `{}`", constraint, from, to)
+            }
+            ViolationKind::UnknownConstraint(c) => write!(f, "unknown constraint `{}`", c),
+            ViolationKind::MalformedConstraint(c) => write!(f, "malformed constraint: {}", c),
+        }
+    }
+}
+
+fn violation(row: &Row, span: Span, kind: ViolationKind) -> Violation {
+    Violation {
+        key: row.key.clone(),
+        span: Some(span),
+        record: None,
+        kind,
+    }
+}
+
+fn parse_constraints(row: &Row, out: &mut Vec<Violation>) -> Vec<Constraint> {
+    if row.kind == RowKind::Enum {
+        return vec![Constraint::OneOf(row.constraints.clone())];
+    }
+    let mut constraints = Vec::new();
+    let mut cells = row.constraints.iter().zip(&row.spans.constraints);
+    while let Some((c, span)) = cells.next() {
+        let parsed = match c.as_str() {
+            "primarykey" => Constraint::PrimaryKey,
+            "immutable" => Constraint::Immutable,
+            "readonly" => Constraint::ReadOnly,
+            "nonnull" => Constraint::NonNull,
+            "nonwaivable" => Constraint::NonWaivable,
+            "auto" => Constraint::Auto,
+            other if other.starts_with("range") => {
+                let lo = other["range".len()..].parse::<f64>();
+                let hi = cells.next().map(|(h, _)| h.parse::<f64>());
+                match (lo, hi) {
+                    (Ok(lo), Some(Ok(hi))) if lo <= hi => Constraint::Range(lo, hi),
+                    _ => {
+                        out.push(violation(
+                            row,
+                            *span,
+                            ViolationKind::MalformedConstraint(format!("`{}` needs the form range<lo>,<hi>", other)),
+                        ));
+                        continue;
+                    }
+                }
+            }
+            other => {
+                out.push(violation(row, *span, ViolationKind::UnknownConstraint(other.to_string())));
+                continue;
+            }
+        };
+        constraints.push(parsed);
+    }
+    constraints
+}
+
+/// Field declarations of a shard with their parsed constraints.
+#[derive(Debug, Clone, Default)]
+pub struct Schema {
+    fields: BTreeMap<String, FieldSchema>,
+}
+
+impl Schema {
+    /// Build the schema; malformed or unknown constraints and duplicate
+    /// declarations come back as violations.
+    pub fn from_shard(shard: &Shard) -> (Self, Vec<Violation>) {
+        let mut fields: BTreeMap<String, FieldSchema> = BTreeMap::new();
+        let mut violations = Vec::new();
+        for (_, row) in shard.rows() {
+            if row.kind.is_rule() {
+                continue;
+            }
+            let constraints = parse_constraints(row, &mut violations);
+            if let Some(first) = fields.get(&row.key) {
+                violations.push(violation(row, row.spans.key, ViolationKind::DuplicateDeclaration { first: first.span }));
+                continue;
+            }
+            fields.insert(
+                row.key.clone(),
+                FieldSchema {
+                    key: row.key.clone(),
+                    entity_type: row.entity_type.clone(),
+                    datatype: row.datatype.clone().unwrap_or(DataType::String),
+                    constraints,
+                    span: row.spans.key,
+                },
+            );
+        }
+        (Self { fields }, violations)
+    }
+
+    pub fn field(&self, key: &str) -> Option<&FieldSchema> {
+        self.fields.get(key)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = &FieldSchema> {
+        self.fields.values()
+    }
+
+    /// Type, range and enum checks for one value.
+    pub fn check_value(&self, key: &str, value: &Value) -> Option<ViolationKind> {
+        let field = match self.fields.get(key) {
+            Some(f) => f,
+            None => return Some(ViolationKind::UnknownField),
+        };
+        let mismatch = || ViolationKind::TypeMismatch {
+            expected: field.datatype.clone(),
+            value: value.to_string(),
+        };
+        match (&field.datatype, value) {
+            (DataType::Float, Value::Number(_)) | (DataType::Bool, Value::Bool(_)) => {}
+            (DataType::Int, Value::Number(n)) if n.fract() == 0.0 => {}
+            (DataType::String | DataType::Other(_), Value::Str(_)) => {}
+            _ => return Some(mismatch()),
+        }
+        for c in &field.constraints {
+            match (c, value) {
+                (Constraint::Range(min, max), Value::Number(n)) if !(*min..=*max).contains(n) => {
+                    return Some(ViolationKind::OutOfRange { value: *n, min: *min, max: *max });
+                }
+                (Constraint::OneOf(allowed), Value::Str(s)) if !allowed.contains(s) => {
+                    return Some(ViolationKind::NotInEnum {
+                        value: s.clone(),
+                        allowed: allowed.clone(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// The typed value a row declares, or why it has none.
+    pub fn declared_value(&self, row: &Row) -> Result<Option<Value>, ViolationKind> {
+        let datatype = row.datatype.clone().unwrap_or(DataType::String);
+        let raw = row.value.trim();
+        if raw.is_empty() && datatype != DataType::String {
+            return Ok(None);
+        }
+        let mismatch = || ViolationKind::TypeMismatch {
+            expected: datatype.clone(),
+            value: row.value.clone(),
+        };
+        match datatype {
+            DataType::Float | DataType::Int => raw.parse().map(|n| Some(Value::Number(n))).map_err(|_| mismatch()),
+            DataType::Bool => row.bool_value().map(|b| Some(Value::Bool(b))).ok_or_else(mismatch),
+            _ if raw.is_empty() => Ok(None),
+            _ => Ok(Some(Value::Str(row.value.clone()))),
+        }
+    }
+
+    /// Checks one runtime state: every value valid, every nonnull field set.
+    pub fn validate_state(&self, state: &State) -> Vec<Violation> {
+        let mut out = Vec::new();
+        for (key, value) in state {
+            if let Some(kind) = self.check_value(key, value) {
+                out.push(Violation {
+                    key: key.clone(),
+                    span: None,
+                    record: None,
+                    kind,
+                });
+            }
+        }
+        for field in self.fields.values() {
+            let unset = match state.get(&field.key) {
+                None => true,
+                Some(Value::Str(s)) => s.is_empty(),
+                Some(_) => false,
+            };
+            if unset && field.has(&Constraint::NonNull) {
+                out.push(Violation {
+                    key: field.key.clone(),
+                    span: None,
+                    record: None,
+                    kind: ViolationKind::Missing,
+                });
+            }
+        }
+        out
+    }
+
+    /// Checks several records of runtime state, including primary-key uniqueness.
+    pub fn validate_records(&self, records: &[State]) -> Vec<Violation> {
+        let mut out = Vec::new();
+        let mut seen: BTreeMap<(String, String), usize> = BTreeMap::new();
+        for (idx, state) in records.iter().enumerate() {
+            for mut v in self.validate_state(state) {
+                v.record = Some(idx);
+                out.push(v);
+            }
+            for field in self.fields.values().filter(|f| f.has(&Constraint::PrimaryKey)) {
+                let Some(value) = state.get(&field.key) else { continue };
+                if seen.insert((field.key.clone(), value.to_string()), idx).is_some() {
+                    out.push(Violation {
+                        key: field.key.clone(),
+                        span: None,
+                        record: Some(idx),
+                        kind: ViolationKind::DuplicatePrimaryKey { value: value.to_string() },
+                    });
+                }
+            }
+        }
+        out
+    }
+
+    /// Checks a state transition: `after` must be valid and leave
+    /// immutable / readonly / nonwaivable fields as they were.
+    pub fn validate_update(&self, before: &State, after: &State) -> Vec<Violation> {
+        let mut out = self.validate_state(after);
+        for field in self.fields.values() {
+            let Some(constraint) = field.frozen_by() else { continue };
+            let (from, to) = (before.get(&field.key), after.get(&field.key));
+            if from != to {
+                let show = |v: Option<&Value>| v.map(|v| v.to_string()).unwrap_or_default();
+                out.push(Violation {
+                    key: field.key.clone(),
+                    span: None,
+                    record: None,
+                    kind: ViolationKind::FrozenAssignment {
+                        constraint,
+                        from: show(from),
+                        to: show(to),
+                    },
+                });
+            }
+        }
+        out
+    }
+}
+
+/// Static validation of a shard: constraints, declared defaults, duplicate
+/// declarations and rule assignments to frozen fields.
+pub fn validate_shard(shard: &Shard) -> Vec<Violation> {
+    let (schema, mut out) = Schema::from_shard(shard);
+    for (_, row) in shard.rows() {
+        if row.kind.is_rule() {
+            let Ok(rule) = parse_rule(&row.value) else { continue };
+            for a in rule.assignments() {
+                if let Some(constraint) = schema.field(&a.target).and_then(FieldSchema::frozen_by) {
+                    out.push(violation(
+                        row,
+                        row.spans.value,
+                        ViolationKind::FrozenAssignment {
+                            constraint,
+                            from: shard.row(&a.target).map(|r| r.value.clone()).unwrap_or_default(),
+                            to: a.value.to_string(),
+                        },
+                    ));
+                }
+            }
+            continue;
+        }
+        match schema.declared_value(row) {
+            Ok(Some(value)) => {
+                if let Some(kind) = schema.check_value(&row.key, &value) {
+                    out.push(violation(row, row.spans.value, kind));
+                }
+            }
+            Ok(None) => {}
+            Err(kind) => out.push(violation(row, row.spans.value, kind)),
+        }
+    }
+    out.sort_by_key(|v| v.span);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::parse;
+
+    const SHARD: &str = "\
+SECTION,FIELDS
+ROW,subject,subject,scalar,subjectid,,string,primarykey,ID
+ROW,subject,subject,enum,stage,wake,string,wake,N2,N3,Stage
+ROW,subject,subject,scalar,risk,0.2,float,range0,1,Risk
+ROW,subject,subject,scalar,count,3,int,nonnull,Count
+ROW,guard,policy,flag,privacy,true,bool,nonwaivable,Guard
+";
+
+    fn shard(extra: &str) -> Shard {
+        parse(&format!("{}{}", SHARD, extra)).unwrap()
+    }
+
+    fn state(pairs: &[(&str, Value)]) -> State {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+    }
+
+    #[test]
+    fn a_clean_shard_has_no_violations() {
+        assert_eq!(validate_shard(&shard("")), []);
+    }
+
+    #[test]
+    fn declared_values_are_checked_where_they_stand() {
+        let violations = validate_shard(&shard(
+            "\
+ROW,subject,subject,scalar,ceiling,1.5,float,range0,1,Out of range
+ROW,subject,subject,enum,mode,dreaming,string,passive,active,Not in enum
+ROW,subject,subject,scalar,score,high,float,,Not a number
+ROW,subject,subject,scalar,steps,2.5,int,,Not an int
+",
+        ));
+        let shown: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            shown,
+            [
+                "7:36: ceiling: 1.5 outside [0, 1]",
+                "8:31: mode: `dreaming` not one of passive,active",
+                "9:34: score: `high` is not a valid float",
+                "10:34: steps: `2.5` is not a valid int",
+            ]
+        );
+        assert_eq!(violations[0].kind, ViolationKind::OutOfRange { value: 1.5, min: 0.0, max: 1.0 });
+        assert!(violations.iter().all(|v| v.record.is_none()));
+    }
+
+    #[test]
+    fn constraints_and_declarations_are_checked() {
+        let violations = validate_shard(&shard(
+            "\
+ROW,subject,subject,scalar,level,0.5,float,between0,1,Unknown
+ROW,subject,subject,scalar,gain,0.5,float,range1,0,Reversed
+ROW,subject,subject,scalar,risk,0.3,float,range0,1,Again
+ROW,rule,rule,expression,waive,\"privacy = false\",string,readonly,
+",
+        ));
+        let kinds: Vec<&ViolationKind> = violations.iter().map(|v| &v.kind).collect();
+        assert_eq!(kinds[0], &ViolationKind::UnknownConstraint("between0".into()));
+        assert_eq!(violations[0].span, Some(Span { line: 7, column: 44 }));
+        assert!(matches!(kinds[1], ViolationKind::UnknownConstraint(c) if c == "1"));
+        assert!(matches!(kinds[2], ViolationKind::MalformedConstraint(_)));
+        assert_eq!(kinds[3], &ViolationKind::DuplicateDeclaration { first: Span { line: 4, column: 28 } });
+        assert_eq!(
+            kinds[4],
+            &ViolationKind::FrozenAssignment { constraint: "nonwaivable", from: "true".into(), to: "false".into() }
+        );
+        assert_eq!(violations[4].key, "waive");
+    }
+
+    #[test]
+    fn runtime_records_are_checked_by_index() {
+        let (schema, violations) = Schema::from_shard(&shard(""));
+        assert!(violations.is_empty());
+        let good = state(&[
+            ("subjectid", Value::Str("u1".into())),
+            ("stage", Value::Str("N2".into())),
+            ("risk", Value::Number(0.1)),
+            ("count", Value::Number(1.0)),
+        ]);
+        let mut bad = good.clone();
+        bad.insert("subjectid".into(), Value::Str("u2".into()));
+        bad.insert("stage".into(), Value::Str("REM".into()));
+        bad.insert("risk".into(), Value::Bool(true));
+        bad.insert("extra".into(), Value::Number(1.0));
+        bad.remove("count");
+
+        let violations = schema.validate_records(&[good.clone(), bad, good]);
+        let shown: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            shown,
+            [
+                "record 1: extra: not declared by the shard",
+                "record 1: risk: `true` is not a valid float",
+                "record 1: stage: `REM` not one of wake,N2,N3",
+                "record 1: count: nonnull field has no value",
+                "record 2: subjectid: duplicate primary key `u1`",
+            ]
+        );
+        assert!(violations.iter().all(|v| v.span.is_none()));
+    }
+
+    #[test]
+    fn updates_leave_frozen_fields_alone() {
+        let (schema, _) = Schema::from_shard(&shard(""));
+        let before = state(&[("privacy", Value::Bool(true)), ("count", Value::Number(1.0))]);
+        let mut after = before.clone();
+        after.insert("count".into(), Value::Number(2.0));
+        assert!(schema.validate_update(&before, &after).is_empty());
+
+        after.insert("privacy".into(), Value::Bool(false));
+        let violations = schema.validate_update(&before, &after);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].to_string(), "privacy: nonwaivable field changed from `true` to `false`");
+    }
+}
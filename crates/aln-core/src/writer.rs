@@ -0,0 +1,62 @@
+//! ALN text from the AST. `parse(&write(&shard))` gives back the same items.
+
+use crate::ast::{ItemKind, Row, Shard};
+
+/// Quote a cell when it would not survive `split_cells` as-is.
+pub fn cell(text: &str) -> String {
+    if text.contains(',') || text.contains('"') {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn write_row(row: &Row) -> String {
+    let mut cells = vec![
+        "ROW".to_string(),
+        cell(&row.entity_type),
+        cell(&row.field),
+        cell(row.kind.as_str()),
+        cell(&row.key),
+        // Rule text is always quoted, as the shards write it.
+        if row.kind.is_rule() {
+            format!("\"{}\"", row.value.replace('"', "\"\""))
+        } else {
+            cell(&row.value)
+        },
+    ];
+    let datatype = row.datatype.as_ref().map(|d| d.as_str()).unwrap_or("");
+    if row.datatype.is_some() || !row.constraints.is_empty() || !row.notes.is_empty() {
+        cells.push(cell(datatype));
+    }
+    if !row.constraints.is_empty() || !row.notes.is_empty() {
+        if row.constraints.is_empty() {
+            cells.push(String::new());
+        }
+        cells.extend(row.constraints.iter().map(|c| cell(c)));
+        cells.push(cell(&row.notes));
+    }
+    cells.join(",")
+}
+
+pub fn write_item(kind: &ItemKind) -> String {
+    match kind {
+        ItemKind::DestinationPath(p) => format!("destination-path,{}", cell(p)),
+        ItemKind::Datashard { kind, description } => format!("{}.Datashard,{}", kind, description),
+        ItemKind::Header(cells) => cells.iter().map(|c| cell(c)).collect::<Vec<_>>().join(","),
+        ItemKind::Section(s) => format!("SECTION,{}", cell(s)),
+        ItemKind::Row(r) => write_row(r),
+        ItemKind::Comment { indent, text } => format!("{}#{}", indent, text),
+        ItemKind::Blank => String::new(),
+        ItemKind::Footer(s) => format!("FOOTER,{}", cell(s)),
+    }
+}
+
+pub fn write(shard: &Shard) -> String {
+    let mut out = String::new();
+    for item in &shard.items {
+        out.push_str(&write_item(&item.kind));
+        out.push('\n');
+    }
+    out
+}
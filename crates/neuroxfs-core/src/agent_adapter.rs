@@ -1,7 +1,14 @@
+use std::sync::Arc;
+
 use serde::{Deserialize, Serialize};
 use crate::artifact::{SovereignArtifact, ArtifactKind};
+use crate::caller::{CallerContext, Purpose, Role};
 use crate::fs_handle::{FsHandle, FsMode};
+use crate::guards::GuardSet;
+use crate::liberty::LibertyBoard;
+use crate::routes::Route;
 use crate::error::FsError;
+use crate::explain::{CheckKind, DecisionBuilder};
 
 /// What external agents may ask NeuroXFS to do.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -17,6 +24,10 @@ pub struct AgentFsRequest {
     pub subject_id: String,
     pub artifact_id: String, // logical ID, resolved by your manifest, not a path
     pub op: AgentOperationKind,
+    pub route: Route, // route the request arrived on
+    /// Why the agent wants the data. Requests that do not say are denied.
+    #[serde(default)]
+    pub purpose: Option<Purpose>,
     pub via_evolve_token: bool,
 }
 
@@ -40,11 +51,56 @@ pub trait ArtifactResolver {
 
 pub struct NeuroxfsAgentAdapter<R> {
     resolver: R,
+    guards: GuardSet,
+    liberty: Arc<LibertyBoard>,
 }
 
 impl<R: ArtifactResolver> NeuroxfsAgentAdapter<R> {
     pub fn new(resolver: R) -> Self {
-        Self { resolver }
+        Self::with_guards(resolver, GuardSet::default())
+    }
+
+    pub fn with_guards(resolver: R, guards: GuardSet) -> Self {
+        Self {
+            resolver,
+            guards,
+            liberty: Arc::new(LibertyBoard::default()),
+        }
+    }
+
+    /// Share the subjects' cognitive-liberty switches with this adapter.
+    pub fn with_liberty(mut self, liberty: Arc<LibertyBoard>) -> Self {
+        self.liberty = liberty;
+        self
+    }
+
+    /// Open a handle for an agent. The handle stops working as soon as the
+    /// subject flips their cognitive-liberty switch.
+    pub fn open_handle(&self, req: &AgentFsRequest, art: SovereignArtifact, mode: FsMode) -> Result<FsHandle, FsError> {
+        let lease = self.liberty.lease(&req.subject_id)?;
+        let mut handle = FsHandle::open(art, mode, Self::caller_for(req)?, req.via_evolve_token, &self.guards)?;
+        handle.bind_lease(lease);
+        Ok(handle)
+    }
+
+    fn check_liberty(&self, subject_id: &str) -> Result<(), FsError> {
+        if self.liberty.is_suspended(subject_id)? {
+            return Err(Self::deny(
+                "agent-request",
+                "cognitive_liberty",
+                "subject has suspended all agent access",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Agents act on the requesting subject's behalf, never as a third party,
+    /// and for the purpose the request declares.
+    fn caller_for(req: &AgentFsRequest) -> Result<CallerContext, FsError> {
+        let Some(purpose) = req.purpose else {
+            return Err(Self::deny("agent-request", "purpose-binding", "request declares no purpose"));
+        };
+        CallerContext::new(req.subject_id.clone(), Role::Agent, purpose, req.route)
     }
 
     fn summarize_bytes(bytes: &[u8]) -> String {
@@ -57,6 +113,31 @@ impl<R: ArtifactResolver> NeuroxfsAgentAdapter<R> {
 
 impl<R: ArtifactResolver> AgentAdapter for NeuroxfsAgentAdapter<R> {
     fn handle_request(&self, req: AgentFsRequest) -> Result<AgentFsResponse, FsError> {
+        match self.dispatch(req) {
+            // Denials carry their reason tree back to the agent instead of a bare error.
+            Err(FsError::Denied(decision)) => Ok(AgentFsResponse {
+                ok: false,
+                message: decision.summary(),
+                data: Some(serde_json::json!({
+                    "explanation": decision.to_json(),
+                    "text": decision.render_text(),
+                })),
+            }),
+            other => other,
+        }
+    }
+}
+
+impl<R: ArtifactResolver> NeuroxfsAgentAdapter<R> {
+    fn deny(action: &str, label: &str, detail: &str) -> FsError {
+        let mut d = DecisionBuilder::new("NeuroxfsAgentAdapter", action);
+        d.fail(CheckKind::Rule, label, detail);
+        FsError::Denied(Box::new(d.finish()))
+    }
+
+    fn dispatch(&self, req: AgentFsRequest) -> Result<AgentFsResponse, FsError> {
+        self.check_liberty(&req.subject_id)?;
+
         // Resolve artifact by logical ID.
         let art = self
             .resolver
@@ -66,16 +147,17 @@ impl<R: ArtifactResolver> AgentAdapter for NeuroxfsAgentAdapter<R> {
             AgentOperationKind::ReadSummary => {
                 // Only allow summary-style reads on non-kernel artifacts.
                 if matches!(art.kind, ArtifactKind::SovereignConfig | ArtifactKind::BChainProof) {
-                    return Err(FsError::PolicyError(
-                        "Agent cannot read sovereign-config or proof artifacts".into(),
+                    return Err(Self::deny(
+                        "read-summary",
+                        "agent-kernel-read",
+                        "Agent cannot read sovereign-config or proof artifacts",
                     ));
                 }
-                let mut handle = FsHandle::open(
-                    art,
-                    FsMode::ReadOnly,
-                    req.subject_id.clone(),
-                    false,
-                )?;
+                let read_req = AgentFsRequest {
+                    via_evolve_token: false,
+                    ..req.clone()
+                };
+                let mut handle = self.open_handle(&read_req, art, FsMode::ReadOnly)?;
                 let bytes = handle.read_all()?;
                 let summary = Self::summarize_bytes(&bytes);
                 Ok(AgentFsResponse {
@@ -85,9 +167,12 @@ impl<R: ArtifactResolver> AgentAdapter for NeuroxfsAgentAdapter<R> {
                 })
             }
             AgentOperationKind::ReadMetadata => {
-                let art = self
-                    .resolver
-                    .resolve(&req.subject_id, &req.artifact_id)?;
+                // The descriptor is not opened, but it is only shown to callers
+                // who could open the artifact for read, and the decision is logged.
+                let caller = Self::caller_for(&req)?;
+                let decision = self.guards.evaluate(&caller, &art, FsMode::ReadOnly, false);
+                self.guards.record(&caller, &art, FsMode::ReadOnly, "read-metadata", &decision)?;
+                decision.into_result()?;
                 Ok(AgentFsResponse {
                     ok: true,
                     message: "metadata-ok".into(),
@@ -99,16 +184,13 @@ impl<R: ArtifactResolver> AgentAdapter for NeuroxfsAgentAdapter<R> {
             AgentOperationKind::AppendNote => {
                 // Only allowed on non-kernel, non-neural artifacts.
                 if matches!(art.kind, ArtifactKind::SovereignConfig | ArtifactKind::NeuralShard) {
-                    return Err(FsError::PolicyError(
-                        "Agent cannot append to sovereign-config or raw neural shards".into(),
+                    return Err(Self::deny(
+                        "append-note",
+                        "agent-kernel-append",
+                        "Agent cannot append to sovereign-config or raw neural shards",
                     ));
                 }
-                let mut handle = FsHandle::open(
-                    art,
-                    FsMode::ReadWrite,
-                    req.subject_id.clone(),
-                    req.via_evolve_token,
-                )?;
+                let mut handle = self.open_handle(&req, art, FsMode::ReadWrite)?;
                 let note = format!("\n# agent-note: {}", req.artifact_id);
                 handle.write_all(note.as_bytes())?;
                 Ok(AgentFsResponse {
@@ -120,3 +202,80 @@ impl<R: ArtifactResolver> AgentAdapter for NeuroxfsAgentAdapter<R> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Notes;
+
+    impl ArtifactResolver for Notes {
+        fn resolve(&self, subject_id: &str, artifact_id: &str) -> Result<SovereignArtifact, FsError> {
+            let mut art = SovereignArtifact::sovereign_config(format!("/nonexistent/{}", artifact_id), subject_id);
+            art.kind = ArtifactKind::GenericData;
+            art.routes = vec![Route::Chat];
+            art.route_allowances.clear();
+            Ok(art)
+        }
+    }
+
+    fn request(op: &str, purpose: &str) -> AgentFsRequest {
+        let purpose = if purpose.is_empty() { String::new() } else { format!(r#","purpose":"{}""#, purpose) };
+        let json = format!(
+            r#"{{"subject_id":"subject-1","artifact_id":"notes","op":"{}","route":"CHAT","via_evolve_token":false{}}}"#,
+            op, purpose
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    #[test]
+    fn purpose_comes_from_the_request() {
+        let adapter = NeuroxfsAgentAdapter::new(Notes);
+
+        // Undeclared purpose: denied outright.
+        let res = adapter.handle_request(request("ReadSummary", "")).unwrap();
+        assert!(!res.ok);
+        assert!(res.message.contains("declares no purpose"), "{}", res.message);
+
+        // Automated decisions on forbid_decision_use data.
+        let res = adapter.handle_request(request("ReadSummary", "AutomatedDecision")).unwrap();
+        assert!(!res.ok);
+        assert!(res.message.contains("forbid_decision_use"), "{}", res.message);
+
+        // A purpose agents may not declare.
+        let res = adapter.handle_request(request("ReadSummary", "ClinicalCare")).unwrap();
+        assert!(!res.ok);
+        assert!(res.message.contains("may not declare"), "{}", res.message);
+
+        // Introspection passes the guards and only fails opening the (missing) file.
+        assert!(matches!(adapter.handle_request(request("ReadSummary", "Introspection")), Err(FsError::Io(_))));
+    }
+
+    #[test]
+    fn metadata_reads_pass_the_guards_and_are_logged() {
+        use crate::audit::AuditChain;
+        use std::sync::Mutex;
+
+        let guards = GuardSet {
+            audit: Some(Arc::new(Mutex::new(AuditChain::new()))),
+            ..GuardSet::default()
+        };
+        let adapter = NeuroxfsAgentAdapter::with_guards(Notes, guards.clone());
+
+        let res = adapter.handle_request(request("ReadMetadata", "Introspection")).unwrap();
+        assert!(res.ok, "{}", res.message);
+        assert_eq!(res.data.unwrap()["path"], "/nonexistent/notes");
+
+        // Not exposed on BCI, and no purpose at all.
+        let mut bci = request("ReadMetadata", "Introspection");
+        bci.route = Route::Bci;
+        let res = adapter.handle_request(bci).unwrap();
+        assert!(!res.ok);
+        assert!(res.message.contains("not exposed on Bci"), "{}", res.message);
+        assert!(!adapter.handle_request(request("ReadMetadata", "")).unwrap().ok);
+
+        let audit = guards.audit.as_ref().unwrap().lock().unwrap();
+        let actions: Vec<_> = audit.records().map(|r| (r.body.action.clone(), r.body.detail.contains("Allow"))).collect();
+        assert_eq!(actions, [("read-metadata".to_string(), true), ("read-metadata".to_string(), false)]);
+    }
+}
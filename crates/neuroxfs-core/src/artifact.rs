@@ -1,6 +1,10 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::grants::GrantOperation;
+use crate::routes::{Route, RouteAllowance};
+use crate::tags::GovernanceTag;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ArtifactKind {
     NeuralShard,
     NeuroRightsPolicy,
@@ -9,15 +13,38 @@ pub enum ArtifactKind {
     BChainProof,
     Model,
     SovereignConfig,
+    /// The subject's own cognitive-liberty switch. Not a kernel artifact: no
+    /// guardian quorum or EVOLVE token stands between a subject and their suspend.
+    LibertySwitch,
+    Biospec,
     GenericData,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct NeurorightsProfile {
     pub mental_privacy: bool,
     pub dreamstate_sensitive: bool,
     pub soul_non_tradeable: bool,
     pub forbid_decision_use: bool,
+    #[serde(default)]
+    pub mental_integrity: bool, // stimulation / closed-loop: dual-control writes only
+}
+
+impl NeurorightsProfile {
+    /// Strictest combination: every flag set on either side stays set.
+    pub fn union(&self, other: &NeurorightsProfile) -> NeurorightsProfile {
+        NeurorightsProfile {
+            mental_privacy: self.mental_privacy || other.mental_privacy,
+            dreamstate_sensitive: self.dreamstate_sensitive || other.dreamstate_sensitive,
+            soul_non_tradeable: self.soul_non_tradeable || other.soul_non_tradeable,
+            forbid_decision_use: self.forbid_decision_use || other.forbid_decision_use,
+            mental_integrity: self.mental_integrity || other.mental_integrity,
+        }
+    }
+
+    pub fn at_least_as_strict_as(&self, other: &NeurorightsProfile) -> bool {
+        self.union(other) == *self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -25,10 +52,53 @@ pub struct SovereignArtifact {
     pub path: String,
     pub subject_id: String, // Bostrom / OrganicCPU subject
     pub kind: ArtifactKind,
-    pub routes: Vec<String>, // e.g., ["CHAT", "BCI", "OTA"]
+    pub routes: Vec<Route>, // e.g., ["CHAT", "BCI", "OTA"]
+    #[serde(default)]
+    pub route_allowances: Vec<RouteAllowance>, // per-route ops; absent = read-only
     pub roh_before: f32,
     pub roh_after: f32,
     pub neurorights: NeurorightsProfile,
     pub lifeforce_cost: f32,
-    pub governance_tags: Vec<String>, // EVOLVE, SMART, etc.
+    pub governance_tags: Vec<GovernanceTag>, // EVOLVE, SMART, EXPORT
+    #[serde(default)]
+    pub inputs: Vec<String>, // paths of the artifacts this one was derived from
+}
+
+impl SovereignArtifact {
+    /// Descriptor for a subject's own sovereign config file (grants, switches):
+    /// private, INTROSPECT-only, and changed only through EVOLVE.
+    pub fn sovereign_config(path: impl Into<String>, subject_id: &str) -> Self {
+        Self {
+            path: path.into(),
+            subject_id: subject_id.to_string(),
+            kind: ArtifactKind::SovereignConfig,
+            routes: vec![Route::Introspect],
+            route_allowances: vec![RouteAllowance {
+                route: Route::Introspect,
+                operations: vec![GrantOperation::Read, GrantOperation::Write],
+            }],
+            roh_before: 0.0,
+            roh_after: 0.0,
+            neurorights: NeurorightsProfile {
+                mental_privacy: true,
+                dreamstate_sensitive: false,
+                soul_non_tradeable: false,
+                forbid_decision_use: true,
+                mental_integrity: false,
+            },
+            lifeforce_cost: 0.0,
+            governance_tags: vec![GovernanceTag::Evolve],
+            inputs: Vec::new(),
+        }
+    }
+
+    /// Descriptor for a subject's liberty switch record: private and
+    /// INTROSPECT-only like a sovereign config, but written directly.
+    pub fn liberty_switch(path: impl Into<String>, subject_id: &str) -> Self {
+        Self {
+            kind: ArtifactKind::LibertySwitch,
+            governance_tags: Vec::new(),
+            ..Self::sovereign_config(path, subject_id)
+        }
+    }
 }
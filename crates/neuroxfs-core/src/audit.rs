@@ -0,0 +1,159 @@
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::FsError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AuditSeverity {
+    Info,
+    Warning,
+    /// Emergency access; surfaced to the subject ahead of everything else.
+    BreakGlass,
+}
+
+/// Fields covered by the record hash.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditBody {
+    pub seq: u64,
+    pub timestamp: u64,
+    pub severity: AuditSeverity,
+    pub principal: String,
+    pub purpose: String,
+    pub subject_id: String,
+    pub artifact_path: String,
+    pub action: String,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub body: AuditBody,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditRecord {
+    /// Hash of `body` chained to `prev_hash`; equals `hash` unless the record was edited.
+    pub fn computed_hash(&self) -> String {
+        record_hash(&self.prev_hash, &self.body)
+    }
+}
+
+pub const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+fn record_hash(prev_hash: &str, body: &AuditBody) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(prev_hash.as_bytes());
+    hasher.update(serde_json::to_vec(body).expect("audit body serialization is infallible"));
+    hex::encode(hasher.finalize())
+}
+
+/// Append-only, hash-chained audit log.
+///
+/// A chain opened from a file writes every record to disk before `append`
+/// returns; `new` keeps the chain in memory only.
+#[derive(Debug, Clone, Default)]
+pub struct AuditChain {
+    records: Vec<AuditRecord>,
+    file: Option<PathBuf>,
+}
+
+impl AuditChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load and verify the NDJSON log at `path`, creating it on first append.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FsError> {
+        let path = path.as_ref();
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(FsError::Io(e)),
+        };
+        let records = text
+            .lines()
+            .filter(|l| !l.trim().is_empty())
+            .enumerate()
+            .map(|(i, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| FsError::PolicyError(format!("audit record {}: {}", i, e)))
+            })
+            .collect::<Result<Vec<AuditRecord>, FsError>>()?;
+        let chain = Self {
+            records,
+            file: Some(path.to_path_buf()),
+        };
+        chain.verify()?;
+        Ok(chain)
+    }
+
+    pub fn head_hash(&self) -> &str {
+        self.records.last().map_or(GENESIS_HASH, |r| r.hash.as_str())
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn append(
+        &mut self,
+        timestamp: u64,
+        severity: AuditSeverity,
+        principal: &str,
+        purpose: &str,
+        subject_id: &str,
+        artifact_path: &str,
+        action: &str,
+        detail: &str,
+    ) -> Result<&AuditRecord, FsError> {
+        let body = AuditBody {
+            seq: self.records.len() as u64,
+            timestamp,
+            severity,
+            principal: principal.to_string(),
+            purpose: purpose.to_string(),
+            subject_id: subject_id.to_string(),
+            artifact_path: artifact_path.to_string(),
+            action: action.to_string(),
+            detail: detail.to_string(),
+        };
+        let prev_hash = self.head_hash().to_string();
+        let hash = record_hash(&prev_hash, &body);
+        let record = AuditRecord { body, prev_hash, hash };
+        if let Some(path) = &self.file {
+            let mut line = serde_json::to_string(&record).expect("audit record serialization is infallible");
+            line.push('\n');
+            let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(FsError::Io)?;
+            file.write_all(line.as_bytes()).map_err(FsError::Io)?;
+            file.sync_data().map_err(FsError::Io)?;
+        }
+        self.records.push(record);
+        Ok(self.records.last().unwrap())
+    }
+
+    pub fn records(&self) -> impl Iterator<Item = &AuditRecord> {
+        self.records.iter()
+    }
+
+    /// Recompute every link; reports the first record that does not chain.
+    pub fn verify(&self) -> Result<(), FsError> {
+        let mut prev = GENESIS_HASH.to_string();
+        for (i, rec) in self.records.iter().enumerate() {
+            if rec.body.seq != i as u64 || rec.prev_hash != prev || rec.hash != rec.computed_hash() {
+                return Err(FsError::PolicyError(format!("audit chain broken at record {}", i)));
+            }
+            prev = rec.hash.clone();
+        }
+        Ok(())
+    }
+
+    pub fn to_ndjson(&self) -> String {
+        self.records
+            .iter()
+            .map(|r| serde_json::to_string(r).expect("audit record serialization is infallible"))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
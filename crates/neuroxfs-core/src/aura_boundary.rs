@@ -0,0 +1,3 @@
+//! The aura boundary guard lives in `guards` next to the other built-in guards.
+
+pub use crate::guards::AuraBoundaryGuard;
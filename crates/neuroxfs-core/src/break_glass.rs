@@ -0,0 +1,566 @@
+use ed25519_dalek::{Signer, SigningKey};
+use serde::{Deserialize, Serialize};
+
+use crate::artifact::{ArtifactKind, SovereignArtifact};
+use crate::audit::{AuditChain, AuditRecord, AuditSeverity};
+use crate::caller::{CallerContext, Purpose, Role};
+use crate::clock::unix_now;
+use crate::error::FsError;
+use crate::explain::{CheckKind, DecisionBuilder};
+use crate::fs_handle::{FsHandle, FsMode};
+use crate::grants::{verify_hex, SubjectKeyring};
+use crate::guards::GuardSet;
+use crate::routes::Route;
+
+/// Upper bound on any break-glass pass, regardless of what was requested.
+pub const MAX_BREAK_GLASS_SECS: u64 = 15 * 60;
+
+const INVOKE: &str = "break-glass-invoke";
+const READ: &str = "break-glass-read";
+const ACKNOWLEDGED: &str = "break-glass-acknowledged";
+
+/// The caregiver is whoever invokes the request, see `BreakGlassController::invoke`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakGlassRequest {
+    pub subject_id: String,
+    pub artifact_path: String,
+    pub justification: String,
+    pub requested_secs: u64,
+}
+
+/// Short-lived, read-only access to one artifact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BreakGlassPass {
+    pub pass_id: u64,
+    pub caregiver: String,
+    pub subject_id: String,
+    pub artifact_path: String,
+    pub issued_at: u64,
+    pub expires_at: u64,
+    pub audit_hash: String,
+}
+
+/// Post-hoc review the subject has to acknowledge.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewItem {
+    pub pass_id: u64,
+    pub subject_id: String,
+    pub caregiver: String,
+    pub artifact_path: String,
+    pub justification: String,
+    pub opened_at: u64,
+    pub audit_hash: String,
+    pub acknowledged_at: Option<u64>,
+}
+
+impl ReviewItem {
+    /// What the subject signs to acknowledge; the invoke record's hash makes
+    /// it specific to this one use of break-glass.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        format!("break-glass-ack:{}:{}:{}", self.pass_id, self.subject_id, self.audit_hash).into_bytes()
+    }
+
+    /// Helper for subjects holding their own key.
+    pub fn sign(&self, key: &SigningKey) -> String {
+        hex::encode(key.sign(&self.signing_bytes()).to_bytes())
+    }
+}
+
+/// `detail` of an invoke record: what the pass and review need beyond the
+/// record's own principal, subject, path and timestamp.
+#[derive(Debug, Serialize, Deserialize)]
+struct InvokeDetail {
+    pass_id: u64,
+    expires_at: u64,
+    justification: String,
+}
+
+/// `detail` of a read or acknowledgement record.
+#[derive(Debug, Serialize, Deserialize)]
+struct PassDetail {
+    pass_id: u64,
+}
+
+/// `detail` of an acknowledgement record; the signature stays checkable later.
+#[derive(Debug, Serialize, Deserialize)]
+struct AckDetail {
+    pass_id: u64,
+    signature: String,
+}
+
+/// Emergency reads without a prior grant: any Caregiver with a justification
+/// gets a short read-only pass, and every invocation, read and acknowledgement
+/// is on the audit chain before it takes effect.
+///
+/// Passes and review items are not kept anywhere else: they are rebuilt from
+/// the chain, so a controller over a file-backed chain survives restarts.
+#[derive(Debug)]
+pub struct BreakGlassController {
+    audit: AuditChain,
+    passes: Vec<BreakGlassPass>,
+    reviews: Vec<ReviewItem>,
+}
+
+impl BreakGlassController {
+    /// Take over `audit`, restoring the passes and reviews it records.
+    pub fn new(audit: AuditChain) -> Result<Self, FsError> {
+        let mut bg = Self {
+            audit: AuditChain::new(),
+            passes: Vec::new(),
+            reviews: Vec::new(),
+        };
+        for rec in audit.records() {
+            bg.replay(rec)?;
+        }
+        bg.audit = audit;
+        Ok(bg)
+    }
+
+    fn replay(&mut self, rec: &AuditRecord) -> Result<(), FsError> {
+        let parse_err = |e: serde_json::Error| {
+            FsError::PolicyError(format!("break-glass record {}: {}", rec.body.seq, e))
+        };
+        match rec.body.action.as_str() {
+            INVOKE => {
+                let detail: InvokeDetail = serde_json::from_str(&rec.body.detail).map_err(parse_err)?;
+                self.passes.push(BreakGlassPass {
+                    pass_id: detail.pass_id,
+                    caregiver: rec.body.principal.clone(),
+                    subject_id: rec.body.subject_id.clone(),
+                    artifact_path: rec.body.artifact_path.clone(),
+                    issued_at: rec.body.timestamp,
+                    expires_at: detail.expires_at,
+                    audit_hash: rec.hash.clone(),
+                });
+                self.reviews.push(ReviewItem {
+                    pass_id: detail.pass_id,
+                    subject_id: rec.body.subject_id.clone(),
+                    caregiver: rec.body.principal.clone(),
+                    artifact_path: rec.body.artifact_path.clone(),
+                    justification: detail.justification,
+                    opened_at: rec.body.timestamp,
+                    audit_hash: rec.hash.clone(),
+                    acknowledged_at: None,
+                });
+            }
+            ACKNOWLEDGED => {
+                let detail: PassDetail = serde_json::from_str(&rec.body.detail).map_err(parse_err)?;
+                if let Some(item) = self.reviews.iter_mut().find(|r| r.pass_id == detail.pass_id) {
+                    item.acknowledged_at = Some(rec.body.timestamp);
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Only Biospec / lifeforce data, and never soul-non-tradeable or dream-sensitive shards.
+    fn check_eligible(d: &mut DecisionBuilder, art: &SovereignArtifact) {
+        let biospec = matches!(art.kind, ArtifactKind::Biospec)
+            || art.path.ends_with(".biospec.aln")
+            || art.path.ends_with(".lifeforce.aln");
+        if !biospec {
+            d.fail(
+                CheckKind::Rule,
+                "biospec",
+                format!("break-glass covers only Biospec and .lifeforce.aln artifacts, not {:?}", art.kind),
+            );
+            return;
+        }
+        d.pass(CheckKind::Rule, "biospec", format!("{} is Biospec / lifeforce data", art.path));
+        if art.neurorights.soul_non_tradeable {
+            d.fail(CheckKind::Flag, "soul_non_tradeable", "break-glass cannot unlock soul-non-tradeable data");
+            return;
+        }
+        if art.neurorights.dreamstate_sensitive {
+            d.fail(CheckKind::Flag, "dreamstate_sensitive", "break-glass cannot unlock dreamstate-sensitive data");
+            return;
+        }
+        d.pass(CheckKind::Flag, "neurorights", "neither soul-non-tradeable nor dreamstate-sensitive");
+    }
+
+    /// Break-glass stands in for a grant, so it is limited to the Caregiver role.
+    fn check_caregiver(d: &mut DecisionBuilder, caregiver: &CallerContext) {
+        if caregiver.role() != Role::Caregiver {
+            d.fail(
+                CheckKind::Rule,
+                "caregiver-role",
+                format!("break-glass is reserved for the Caregiver role, not {:?}", caregiver.role()),
+            );
+            return;
+        }
+        d.pass(CheckKind::Rule, "caregiver-role", format!("{} acts as Caregiver", caregiver.principal()));
+    }
+
+    fn check_request(d: &mut DecisionBuilder, req: &BreakGlassRequest, art: &SovereignArtifact) {
+        if req.justification.trim().is_empty() {
+            d.fail(CheckKind::Rule, "justification", "break-glass requires a stated justification");
+        } else if req.subject_id != art.subject_id || req.artifact_path != art.path {
+            d.fail(CheckKind::Rule, "request", "break-glass request does not match artifact");
+        } else {
+            d.pass(CheckKind::Rule, "request", format!("justified request for {}", art.path));
+        }
+    }
+
+    /// A known, unexpired pass for this artifact; `None` once `d` has failed.
+    fn check_pass(&self, d: &mut DecisionBuilder, pass_id: u64, art: &SovereignArtifact, now: u64) -> Option<&BreakGlassPass> {
+        let Some(pass) = self.passes.iter().find(|p| p.pass_id == pass_id) else {
+            d.fail(CheckKind::Token, "pass", format!("unknown pass {}", pass_id));
+            return None;
+        };
+        if now >= pass.expires_at {
+            d.fail(CheckKind::Token, "pass", format!("pass {} expired at {}", pass_id, pass.expires_at));
+            return None;
+        }
+        if pass.artifact_path != art.path || pass.subject_id != art.subject_id {
+            d.fail(CheckKind::Token, "pass", format!("pass {} does not cover {}", pass_id, art.path));
+            return None;
+        }
+        d.pass(CheckKind::Token, "pass", format!("pass {} valid until {}", pass_id, pass.expires_at));
+        Some(pass)
+    }
+
+    pub fn invoke(
+        &mut self,
+        req: BreakGlassRequest,
+        caregiver: &CallerContext,
+        art: &SovereignArtifact,
+    ) -> Result<BreakGlassPass, FsError> {
+        let now = unix_now();
+        let mut d = DecisionBuilder::new("BreakGlass", "invoke");
+        Self::check_caregiver(&mut d, caregiver);
+        if !d.is_denied() {
+            Self::check_request(&mut d, &req, art);
+        }
+        if !d.is_denied() {
+            Self::check_eligible(&mut d, art);
+        }
+        d.finish().into_result()?;
+
+        let ttl = req.requested_secs.clamp(1, MAX_BREAK_GLASS_SECS);
+        let detail = InvokeDetail {
+            pass_id: self.passes.iter().map(|p| p.pass_id).max().unwrap_or(0) + 1,
+            expires_at: now + ttl,
+            justification: req.justification,
+        };
+        let record = self
+            .audit
+            .append(
+                now,
+                AuditSeverity::BreakGlass,
+                caregiver.principal(),
+                &format!("{:?}", caregiver.purpose()),
+                &req.subject_id,
+                &req.artifact_path,
+                INVOKE,
+                &serde_json::to_string(&detail).expect("break-glass detail serialization is infallible"),
+            )?
+            .clone();
+        self.replay(&record)?;
+        Ok(self.passes.last().cloned().expect("replayed invoke adds a pass"))
+    }
+
+    /// Open the artifact read-only under a pass; each use is audited, on the
+    /// break-glass chain and on the access trail in `guards.audit`.
+    pub fn open_read(&mut self, pass_id: u64, art: SovereignArtifact, guards: &GuardSet) -> Result<FsHandle, FsError> {
+        let now = unix_now();
+        let mut d = DecisionBuilder::new("BreakGlass", "read");
+        let caller = match self.check_pass(&mut d, pass_id, &art, now) {
+            Some(pass) => CallerContext::new(pass.caregiver.clone(), Role::Caregiver, Purpose::ClinicalCare, Route::BreakGlass)?,
+            None => return Err(FsError::Denied(Box::new(d.finish()))),
+        };
+        Self::check_eligible(&mut d, &art);
+        let decision = d.finish().into_result()?;
+
+        self.audit.append(
+            now,
+            AuditSeverity::BreakGlass,
+            caller.principal(),
+            &format!("{:?}", caller.purpose()),
+            &art.subject_id,
+            &art.path,
+            READ,
+            &serde_json::to_string(&PassDetail { pass_id }).expect("break-glass detail serialization is infallible"),
+        )?;
+        FsHandle::open_admitted(art, FsMode::ReadOnly, caller, false, decision, guards, READ)
+    }
+
+    /// Only the subject can close their own review item. A `CallerContext` is
+    /// only what the caller claims, so the subject also signs the item
+    /// (`ReviewItem::sign`) with the key `keyring` holds for them.
+    pub fn acknowledge(
+        &mut self,
+        pass_id: u64,
+        subject: &CallerContext,
+        signature: &str,
+        keyring: &SubjectKeyring,
+    ) -> Result<(), FsError> {
+        let now = unix_now();
+        let mut d = DecisionBuilder::new("BreakGlass", "acknowledge");
+        if subject.role() != Role::Subject {
+            d.fail(
+                CheckKind::Rule,
+                "subject-role",
+                format!("reviews are acknowledged by the subject, not {:?}", subject.role()),
+            );
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+        let Some(item) = self
+            .reviews
+            .iter()
+            .find(|r| r.pass_id == pass_id && r.subject_id == subject.principal())
+        else {
+            d.fail(
+                CheckKind::Rule,
+                "review-item",
+                format!("no review item for pass {} belongs to {}", pass_id, subject.principal()),
+            );
+            return Err(FsError::Denied(Box::new(d.finish())));
+        };
+        if item.acknowledged_at.is_some() {
+            return Ok(());
+        }
+        let verified = match keyring.get(&item.subject_id) {
+            Some(key) => verify_hex(key, &item.signing_bytes(), signature),
+            None => Err(format!("no signing key registered for {}", item.subject_id)),
+        };
+        if let Err(e) = verified {
+            d.fail(CheckKind::Token, "subject-signature", format!("acknowledgement for pass {}: {}", pass_id, e));
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+        let detail = AckDetail { pass_id, signature: signature.to_string() };
+        let record = self
+            .audit
+            .append(
+                now,
+                AuditSeverity::Info,
+                subject.principal(),
+                &format!("{:?}", subject.purpose()),
+                subject.principal(),
+                &item.artifact_path,
+                ACKNOWLEDGED,
+                &serde_json::to_string(&detail).expect("break-glass detail serialization is infallible"),
+            )?
+            .clone();
+        self.replay(&record)
+    }
+
+    pub fn pending_reviews<'a>(&'a self, subject_id: &'a str) -> impl Iterator<Item = &'a ReviewItem> {
+        self.reviews
+            .iter()
+            .filter(move |r| r.subject_id == subject_id && r.acknowledged_at.is_none())
+    }
+
+    pub fn audit(&self) -> &AuditChain {
+        &self.audit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("neuroxfs-break-glass-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn biospec(dir: &std::path::Path) -> SovereignArtifact {
+        let path = dir.join("vitals.biospec.aln");
+        std::fs::write(&path, "heart-rate,62\n").unwrap();
+        SovereignArtifact {
+            kind: ArtifactKind::Biospec,
+            ..SovereignArtifact::sovereign_config(path.display().to_string(), "subject-1")
+        }
+    }
+
+    fn request(art: &SovereignArtifact) -> BreakGlassRequest {
+        BreakGlassRequest {
+            subject_id: art.subject_id.clone(),
+            artifact_path: art.path.clone(),
+            justification: "subject unresponsive".into(),
+            requested_secs: 60,
+        }
+    }
+
+    fn caregiver(name: &str) -> CallerContext {
+        CallerContext::new(name, Role::Caregiver, Purpose::ClinicalCare, Route::BreakGlass).unwrap()
+    }
+
+    fn subject(id: &str) -> CallerContext {
+        CallerContext::subject(id, Route::Introspect)
+    }
+
+    fn subject_key() -> SigningKey {
+        SigningKey::from_bytes(&[3; 32])
+    }
+
+    fn keyring() -> SubjectKeyring {
+        let mut keyring = SubjectKeyring::default();
+        keyring.insert("subject-1", subject_key().verifying_key());
+        keyring
+    }
+
+    /// subject-1's signed acknowledgement of `pass_id`.
+    fn signed_ack(bg: &BreakGlassController, pass_id: u64) -> String {
+        let item = bg.pending_reviews("subject-1").find(|r| r.pass_id == pass_id).unwrap();
+        item.sign(&subject_key())
+    }
+
+    #[test]
+    fn invoke_needs_a_caregiver_and_a_justification_but_no_grant() {
+        let dir = scratch("invoke");
+        let art = biospec(&dir);
+        let mut bg = BreakGlassController::new(AuditChain::new()).unwrap();
+
+        let clinician = CallerContext::new("nurse", Role::Clinician, Purpose::ClinicalCare, Route::BreakGlass).unwrap();
+        match bg.invoke(request(&art), &clinician, &art).unwrap_err() {
+            FsError::Denied(d) => assert!(d.summary().contains("Caregiver role"), "{}", d.summary()),
+            other => panic!("expected a guard decision, got {}", other),
+        }
+        let unjustified = BreakGlassRequest { justification: "  ".into(), ..request(&art) };
+        assert!(matches!(bg.invoke(unjustified, &caregiver("nurse"), &art), Err(FsError::Denied(_))));
+        let mut dream = art.clone();
+        dream.neurorights.dreamstate_sensitive = true;
+        assert!(matches!(bg.invoke(request(&dream), &caregiver("nurse"), &dream), Err(FsError::Denied(_))));
+        assert_eq!(bg.audit().records().count(), 0);
+
+        let pass = bg.invoke(request(&art), &caregiver("nurse"), &art).unwrap();
+        assert_eq!(pass.caregiver, "nurse");
+        assert!(pass.expires_at - pass.issued_at <= MAX_BREAK_GLASS_SECS);
+        assert_eq!(bg.pending_reviews("subject-1").count(), 1);
+    }
+
+    #[test]
+    fn refused_reads_carry_a_decision() {
+        let dir = scratch("decision");
+        let art = biospec(&dir);
+        let mut bg = BreakGlassController::new(AuditChain::new()).unwrap();
+        match bg.open_read(7, art.clone(), &GuardSet::default()).unwrap_err() {
+            FsError::Denied(d) => assert_eq!(d.summary(), "BreakGlass: unknown pass 7"),
+            other => panic!("expected a guard decision, got {}", other),
+        }
+
+        let pass = bg.invoke(request(&art), &caregiver("nurse"), &art).unwrap();
+        let other = SovereignArtifact { path: dir.join("other.biospec.aln").display().to_string(), ..art.clone() };
+        assert!(matches!(bg.open_read(pass.pass_id, other, &GuardSet::default()), Err(FsError::Denied(_))));
+        let handle = bg.open_read(pass.pass_id, art, &GuardSet::default()).unwrap();
+        assert!(handle.decision().render_text().contains("pass"));
+    }
+
+    #[test]
+    fn only_the_subject_acknowledges_their_review() {
+        let dir = scratch("acknowledge");
+        let art = biospec(&dir);
+        let mut bg = BreakGlassController::new(AuditChain::new()).unwrap();
+        let pass = bg.invoke(request(&art), &caregiver("nurse"), &art).unwrap();
+
+        let ack = signed_ack(&bg, pass.pass_id);
+        assert!(matches!(bg.acknowledge(pass.pass_id, &subject("subject-2"), &ack, &keyring()), Err(FsError::Denied(_))));
+        let agent = CallerContext::new("subject-1", Role::Agent, Purpose::Introspection, Route::Introspect).unwrap();
+        assert!(matches!(bg.acknowledge(pass.pass_id, &agent, &ack, &keyring()), Err(FsError::Denied(_))));
+        // Anyone can claim to be the subject; only the subject's key signs for them.
+        let item = bg.pending_reviews("subject-1").next().unwrap();
+        let forged = item.sign(&SigningKey::from_bytes(&[4; 32]));
+        match bg.acknowledge(pass.pass_id, &subject("subject-1"), &forged, &keyring()).unwrap_err() {
+            FsError::Denied(d) => assert!(d.summary().contains("signature verification failed"), "{}", d.summary()),
+            other => panic!("expected a guard decision, got {}", other),
+        }
+        let unknown = bg.acknowledge(pass.pass_id, &subject("subject-1"), &ack, &SubjectKeyring::default());
+        assert!(matches!(unknown, Err(FsError::Denied(_))));
+        assert_eq!(bg.pending_reviews("subject-1").count(), 1);
+
+        bg.acknowledge(pass.pass_id, &subject("subject-1"), &ack, &keyring()).unwrap();
+        assert_eq!(bg.pending_reviews("subject-1").count(), 0);
+    }
+
+    #[test]
+    fn records_are_on_disk_before_the_read() {
+        let dir = scratch("persist");
+        let art = biospec(&dir);
+        let log = dir.join("audit.ndjson");
+        let mut bg = BreakGlassController::new(AuditChain::open(&log).unwrap()).unwrap();
+
+        let pass = bg.invoke(request(&art), &caregiver("nurse"), &art).unwrap();
+        let mut handle = bg.open_read(pass.pass_id, art.clone(), &GuardSet::default()).unwrap();
+        let reopened = AuditChain::open(&log).unwrap();
+        let actions: Vec<_> = reopened.records().map(|r| r.body.action.clone()).collect();
+        assert_eq!(actions, [INVOKE, READ]);
+        assert_eq!(reopened.head_hash(), bg.audit().head_hash());
+        assert_eq!(handle.read_all().unwrap(), b"heart-rate,62\n");
+
+        let ack = signed_ack(&bg, pass.pass_id);
+        bg.acknowledge(pass.pass_id, &subject("subject-1"), &ack, &keyring()).unwrap();
+        assert_eq!(AuditChain::open(&log).unwrap().records().count(), 3);
+    }
+
+    #[test]
+    fn reads_are_recorded_on_the_access_trail() {
+        use std::sync::{Arc, Mutex};
+
+        let dir = scratch("trail");
+        let art = biospec(&dir);
+        let guards = GuardSet {
+            audit: Some(Arc::new(Mutex::new(AuditChain::new()))),
+            ..GuardSet::default()
+        };
+        let mut bg = BreakGlassController::new(AuditChain::new()).unwrap();
+        let pass = bg.invoke(request(&art), &caregiver("nurse"), &art).unwrap();
+        bg.open_read(pass.pass_id, art, &guards).unwrap();
+
+        let trail = guards.audit.as_ref().unwrap().lock().unwrap();
+        let actions: Vec<_> = trail.records().map(|r| r.body.action.clone()).collect();
+        assert_eq!(actions, [READ]);
+    }
+
+    #[test]
+    fn passes_and_reviews_survive_a_restart() {
+        let dir = scratch("restart");
+        let art = biospec(&dir);
+        let log = dir.join("audit.ndjson");
+        let mut bg = BreakGlassController::new(AuditChain::open(&log).unwrap()).unwrap();
+        let first = bg.invoke(request(&art), &caregiver("nurse"), &art).unwrap();
+        let second = bg.invoke(request(&art), &caregiver("nurse"), &art).unwrap();
+        let ack = signed_ack(&bg, first.pass_id);
+        bg.acknowledge(first.pass_id, &subject("subject-1"), &ack, &keyring()).unwrap();
+        drop(bg);
+
+        let mut bg = BreakGlassController::new(AuditChain::open(&log).unwrap()).unwrap();
+        let pending: Vec<_> = bg.pending_reviews("subject-1").map(|r| r.pass_id).collect();
+        assert_eq!(pending, [second.pass_id]);
+        assert_eq!(bg.pending_reviews("subject-1").next().unwrap().audit_hash, second.audit_hash);
+        bg.open_read(second.pass_id, art.clone(), &GuardSet::default()).unwrap();
+        let third = bg.invoke(request(&art), &caregiver("nurse"), &art).unwrap();
+        assert!(third.pass_id > second.pass_id);
+    }
+
+    #[test]
+    fn unwritable_audit_log_blocks_access() {
+        let dir = scratch("unwritable");
+        let art = biospec(&dir);
+        let log = dir.join("audit.ndjson");
+        let chain = AuditChain::open(&log).unwrap();
+        // A directory where the log file should be: every append fails, and so does reopening.
+        std::fs::create_dir_all(&log).unwrap();
+        assert!(matches!(AuditChain::open(&log), Err(FsError::Io(_))));
+
+        let mut bg = BreakGlassController::new(chain).unwrap();
+        assert!(matches!(bg.invoke(request(&art), &caregiver("nurse"), &art), Err(FsError::Io(_))));
+        assert!(bg.pending_reviews("subject-1").next().is_none());
+    }
+
+    #[test]
+    fn tampered_log_fails_to_open() {
+        let dir = scratch("tampered");
+        let log = dir.join("audit.ndjson");
+        let mut chain = AuditChain::open(&log).unwrap();
+        chain.append(1, AuditSeverity::Info, "a", "Introspection", "subject-1", "x", "read", "").unwrap();
+        chain.append(2, AuditSeverity::Info, "a", "Introspection", "subject-1", "x", "read", "").unwrap();
+        let text = std::fs::read_to_string(&log).unwrap().replace("\"timestamp\":1", "\"timestamp\":9");
+        std::fs::write(&log, text).unwrap();
+        assert!(AuditChain::open(&log).is_err());
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::FsError;
+use crate::explain::{CheckKind, DecisionBuilder};
+use crate::routes::Route;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Role {
+    Subject,
+    Clinician,
+    Researcher,
+    Caregiver,
+    Agent,
+}
+
+/// Declared reason for an access; grants are bound to exactly one purpose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Purpose {
+    Introspection,
+    ClinicalCare,
+    Research,
+    AutomatedDecision,
+}
+
+impl Purpose {
+    pub fn is_decision_making(self) -> bool {
+        matches!(self, Purpose::AutomatedDecision)
+    }
+}
+
+impl Role {
+    /// Capability table: which purposes a role may declare. Agents act under
+    /// the subject's principal, so they may introspect on the subject's behalf.
+    pub fn may_declare(self, purpose: Purpose) -> bool {
+        match purpose {
+            Purpose::Introspection => matches!(self, Role::Subject | Role::Agent),
+            Purpose::ClinicalCare => matches!(self, Role::Clinician | Role::Caregiver),
+            Purpose::Research => self == Role::Researcher,
+            Purpose::AutomatedDecision => self == Role::Agent,
+        }
+    }
+}
+
+/// Who is asking, in what capacity, why, and over which route.
+///
+/// Built only through `new` (or deserialized through the same check), so the
+/// role / purpose pair always satisfies `Role::may_declare`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "CallerFields")]
+pub struct CallerContext {
+    principal: String, // Bostrom / OrganicCPU subject or service identity
+    role: Role,
+    purpose: Purpose,
+    route: Route,
+}
+
+#[derive(Deserialize)]
+struct CallerFields {
+    principal: String,
+    role: Role,
+    purpose: Purpose,
+    route: Route,
+}
+
+impl TryFrom<CallerFields> for CallerContext {
+    type Error = String;
+
+    fn try_from(f: CallerFields) -> Result<Self, String> {
+        CallerContext::new(f.principal, f.role, f.purpose, f.route).map_err(|e| e.to_string())
+    }
+}
+
+impl CallerContext {
+    pub fn new(principal: impl Into<String>, role: Role, purpose: Purpose, route: Route) -> Result<Self, FsError> {
+        if !role.may_declare(purpose) {
+            let mut d = DecisionBuilder::new("CallerContext", "declare");
+            d.fail(
+                CheckKind::Rule,
+                "purpose-binding",
+                format!("role {:?} may not declare purpose {:?}", role, purpose),
+            );
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+        Ok(Self {
+            principal: principal.into(),
+            role,
+            purpose,
+            route,
+        })
+    }
+
+    /// The subject acting on their own artifacts.
+    pub fn subject(subject_id: impl Into<String>, route: Route) -> Self {
+        Self {
+            principal: subject_id.into(),
+            role: Role::Subject,
+            purpose: Purpose::Introspection,
+            route,
+        }
+    }
+
+    /// The same caller arriving over another route.
+    pub fn with_route(mut self, route: Route) -> Self {
+        self.route = route;
+        self
+    }
+
+    pub fn principal(&self) -> &str {
+        &self.principal
+    }
+
+    pub fn role(&self) -> Role {
+        self.role
+    }
+
+    pub fn purpose(&self) -> Purpose {
+        self.purpose
+    }
+
+    pub fn route(&self) -> Route {
+        self.route
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undeclarable_purposes_are_refused_at_construction() {
+        assert!(CallerContext::new("lab", Role::Researcher, Purpose::Research, Route::Chat).is_ok());
+        for (role, purpose) in [
+            (Role::Researcher, Purpose::ClinicalCare),
+            (Role::Clinician, Purpose::AutomatedDecision),
+            (Role::Subject, Purpose::AutomatedDecision),
+            (Role::Caregiver, Purpose::Introspection),
+        ] {
+            let err = CallerContext::new("someone", role, purpose, Route::Chat).unwrap_err();
+            assert!(matches!(err, FsError::Denied(_)), "{:?} / {:?}", role, purpose);
+        }
+    }
+
+    #[test]
+    fn deserialization_applies_the_same_table() {
+        let ok = r#"{"principal":"agent-7","role":"Agent","purpose":"AutomatedDecision","route":"CHAT"}"#;
+        let caller: CallerContext = serde_json::from_str(ok).unwrap();
+        assert_eq!(caller.purpose(), Purpose::AutomatedDecision);
+        let forged = r#"{"principal":"lab","role":"Researcher","purpose":"ClinicalCare","route":"CHAT"}"#;
+        assert!(serde_json::from_str::<CallerContext>(forged).is_err());
+    }
+}
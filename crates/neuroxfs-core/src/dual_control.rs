@@ -0,0 +1,736 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+use serde::{Deserialize, Serialize};
+
+use crate::artifact::SovereignArtifact;
+use crate::caller::{CallerContext, Purpose, Role};
+use crate::clock::unix_now;
+use crate::error::FsError;
+use crate::explain::{CheckKind, DecisionBuilder, GuardDecision};
+use crate::fs_handle::{FsHandle, FsMode};
+use crate::grants::GrantOperation;
+use crate::guards::GuardSet;
+use crate::kernel_lock::payload_hash;
+use crate::routes::Route;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ParamClass {
+    Stimulation,
+    ClosedLoop,
+    Telemetry,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParamBounds {
+    pub name: String,
+    pub class: ParamClass,
+    pub min: f64,
+    pub max: f64,
+    pub unit: String,
+}
+
+/// Allowed parameters and their bounds for one BCI config artifact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterSchema {
+    pub params: Vec<ParamBounds>,
+}
+
+impl ParameterSchema {
+    /// Every schema parameter present, nothing unknown, everything in bounds.
+    pub fn validate(&self, values: &BTreeMap<String, f64>) -> Result<(), Vec<String>> {
+        let mut errors = Vec::new();
+        for p in &self.params {
+            match values.get(&p.name) {
+                None => errors.push(format!("{}: missing", p.name)),
+                Some(v) if !v.is_finite() || *v < p.min || *v > p.max => errors.push(format!(
+                    "{}: {} outside [{}, {}] {}",
+                    p.name, v, p.min, p.max, p.unit
+                )),
+                Some(_) => {}
+            }
+        }
+        for name in values.keys() {
+            if !self.params.iter().any(|p| &p.name == name) {
+                errors.push(format!("{}: not in parameter schema", name));
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(errors)
+        }
+    }
+
+    /// Stimulation or closed-loop parameters whose value differs between the two.
+    pub fn stimulation_changes(&self, old: &BTreeMap<String, f64>, new: &BTreeMap<String, f64>) -> Vec<&str> {
+        self.params
+            .iter()
+            .filter(|p| matches!(p.class, ParamClass::Stimulation | ParamClass::ClosedLoop))
+            .filter(|p| old.get(&p.name) != new.get(&p.name))
+            .map(|p| p.name.as_str())
+            .collect()
+    }
+
+    pub fn touches_stimulation(&self, old: &BTreeMap<String, f64>, new: &BTreeMap<String, f64>) -> bool {
+        !self.stimulation_changes(old, new).is_empty()
+    }
+}
+
+/// Dual control applies to mental-integrity artifacts reachable on BCI.
+pub fn requires_dual_control(art: &SovereignArtifact) -> bool {
+    art.neurorights.mental_integrity && art.routes.contains(&Route::Bci)
+}
+
+const DUAL_CONTROL_WRITE: &str = "dual-control-write";
+
+/// Co-signed changes are accepted for this long after they are created.
+pub const DEFAULT_CHANGE_TTL_SECS: u64 = 10 * 60;
+
+/// Single-party writes to dual-control configs: telemetry values may change
+/// through the normal guarded path, stimulation / closed-loop values may not.
+///
+/// Schemas are registered per artifact path; without one a write cannot be
+/// told apart from a stimulation change and is refused.
+#[derive(Debug, Clone, Default)]
+pub struct StimulationGuard {
+    schemas: BTreeMap<String, ParameterSchema>,
+}
+
+impl StimulationGuard {
+    pub fn with_schema(mut self, path: impl Into<String>, schema: ParameterSchema) -> Self {
+        self.schemas.insert(path.into(), schema);
+        self
+    }
+
+    pub fn schema(&self, path: &str) -> Option<&ParameterSchema> {
+        self.schemas.get(path)
+    }
+
+    /// Open-time check; the payload itself is checked by `check_payload` on write.
+    pub fn check(&self, art: &SovereignArtifact, mode: FsMode) -> GuardDecision {
+        let mut d = DecisionBuilder::new("StimulationGuard", &format!("{:?}", mode));
+        if mode == FsMode::ReadOnly || !requires_dual_control(art) {
+            d.skip(CheckKind::Flag, "mental_integrity", "not a write to a dual-control config");
+            return d.finish();
+        }
+        if self.schema(&art.path).is_none() {
+            d.fail(
+                CheckKind::Flag,
+                "mental_integrity",
+                format!("no parameter schema for {}; stimulation config requires a dual-control write", art.path),
+            );
+            return d.finish();
+        }
+        d.pass(
+            CheckKind::Flag,
+            "mental_integrity",
+            "telemetry-only writes allowed; stimulation / closed-loop changes need dual control",
+        );
+        d.finish()
+    }
+
+    /// A single-party rewrite from `old` to `new` bytes may change telemetry values only.
+    pub fn check_payload(schema: &ParameterSchema, old: &[u8], new: &[u8]) -> GuardDecision {
+        let mut d = DecisionBuilder::new("StimulationGuard", "write");
+        let parsed = DualControlWriter::parse_or_empty(old)
+            .and_then(|old| DualControlWriter::parse(new).map(|new| (old, new)));
+        let (old, new) = match parsed {
+            Ok(values) => values,
+            Err(e) => {
+                d.fail(CheckKind::Rule, "parameter-schema", e.to_string());
+                return d.finish();
+            }
+        };
+        if let Err(e) = schema.validate(&new) {
+            d.fail(CheckKind::Rule, "parameter-schema", e.join("; "));
+            return d.finish();
+        }
+        d.pass(CheckKind::Rule, "parameter-schema", "every value within bounds");
+        let changed = schema.stimulation_changes(&old, &new);
+        if !changed.is_empty() {
+            d.fail(
+                CheckKind::Flag,
+                "mental_integrity",
+                format!("changes {}; stimulation / closed-loop writes require dual control", changed.join(", ")),
+            );
+            return d.finish();
+        }
+        d.pass(CheckKind::Flag, "mental_integrity", "telemetry-only change");
+        d.finish()
+    }
+}
+
+/// A proposed set of parameter values, signed by both approvers.
+///
+/// Each change applies at most once and only before `expires_at`; the nonce
+/// keeps otherwise identical changes distinct.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ParameterChange {
+    pub subject_id: String,
+    pub artifact_path: String,
+    pub values: BTreeMap<String, f64>,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub nonce: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChangeApproval {
+    pub principal: String,
+    pub signature: String, // hex Ed25519 over `ParameterChange::signing_bytes`
+}
+
+/// Both signatures a dual-control write needs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DualApproval {
+    pub subject: ChangeApproval,
+    pub clinician: ChangeApproval,
+}
+
+impl ParameterChange {
+    pub fn new(art: &SovereignArtifact, values: BTreeMap<String, f64>, nonce: u64) -> Self {
+        let created_at = unix_now();
+        Self {
+            subject_id: art.subject_id.clone(),
+            artifact_path: art.path.clone(),
+            values,
+            created_at,
+            expires_at: created_at + DEFAULT_CHANGE_TTL_SECS,
+            nonce,
+        }
+    }
+
+    pub fn payload(&self) -> Vec<u8> {
+        serde_json::to_vec_pretty(&self.values).expect("parameter serialization is infallible")
+    }
+
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        format!(
+            "bci-change:{}:{}:{}:{}:{}:{}",
+            self.subject_id,
+            self.artifact_path,
+            self.created_at,
+            self.expires_at,
+            self.nonce,
+            payload_hash(&self.payload())
+        )
+        .into_bytes()
+    }
+
+    /// Identifies the signed change; consumed ids are never applied again.
+    pub fn change_id(&self) -> String {
+        payload_hash(&self.signing_bytes())
+    }
+
+    pub fn approve(&self, principal: &str, key: &SigningKey) -> ChangeApproval {
+        ChangeApproval {
+            principal: principal.to_string(),
+            signature: hex::encode(key.sign(&self.signing_bytes()).to_bytes()),
+        }
+    }
+}
+
+/// Two-party write path for stimulation / closed-loop configs.
+///
+/// Parameter schemas come from `guards.stimulation`, the same ones the
+/// single-party path is checked against. A writer opened on a file records
+/// each applied change id there before the write, so a co-signed change
+/// cannot be replayed after a restart; `new` keeps the ids in memory only.
+#[derive(Debug, Clone, Default)]
+pub struct DualControlWriter {
+    consumed: HashSet<String>,
+    file: Option<PathBuf>,
+}
+
+impl DualControlWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Load the change ids applied so far (one per line), creating the file on first use.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, FsError> {
+        let path = path.as_ref();
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(FsError::Io(e)),
+        };
+        Ok(Self {
+            consumed: text.lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from).collect(),
+            file: Some(path.to_path_buf()),
+        })
+    }
+
+    pub fn consumed(&self) -> impl Iterator<Item = &str> {
+        self.consumed.iter().map(String::as_str)
+    }
+
+    /// Mark `change_id` used, on disk first when the writer is file-backed.
+    fn consume(&mut self, change_id: String) -> Result<(), FsError> {
+        if let Some(path) = &self.file {
+            let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(FsError::Io)?;
+            file.write_all(format!("{}\n", change_id).as_bytes()).map_err(FsError::Io)?;
+            file.sync_data().map_err(FsError::Io)?;
+        }
+        self.consumed.insert(change_id);
+        Ok(())
+    }
+
+    fn verify_approval(&self, d: &mut DecisionBuilder, guards: &GuardSet, change: &ParameterChange, label: &str, approval: &ChangeApproval) {
+        let Some(key) = guards.aura.grants().keyring().get(&approval.principal) else {
+            d.fail(CheckKind::Token, label, format!("no signing key registered for {}", approval.principal));
+            return;
+        };
+        let verified = hex::decode(&approval.signature)
+            .ok()
+            .and_then(|bytes| Signature::from_slice(&bytes).ok())
+            .is_some_and(|sig| key.verify(&change.signing_bytes(), &sig).is_ok());
+        if verified {
+            d.pass(CheckKind::Token, label, format!("signed by {}", approval.principal));
+        } else {
+            d.fail(CheckKind::Token, label, format!("approval by {} does not verify", approval.principal));
+        }
+    }
+
+    /// Deny after the approvals passed, keeping their tree in the explanation.
+    fn deny_after(approved: &GuardDecision, label: &str, detail: &str) -> FsError {
+        let mut d = DecisionBuilder::new("DualControlWriter", "apply");
+        d.include(approved.clone());
+        d.fail(CheckKind::Rule, label, detail);
+        FsError::Denied(Box::new(d.finish()))
+    }
+
+    fn parse(bytes: &[u8]) -> Result<BTreeMap<String, f64>, FsError> {
+        serde_json::from_slice(bytes)
+            .map_err(|e| FsError::PolicyError(format!("parameter file parse error: {}", e)))
+    }
+
+    /// An empty file holds no parameters yet.
+    fn parse_or_empty(bytes: &[u8]) -> Result<BTreeMap<String, f64>, FsError> {
+        if bytes.is_empty() {
+            Ok(BTreeMap::new())
+        } else {
+            Self::parse(bytes)
+        }
+    }
+
+    /// Apply `change` once the subject and a granted clinician have both
+    /// signed it, it has not expired and it was never applied before. Values
+    /// are checked against the schema `guards.stimulation` holds for the
+    /// artifact. The
+    /// write runs every guard except the single-party `StimulationGuard`, with
+    /// the clinician as caller. After writing, the file is re-read and checked against the
+    /// schema and `post_check` (e.g. a device acknowledgement); any failure
+    /// restores the previous values. Every decision, allowed or denied, is
+    /// recorded on `guards.audit`.
+    pub fn apply(
+        &mut self,
+        art: SovereignArtifact,
+        change: &ParameterChange,
+        approvals: &DualApproval,
+        clinician: &CallerContext,
+        guards: &GuardSet,
+        post_check: impl FnOnce(&BTreeMap<String, f64>) -> Result<(), String>,
+    ) -> Result<(), FsError> {
+        let target = art.clone();
+        match self.apply_checked(art, change, approvals, clinician, guards, post_check) {
+            Err(FsError::Denied(decision)) => {
+                guards.record(clinician, &target, FsMode::ReadWrite, DUAL_CONTROL_WRITE, &decision)?;
+                Err(FsError::Denied(decision))
+            }
+            other => other,
+        }
+    }
+
+    fn apply_checked(
+        &mut self,
+        art: SovereignArtifact,
+        change: &ParameterChange,
+        approvals: &DualApproval,
+        clinician: &CallerContext,
+        guards: &GuardSet,
+        post_check: impl FnOnce(&BTreeMap<String, f64>) -> Result<(), String>,
+    ) -> Result<(), FsError> {
+        let mut d = DecisionBuilder::new("DualControlWriter", "apply");
+        if change.artifact_path != art.path || change.subject_id != art.subject_id {
+            d.fail(CheckKind::Rule, "change-target", "parameter change does not match artifact");
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+        let now = unix_now();
+        let change_id = change.change_id();
+        if now >= change.expires_at {
+            d.fail(CheckKind::Token, "change-expiry", format!("change expired at {}", change.expires_at));
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+        if self.consumed.contains(&change_id) {
+            d.fail(CheckKind::Token, "change-replay", format!("change {} was already applied", change_id));
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+        d.pass(
+            CheckKind::Token,
+            "change-replay",
+            format!("change {} unused, expires_at={}", change_id, change.expires_at),
+        );
+        let Some(schema) = guards.stimulation.schema(&art.path) else {
+            d.fail(CheckKind::Rule, "parameter-schema", format!("no parameter schema for {}", art.path));
+            return Err(FsError::Denied(Box::new(d.finish())));
+        };
+        match schema.validate(&change.values) {
+            Ok(()) => d.pass(CheckKind::Rule, "parameter-schema", "every value within bounds"),
+            Err(e) => {
+                d.fail(CheckKind::Rule, "parameter-schema", e.join("; "));
+                return Err(FsError::Denied(Box::new(d.finish())));
+            }
+        }
+
+        let (subject_approval, clinician_approval) = (&approvals.subject, &approvals.clinician);
+
+        // Approval 1: the subject.
+        if subject_approval.principal != art.subject_id {
+            d.fail(CheckKind::Token, "subject-approval", "first approval must come from the subject");
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+        self.verify_approval(&mut d, guards, change, "subject-approval", subject_approval);
+        if d.is_denied() {
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+
+        // Approval 2: an independent clinician holding a write grant for clinical care.
+        if clinician.role() != Role::Clinician || clinician.purpose() != Purpose::ClinicalCare {
+            d.fail(
+                CheckKind::Rule,
+                "clinician-approval",
+                "second approval must be a clinician acting for clinical care",
+            );
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+        if clinician.principal() == art.subject_id || clinician_approval.principal != clinician.principal() {
+            d.fail(CheckKind::Rule, "clinician-approval", "approvals must come from two different parties");
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+        match guards
+            .aura
+            .grants()
+            .find_active(clinician, &art, GrantOperation::Write, now)
+        {
+            Some(g) => d.pass(
+                CheckKind::Grant,
+                "delegation-grant",
+                format!("grant {} from {} (expires_at={})", g.grant_id, g.issuer_subject, g.expires_at),
+            ),
+            None => {
+                d.fail(CheckKind::Grant, "delegation-grant", "clinician holds no active write grant");
+                return Err(FsError::Denied(Box::new(d.finish())));
+            }
+        }
+        self.verify_approval(&mut d, guards, change, "clinician-approval", clinician_approval);
+        if !d.is_denied() {
+            d.include(guards.evaluate_dual_controlled(clinician, &art, FsMode::ReadWrite));
+        }
+        let decision = d.finish().into_result()?;
+
+        let mut handle = FsHandle::open_admitted(
+            art.clone(),
+            FsMode::ReadWrite,
+            clinician.clone(),
+            false,
+            decision.clone(),
+            guards,
+            DUAL_CONTROL_WRITE,
+        )?;
+        let backup = handle.read_all()?;
+        let old = Self::parse_or_empty(&backup)?;
+        if !schema.touches_stimulation(&old, &change.values) {
+            return Err(Self::deny_after(
+                &decision,
+                "stimulation-change",
+                "telemetry-only changes go through the normal guarded write path",
+            ));
+        }
+        // Consumed before writing: a rolled-back change is not retried either.
+        self.consume(change_id)?;
+        handle.replace_all(&change.payload())?;
+
+        let verify = FsHandle::open_admitted(
+            art,
+            FsMode::ReadOnly,
+            clinician.clone(),
+            false,
+            decision.clone(),
+            guards,
+            "dual-control-verify",
+        )
+        .and_then(|mut h| h.read_all())
+            .and_then(|bytes| Self::parse(&bytes))
+            .and_then(|written| {
+                schema
+                    .validate(&written)
+                    .map_err(|e| e.join("; "))
+                    .and_then(|_| post_check(&written))
+                    .map_err(FsError::PolicyError)
+            });
+        if let Err(e) = verify {
+            handle.replace_all(&backup)?;
+            return Err(Self::deny_after(
+                &decision,
+                "post-check",
+                &format!("parameter change rolled back: {}", e),
+            ));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifact::ArtifactKind;
+    use crate::grants::{ArtifactSelector, DelegationGrant, GrantBook, GrantRegistry, SubjectKeyring};
+    use crate::guards::AuraBoundaryGuard;
+    use crate::routes::RouteAllowance;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn schema() -> ParameterSchema {
+        let param = |name: &str, class, max| ParamBounds {
+            name: name.into(),
+            class,
+            min: 0.0,
+            max,
+            unit: String::new(),
+        };
+        ParameterSchema {
+            params: vec![
+                param("amplitude", ParamClass::Stimulation, 5.0),
+                param("loop_gain", ParamClass::ClosedLoop, 1.0),
+                param("battery", ParamClass::Telemetry, 100.0),
+            ],
+        }
+    }
+
+    fn values(amplitude: f64, battery: f64) -> BTreeMap<String, f64> {
+        BTreeMap::from([
+            ("amplitude".to_string(), amplitude),
+            ("loop_gain".to_string(), 0.5),
+            ("battery".to_string(), battery),
+        ])
+    }
+
+    /// A stimulation config on disk with amplitude 1.0, and guards that know its schema.
+    fn setup(name: &str) -> (SovereignArtifact, GuardSet) {
+        let dir = std::env::temp_dir().join(format!("neuroxfs-dual-control-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("stimulator.json");
+        std::fs::write(&path, serde_json::to_vec(&values(1.0, 80.0)).unwrap()).unwrap();
+
+        let mut art = SovereignArtifact::sovereign_config(path.display().to_string(), "subject-1");
+        art.kind = ArtifactKind::GenericData;
+        art.routes = vec![Route::Bci];
+        art.route_allowances = vec![RouteAllowance {
+            route: Route::Bci,
+            operations: vec![GrantOperation::Read, GrantOperation::Write],
+        }];
+        art.neurorights.mental_integrity = true;
+        art.governance_tags.clear();
+
+        let mut keyring = SubjectKeyring::default();
+        keyring.insert("subject-1", key(3).verifying_key());
+        keyring.insert("dr-lee", key(4).verifying_key());
+        let mut book = GrantBook::new("subject-1");
+        book.issue(
+            DelegationGrant {
+                grant_id: "stim-1".into(),
+                issuer_subject: "subject-1".into(),
+                grantee: "dr-lee".into(),
+                selector: ArtifactSelector { kinds: vec![ArtifactKind::GenericData], path_prefix: None },
+                operations: vec![GrantOperation::Read, GrantOperation::Write],
+                purpose: Purpose::ClinicalCare,
+                issued_at: 0,
+                expires_at: u64::MAX,
+                revocable: true,
+            },
+            &key(3),
+        )
+        .unwrap();
+        let mut grants = GrantRegistry::new(keyring);
+        grants.load_book(&book).unwrap();
+
+        let guards = GuardSet {
+            aura: AuraBoundaryGuard::new(grants),
+            stimulation: StimulationGuard::default().with_schema(art.path.clone(), schema()),
+            ..GuardSet::default()
+        };
+        (art, guards)
+    }
+
+    fn clinician(route: Route) -> CallerContext {
+        CallerContext::new("dr-lee", Role::Clinician, Purpose::ClinicalCare, route).unwrap()
+    }
+
+    fn approvals(change: &ParameterChange) -> DualApproval {
+        DualApproval {
+            subject: change.approve("subject-1", &key(3)),
+            clinician: change.approve("dr-lee", &key(4)),
+        }
+    }
+
+    fn on_disk(art: &SovereignArtifact) -> BTreeMap<String, f64> {
+        serde_json::from_slice(&std::fs::read(&art.path).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn telemetry_writes_take_the_normal_guarded_path() {
+        let (art, guards) = setup("telemetry");
+        let subject = CallerContext::subject("subject-1", Route::Bci);
+        let mut handle = FsHandle::open(art.clone(), FsMode::WriteOnly, subject.clone(), false, &guards).unwrap();
+
+        handle.replace_all(&serde_json::to_vec(&values(1.0, 70.0)).unwrap()).unwrap();
+        assert_eq!(on_disk(&art)["battery"], 70.0);
+
+        match handle.replace_all(&serde_json::to_vec(&values(2.0, 70.0)).unwrap()).unwrap_err() {
+            FsError::Denied(d) => assert!(d.summary().contains("amplitude"), "{}", d.summary()),
+            other => panic!("expected a guard decision, got {}", other),
+        }
+        assert!(matches!(handle.write_all(b"{}"), Err(FsError::Denied(_))));
+        assert_eq!(on_disk(&art)["amplitude"], 1.0);
+
+        // Without a schema a write cannot be told apart from a stimulation change.
+        let unknown = GuardSet { stimulation: StimulationGuard::default(), ..guards };
+        assert!(matches!(
+            FsHandle::open(art, FsMode::WriteOnly, subject, false, &unknown),
+            Err(FsError::Denied(_))
+        ));
+    }
+
+    #[test]
+    fn co_signed_changes_apply_once() {
+        let (art, guards) = setup("replay");
+        let log = Path::new(&art.path).with_file_name("consumed.log");
+        let mut writer = DualControlWriter::open(&log).unwrap();
+        let change = ParameterChange::new(&art, values(2.0, 80.0), 1);
+        let approved = approvals(&change);
+
+        writer
+            .apply(art.clone(), &change, &approved, &clinician(Route::Bci), &guards, |_| Ok(()))
+            .unwrap();
+        assert_eq!(on_disk(&art)["amplitude"], 2.0);
+
+        // Restore the old values, then replay the same co-signed change.
+        std::fs::write(&art.path, serde_json::to_vec(&values(1.0, 80.0)).unwrap()).unwrap();
+        let err = writer
+            .apply(art.clone(), &change, &approved, &clinician(Route::Bci), &guards, |_| Ok(()))
+            .unwrap_err();
+        assert!(err.to_string().contains("already applied"), "{}", err);
+
+        // The consumed ids are on disk, so a restarted writer refuses it too.
+        drop(writer);
+        let mut restarted = DualControlWriter::open(&log).unwrap();
+        assert_eq!(restarted.consumed().collect::<Vec<_>>(), [change.change_id()]);
+        assert!(restarted
+            .apply(art.clone(), &change, &approved, &clinician(Route::Bci), &guards, |_| Ok(()))
+            .is_err());
+        assert_eq!(on_disk(&art)["amplitude"], 1.0);
+
+        // A fresh nonce is a new change.
+        let again = ParameterChange::new(&art, values(2.0, 80.0), 2);
+        restarted
+            .apply(art.clone(), &again, &approvals(&again), &clinician(Route::Bci), &guards, |_| Ok(()))
+            .unwrap();
+    }
+
+    #[test]
+    fn the_schema_comes_from_the_stimulation_guard() {
+        let (art, guards) = setup("schema");
+        let mut writer = DualControlWriter::new();
+
+        // The bound registered with the guard is the one co-signed writes meet.
+        let mut strict = schema();
+        strict.params[0].max = 1.5;
+        let strict = GuardSet {
+            stimulation: StimulationGuard::default().with_schema(art.path.clone(), strict),
+            ..guards.clone()
+        };
+        let change = ParameterChange::new(&art, values(2.0, 80.0), 1);
+        let err = writer
+            .apply(art.clone(), &change, &approvals(&change), &clinician(Route::Bci), &strict, |_| Ok(()))
+            .unwrap_err();
+        assert!(err.to_string().contains("amplitude: 2 outside"), "{}", err);
+
+        let none = GuardSet { stimulation: StimulationGuard::default(), ..guards };
+        let err = writer
+            .apply(art.clone(), &change, &approvals(&change), &clinician(Route::Bci), &none, |_| Ok(()))
+            .unwrap_err();
+        assert!(err.to_string().contains("no parameter schema"), "{}", err);
+        assert_eq!(on_disk(&art)["amplitude"], 1.0);
+    }
+
+    #[test]
+    fn expired_or_tampered_changes_are_refused() {
+        let (art, guards) = setup("expired");
+        let mut writer = DualControlWriter::new();
+        let mut change = ParameterChange::new(&art, values(2.0, 80.0), 1);
+        change.expires_at = change.created_at;
+        let err = writer
+            .apply(art.clone(), &change, &approvals(&change), &clinician(Route::Bci), &guards, |_| Ok(()))
+            .unwrap_err();
+        assert!(err.to_string().contains("expired"), "{}", err);
+
+        // Extending the deadline after signing breaks both signatures.
+        let stale = approvals(&change);
+        change.expires_at += DEFAULT_CHANGE_TTL_SECS;
+        let err = writer
+            .apply(art.clone(), &change, &stale, &clinician(Route::Bci), &guards, |_| Ok(()))
+            .unwrap_err();
+        assert!(err.to_string().contains("does not verify"), "{}", err);
+        assert_eq!(on_disk(&art)["amplitude"], 1.0);
+    }
+
+    #[test]
+    fn co_signed_changes_still_pass_the_guard_set() {
+        let (art, guards) = setup("guards");
+        let mut writer = DualControlWriter::new();
+        let change = ParameterChange::new(&art, values(2.0, 80.0), 1);
+        let err = writer
+            .apply(art.clone(), &change, &approvals(&change), &clinician(Route::Chat), &guards, |_| Ok(()))
+            .unwrap_err();
+        assert!(err.to_string().contains("not exposed on Chat"), "{}", err);
+
+        let err = writer
+            .apply(art.clone(), &change, &approvals(&change), &clinician(Route::Bci), &guards, |_| {
+                Err("device did not acknowledge".into())
+            })
+            .unwrap_err();
+        assert!(err.to_string().contains("rolled back"), "{}", err);
+        assert_eq!(on_disk(&art)["amplitude"], 1.0);
+    }
+
+    #[test]
+    fn every_co_signed_decision_is_recorded() {
+        use crate::audit::AuditChain;
+        use std::sync::{Arc, Mutex};
+
+        let (art, guards) = setup("recorded");
+        let guards = GuardSet { audit: Some(Arc::new(Mutex::new(AuditChain::new()))), ..guards };
+        let mut writer = DualControlWriter::new();
+        let change = ParameterChange::new(&art, values(2.0, 80.0), 1);
+        writer
+            .apply(art.clone(), &change, &approvals(&change), &clinician(Route::Bci), &guards, |_| Ok(()))
+            .unwrap();
+        assert!(writer
+            .apply(art.clone(), &change, &approvals(&change), &clinician(Route::Bci), &guards, |_| Ok(()))
+            .is_err());
+
+        let audit = guards.audit.as_ref().unwrap().lock().unwrap();
+        let actions: Vec<_> = audit.records().map(|r| r.body.action.as_str()).collect();
+        assert_eq!(actions, [DUAL_CONTROL_WRITE, "dual-control-verify", DUAL_CONTROL_WRITE]);
+        let last = audit.records().last().unwrap();
+        assert!(last.body.detail.contains("already applied"), "{}", last.body.detail);
+    }
+}
@@ -1,9 +1,12 @@
 use std::io;
 
+use crate::explain::GuardDecision;
+
 #[derive(Debug)]
 pub enum FsError {
     Io(io::Error),
     GuardError(String),
+    Denied(Box<GuardDecision>),
     ModeError(String),
     PolicyError(String),
 }
@@ -13,6 +16,7 @@ impl std::fmt::Display for FsError {
         match self {
             FsError::Io(e) => write!(f, "IO error: {}", e),
             FsError::GuardError(m) => write!(f, "Guard error: {}", m),
+            FsError::Denied(d) => write!(f, "Guard denied: {}", d.summary()),
             FsError::ModeError(m) => write!(f, "Mode error: {}", m),
             FsError::PolicyError(m) => write!(f, "Policy error: {}", m),
         }
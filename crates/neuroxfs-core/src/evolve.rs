@@ -0,0 +1,499 @@
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::FsError;
+use crate::fs_handle::FsHandle;
+
+/// One setting change, addressed by JSON Pointer (RFC 6901) into the config.
+/// `value: None` removes the setting.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SettingChange {
+    pub pointer: String,
+    pub value: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EvolveEventBody {
+    Proposal { proposal_id: u64, changes: Vec<SettingChange> },
+    Approval { proposal_id: u64, approver: String },
+    Apply { proposal_id: u64 },
+    /// Undo an applied proposal, restoring the values it replaced.
+    Revert { proposal_id: u64 },
+}
+
+/// One line of `.evolve.jsonl`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvolveEvent {
+    pub seq: u64,
+    pub timestamp: u64,
+    pub subject_id: String,
+    pub actor: String,
+    #[serde(flatten)]
+    pub body: EvolveEventBody,
+}
+
+/// Where to stop replaying (inclusive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReplayPoint {
+    Head,
+    Seq(u64),
+    Timestamp(u64),
+}
+
+impl ReplayPoint {
+    fn includes(self, ev: &EvolveEvent) -> bool {
+        match self {
+            ReplayPoint::Head => true,
+            ReplayPoint::Seq(s) => ev.seq <= s,
+            ReplayPoint::Timestamp(t) => ev.timestamp <= t,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct ProposalState {
+    seq: u64,
+    changes: Vec<SettingChange>,
+    approvers: Vec<String>,
+    /// Prior values and their provenance, captured at apply time; `Some` while applied.
+    replaced: Option<Vec<Replaced>>,
+}
+
+#[derive(Debug, Clone)]
+struct Replaced {
+    pointer: String,
+    value: Option<Value>,
+    provenance: Option<Provenance>,
+}
+
+/// Config state rebuilt from the stream, with per-setting provenance.
+#[derive(Debug, Clone)]
+pub struct ReplayedConfig {
+    pub config: Value,
+    pub last_seq: Option<u64>,
+    /// pointer -> the apply event whose value is currently in effect.
+    provenance: BTreeMap<String, Provenance>,
+    proposals: HashMap<u64, ProposalState>,
+}
+
+/// Answer to "which evolve event introduced this setting".
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub pointer: String,
+    /// The apply event whose value is in effect.
+    pub applied_seq: u64,
+    pub proposal_id: u64,
+    /// Where the proposal itself was declared.
+    pub proposal_seq: u64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Drift {
+    pub pointer: String,
+    pub replayed: Option<Value>,
+    pub on_disk: Option<Value>,
+}
+
+fn escape_token(t: &str) -> String {
+    t.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape_token(t: &str) -> String {
+    t.replace("~1", "/").replace("~0", "~")
+}
+
+fn set_pointer(root: &mut Value, pointer: &str, value: Option<Value>) -> Result<(), String> {
+    if pointer.is_empty() || !pointer.starts_with('/') {
+        return Err(format!("`{}` is not a setting pointer", pointer));
+    }
+    let tokens: Vec<String> = pointer[1..].split('/').map(unescape_token).collect();
+    let (last, parents) = tokens.split_last().expect("split of a non-empty pointer");
+    let mut node = root;
+    for token in parents {
+        if !node.is_object() {
+            return Err(format!("`{}` crosses a non-object value", pointer));
+        }
+        node = node
+            .as_object_mut()
+            .expect("checked above")
+            .entry(token.clone())
+            .or_insert_with(|| Value::Object(Default::default()));
+    }
+    let obj = node
+        .as_object_mut()
+        .ok_or_else(|| format!("`{}` crosses a non-object value", pointer))?;
+    match value {
+        Some(v) => {
+            obj.insert(last.clone(), v);
+        }
+        None => {
+            obj.remove(last);
+        }
+    }
+    Ok(())
+}
+
+/// Leaf values by pointer; objects are descended, everything else is a leaf.
+fn flatten(value: &Value, prefix: &str, out: &mut BTreeMap<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (k, v) in map {
+                flatten(v, &format!("{}/{}", prefix, escape_token(k)), out);
+            }
+        }
+        Value::Object(_) if prefix.is_empty() => {}
+        other => {
+            out.insert(prefix.to_string(), other.clone());
+        }
+    }
+}
+
+impl ReplayedConfig {
+    /// Which applied proposal set `pointer` (or its nearest recorded ancestor).
+    /// Reverts hand provenance back to whatever was in effect before.
+    pub fn provenance(&self, pointer: &str) -> Option<Provenance> {
+        let mut candidate = pointer.to_string();
+        loop {
+            if let Some(found) = self.provenance.get(&candidate) {
+                return Some(found.clone());
+            }
+            candidate.truncate(candidate.rfind('/')?);
+            if candidate.is_empty() {
+                return None;
+            }
+        }
+    }
+
+    /// Settings whose on-disk value differs from what the stream replays to.
+    pub fn drift(&self, on_disk: &Value) -> Vec<Drift> {
+        let (mut replayed, mut disk) = (BTreeMap::new(), BTreeMap::new());
+        flatten(&self.config, "", &mut replayed);
+        flatten(on_disk, "", &mut disk);
+        let mut pointers: Vec<&String> = replayed.keys().chain(disk.keys()).collect();
+        pointers.sort();
+        pointers.dedup();
+        pointers
+            .into_iter()
+            .filter(|p| replayed.get(*p) != disk.get(*p))
+            .map(|p| Drift {
+                pointer: p.clone(),
+                replayed: replayed.get(p).cloned(),
+                on_disk: disk.get(p).cloned(),
+            })
+            .collect()
+    }
+}
+
+/// The parsed `.evolve.jsonl` stream for one subject's config.
+#[derive(Debug, Clone, Default)]
+pub struct EvolveStream {
+    events: Vec<EvolveEvent>,
+}
+
+impl EvolveStream {
+    pub fn events(&self) -> &[EvolveEvent] {
+        &self.events
+    }
+
+    pub fn append(&mut self, timestamp: u64, subject_id: &str, actor: &str, body: EvolveEventBody) -> Result<&EvolveEvent, FsError> {
+        if self.events.last().is_some_and(|last| timestamp < last.timestamp) {
+            return Err(FsError::PolicyError("evolve event timestamp goes backwards".into()));
+        }
+        self.events.push(EvolveEvent {
+            seq: self.events.len() as u64,
+            timestamp,
+            subject_id: subject_id.to_string(),
+            actor: actor.to_string(),
+            body,
+        });
+        Ok(self.events.last().expect("just pushed"))
+    }
+
+    pub fn from_jsonl(text: &str) -> Result<Self, FsError> {
+        let mut stream = Self::default();
+        for (idx, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let ev: EvolveEvent = serde_json::from_str(line)
+                .map_err(|e| FsError::PolicyError(format!("evolve line {}: {}", idx + 1, e)))?;
+            if ev.seq != stream.events.len() as u64 {
+                return Err(FsError::PolicyError(format!(
+                    "evolve line {}: seq {} out of order (expected {})",
+                    idx + 1,
+                    ev.seq,
+                    stream.events.len()
+                )));
+            }
+            if stream.events.last().is_some_and(|last| ev.timestamp < last.timestamp) {
+                return Err(FsError::PolicyError(format!("evolve line {}: timestamp goes backwards", idx + 1)));
+            }
+            stream.events.push(ev);
+        }
+        Ok(stream)
+    }
+
+    pub fn to_jsonl(&self) -> String {
+        self.events
+            .iter()
+            .map(|e| serde_json::to_string(e).expect("evolve event serialization is infallible") + "\n")
+            .collect()
+    }
+
+    pub fn load(handle: &mut FsHandle) -> Result<Self, FsError> {
+        let bytes = handle.read_all()?;
+        let text = String::from_utf8(bytes).map_err(|_| FsError::PolicyError("evolve stream is not UTF-8".into()))?;
+        Self::from_jsonl(&text)
+    }
+
+    pub fn store(&self, handle: &mut FsHandle) -> Result<(), FsError> {
+        handle.replace_all(self.to_jsonl().as_bytes())
+    }
+
+    /// Rebuild the config up to `point`. An apply needs `min_approvals`
+    /// distinct approvers; an inconsistent stream is an error, not skipped.
+    pub fn replay(&self, point: ReplayPoint, min_approvals: usize) -> Result<ReplayedConfig, FsError> {
+        let mut state = ReplayedConfig {
+            config: Value::Object(Default::default()),
+            last_seq: None,
+            provenance: BTreeMap::new(),
+            proposals: HashMap::new(),
+        };
+        let bad = |ev: &EvolveEvent, msg: String| FsError::PolicyError(format!("evolve seq {}: {}", ev.seq, msg));
+
+        for ev in self.events.iter().take_while(|e| point.includes(e)) {
+            match &ev.body {
+                EvolveEventBody::Proposal { proposal_id, changes } => {
+                    if state.proposals.contains_key(proposal_id) {
+                        return Err(bad(ev, format!("proposal {} declared twice", proposal_id)));
+                    }
+                    state.proposals.insert(
+                        *proposal_id,
+                        ProposalState {
+                            seq: ev.seq,
+                            changes: changes.clone(),
+                            approvers: Vec::new(),
+                            replaced: None,
+                        },
+                    );
+                }
+                EvolveEventBody::Approval { proposal_id, approver } => {
+                    let p = state
+                        .proposals
+                        .get_mut(proposal_id)
+                        .ok_or_else(|| bad(ev, format!("approval for unknown proposal {}", proposal_id)))?;
+                    if !p.approvers.contains(approver) {
+                        p.approvers.push(approver.clone());
+                    }
+                }
+                EvolveEventBody::Apply { proposal_id } => {
+                    let p = state
+                        .proposals
+                        .get(proposal_id)
+                        .ok_or_else(|| bad(ev, format!("apply of unknown proposal {}", proposal_id)))?;
+                    if p.replaced.is_some() {
+                        return Err(bad(ev, format!("proposal {} already applied", proposal_id)));
+                    }
+                    if p.approvers.len() < min_approvals {
+                        return Err(bad(
+                            ev,
+                            format!("proposal {} applied with {} of {} approvals", proposal_id, p.approvers.len(), min_approvals),
+                        ));
+                    }
+                    let (changes, proposal_seq) = (p.changes.clone(), p.seq);
+                    let mut replaced = Vec::new();
+                    for c in &changes {
+                        let old = state.config.pointer(&c.pointer).cloned();
+                        set_pointer(&mut state.config, &c.pointer, c.value.clone()).map_err(|m| bad(ev, m))?;
+                        let previous = state.provenance.insert(
+                            c.pointer.clone(),
+                            Provenance {
+                                pointer: c.pointer.clone(),
+                                applied_seq: ev.seq,
+                                proposal_id: *proposal_id,
+                                proposal_seq,
+                            },
+                        );
+                        replaced.push(Replaced {
+                            pointer: c.pointer.clone(),
+                            value: old,
+                            provenance: previous,
+                        });
+                    }
+                    state.proposals.get_mut(proposal_id).expect("looked up above").replaced = Some(replaced);
+                }
+                EvolveEventBody::Revert { proposal_id } => {
+                    let replaced = state
+                        .proposals
+                        .get_mut(proposal_id)
+                        .and_then(|p| p.replaced.take())
+                        .ok_or_else(|| bad(ev, format!("revert of proposal {} which is not applied", proposal_id)))?;
+                    for r in replaced.into_iter().rev() {
+                        set_pointer(&mut state.config, &r.pointer, r.value).map_err(|m| bad(ev, m))?;
+                        match r.provenance {
+                            Some(p) => state.provenance.insert(r.pointer, p),
+                            None => state.provenance.remove(&r.pointer),
+                        };
+                    }
+                }
+            }
+            state.last_seq = Some(ev.seq);
+        }
+        Ok(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn change(pointer: &str, value: Option<Value>) -> SettingChange {
+        SettingChange { pointer: pointer.into(), value }
+    }
+
+    fn propose(s: &mut EvolveStream, ts: u64, proposal_id: u64, changes: Vec<SettingChange>) {
+        s.append(ts, "subject-1", "subject-1", EvolveEventBody::Proposal { proposal_id, changes })
+            .unwrap();
+    }
+
+    fn approve(s: &mut EvolveStream, ts: u64, proposal_id: u64, approver: &str) {
+        s.append(ts, "subject-1", approver, EvolveEventBody::Approval { proposal_id, approver: approver.into() })
+            .unwrap();
+    }
+
+    fn apply(s: &mut EvolveStream, ts: u64, proposal_id: u64) {
+        s.append(ts, "subject-1", "subject-1", EvolveEventBody::Apply { proposal_id }).unwrap();
+    }
+
+    fn revert(s: &mut EvolveStream, ts: u64, proposal_id: u64) {
+        s.append(ts, "subject-1", "subject-1", EvolveEventBody::Revert { proposal_id }).unwrap();
+    }
+
+    /// Proposal 1 sets the volume and a theme (seq 0-2), proposal 2 raises the volume (seq 3-5).
+    fn two_proposals() -> EvolveStream {
+        let mut s = EvolveStream::default();
+        propose(&mut s, 10, 1, vec![change("/audio/volume", Some(json!(3))), change("/ui/theme", Some(json!("dark")))]);
+        approve(&mut s, 11, 1, "guardian-a");
+        apply(&mut s, 12, 1);
+        propose(&mut s, 20, 2, vec![change("/audio/volume", Some(json!(5)))]);
+        approve(&mut s, 21, 2, "guardian-a");
+        apply(&mut s, 22, 2);
+        s
+    }
+
+    #[test]
+    fn replay_stops_at_the_requested_point() {
+        let s = two_proposals();
+        let head = s.replay(ReplayPoint::Head, 1).unwrap();
+        assert_eq!(head.config, json!({"audio": {"volume": 5}, "ui": {"theme": "dark"}}));
+        assert_eq!(head.last_seq, Some(5));
+
+        let before = s.replay(ReplayPoint::Seq(4), 1).unwrap();
+        assert_eq!(before.config["audio"]["volume"], json!(3));
+        assert_eq!(before.last_seq, Some(4));
+        let at = s.replay(ReplayPoint::Timestamp(12), 1).unwrap();
+        assert_eq!(at.config["audio"]["volume"], json!(3));
+        assert_eq!(s.replay(ReplayPoint::Timestamp(5), 1).unwrap().last_seq, None);
+    }
+
+    #[test]
+    fn provenance_names_the_apply_in_effect() {
+        let head = two_proposals().replay(ReplayPoint::Head, 1).unwrap();
+        let p = head.provenance("/audio/volume").unwrap();
+        assert_eq!((p.applied_seq, p.proposal_id, p.proposal_seq), (5, 2, 3));
+        assert_eq!(head.provenance("/ui/theme").unwrap().proposal_id, 1);
+        // A pointer below a recorded setting resolves to its nearest ancestor.
+        assert_eq!(head.provenance("/ui/theme/accent").unwrap().pointer, "/ui/theme");
+        assert!(head.provenance("/network").is_none());
+    }
+
+    #[test]
+    fn reverts_restore_values_and_provenance() {
+        let mut s = two_proposals();
+        revert(&mut s, 30, 2);
+        let r = s.replay(ReplayPoint::Head, 1).unwrap();
+        assert_eq!(r.config["audio"]["volume"], json!(3));
+        assert_eq!(r.provenance("/audio/volume").unwrap().proposal_id, 1);
+
+        revert(&mut s, 31, 1);
+        let r = s.replay(ReplayPoint::Head, 1).unwrap();
+        assert_eq!(r.config, json!({"audio": {}, "ui": {}}));
+        assert!(r.provenance("/audio/volume").is_none());
+
+        revert(&mut s, 32, 1);
+        let err = s.replay(ReplayPoint::Head, 1).unwrap_err();
+        assert!(err.to_string().contains("revert of proposal 1 which is not applied"), "{}", err);
+    }
+
+    #[test]
+    fn applies_need_distinct_approvers() {
+        let mut s = EvolveStream::default();
+        propose(&mut s, 1, 7, vec![change("/a~1b", Some(json!(true)))]);
+        approve(&mut s, 2, 7, "guardian-a");
+        approve(&mut s, 3, 7, "guardian-a");
+        apply(&mut s, 4, 7);
+        let err = s.replay(ReplayPoint::Head, 2).unwrap_err();
+        assert!(err.to_string().contains("applied with 1 of 2 approvals"), "{}", err);
+
+        let mut s = EvolveStream::default();
+        propose(&mut s, 1, 7, vec![change("/a~1b", Some(json!(true)))]);
+        approve(&mut s, 2, 7, "guardian-a");
+        approve(&mut s, 3, 7, "guardian-b");
+        apply(&mut s, 4, 7);
+        // `~1` in a pointer is an escaped `/` inside one key.
+        assert_eq!(s.replay(ReplayPoint::Head, 2).unwrap().config, json!({"a/b": true}));
+    }
+
+    #[test]
+    fn inconsistent_streams_are_errors() {
+        let broken = |edit: fn(&mut EvolveStream)| {
+            let mut s = two_proposals();
+            edit(&mut s);
+            s.replay(ReplayPoint::Head, 1).unwrap_err().to_string()
+        };
+        assert!(broken(|s| apply(s, 40, 9)).contains("apply of unknown proposal 9"));
+        assert!(broken(|s| apply(s, 40, 1)).contains("proposal 1 already applied"));
+        assert!(broken(|s| propose(s, 40, 2, Vec::new())).contains("proposal 2 declared twice"));
+
+        let mut s = EvolveStream::default();
+        propose(&mut s, 1, 1, vec![change("volume", Some(json!(1)))]);
+        approve(&mut s, 2, 1, "guardian-a");
+        apply(&mut s, 3, 1);
+        assert!(s.replay(ReplayPoint::Head, 1).unwrap_err().to_string().contains("not a setting pointer"));
+    }
+
+    #[test]
+    fn drift_compares_leaves() {
+        let head = two_proposals().replay(ReplayPoint::Head, 1).unwrap();
+        assert!(head.drift(&json!({"audio": {"volume": 5}, "ui": {"theme": "dark"}})).is_empty());
+
+        let drift = head.drift(&json!({"audio": {"volume": 9}, "ui": {"theme": "dark"}, "debug": true}));
+        assert_eq!(
+            drift,
+            [
+                Drift { pointer: "/audio/volume".into(), replayed: Some(json!(5)), on_disk: Some(json!(9)) },
+                Drift { pointer: "/debug".into(), replayed: None, on_disk: Some(json!(true)) },
+            ]
+        );
+    }
+
+    #[test]
+    fn jsonl_round_trips_and_checks_order() {
+        let s = two_proposals();
+        let text = s.to_jsonl();
+        assert_eq!(EvolveStream::from_jsonl(&text).unwrap().events(), s.events());
+
+        let lines: Vec<&str> = text.lines().collect();
+        let swapped = [lines[1], lines[0]].join("\n");
+        let err = EvolveStream::from_jsonl(&swapped).unwrap_err();
+        assert!(err.to_string().contains("line 1: seq 1 out of order"), "{}", err);
+
+        let backwards = text.replacen("\"timestamp\":11", "\"timestamp\":9", 1);
+        let err = EvolveStream::from_jsonl(&backwards).unwrap_err();
+        assert!(err.to_string().contains("line 2: timestamp goes backwards"), "{}", err);
+
+        let mut s = s;
+        assert!(s.append(1, "subject-1", "subject-1", EvolveEventBody::Apply { proposal_id: 1 }).is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::FsError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Outcome {
+    Allow,
+    Deny,
+    Redact,
+}
+
+/// What sort of input a reason node evaluated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CheckKind {
+    Guard,
+    Flag,
+    Rule,
+    Token,
+    Threshold,
+    Grant,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CheckResult {
+    Pass,
+    Fail,
+    NotApplicable,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReasonNode {
+    pub kind: CheckKind,
+    pub label: String,
+    pub detail: String,
+    pub result: CheckResult,
+    /// True on the node that determined the outcome.
+    pub decisive: bool,
+    pub children: Vec<ReasonNode>,
+}
+
+impl ReasonNode {
+    fn render_into(&self, depth: usize, out: &mut String) {
+        let mark = match self.result {
+            CheckResult::Pass => "pass",
+            CheckResult::Fail => "FAIL",
+            CheckResult::NotApplicable => "n/a",
+        };
+        out.push_str(&format!(
+            "{}[{}]{} {:?} {}: {}\n",
+            "  ".repeat(depth),
+            mark,
+            if self.decisive { " <- decisive" } else { "" },
+            self.kind,
+            self.label,
+            self.detail
+        ));
+        for child in &self.children {
+            child.render_into(depth + 1, out);
+        }
+    }
+
+    /// First decisive node, depth first. A denial is explained by a failing
+    /// node, never by a redaction that happened to be recorded before it.
+    fn find_decisive(&self, denied: bool) -> Option<&ReasonNode> {
+        let fits = self.decisive && (!denied || self.result == CheckResult::Fail);
+        self.children
+            .iter()
+            .find_map(|c| c.find_decisive(denied))
+            .or(if fits { Some(self) } else { None })
+    }
+}
+
+/// A guard's verdict together with everything it looked at.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardDecision {
+    pub guard: String,
+    pub outcome: Outcome,
+    pub reason: ReasonNode,
+}
+
+impl GuardDecision {
+    pub fn is_allowed(&self) -> bool {
+        self.outcome != Outcome::Deny
+    }
+
+    /// One-line explanation taken from the decisive node.
+    pub fn summary(&self) -> String {
+        match self.reason.find_decisive(self.outcome == Outcome::Deny) {
+            Some(node) => format!("{}: {}", self.guard, node.detail),
+            None => format!("{}: {:?}", self.guard, self.outcome),
+        }
+    }
+
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::to_value(self).expect("decision serialization is infallible")
+    }
+
+    pub fn render_text(&self) -> String {
+        let mut out = format!("{:?} by {}\n", self.outcome, self.guard);
+        self.reason.render_into(1, &mut out);
+        out
+    }
+
+    /// Allowed decisions pass through; denials become `FsError::Denied`.
+    pub fn into_result(self) -> Result<GuardDecision, FsError> {
+        if self.is_allowed() {
+            Ok(self)
+        } else {
+            Err(FsError::Denied(Box::new(self)))
+        }
+    }
+}
+
+/// Accumulates reason nodes while a guard runs.
+#[derive(Debug)]
+pub struct DecisionBuilder {
+    guard: String,
+    action: String,
+    checks: Vec<ReasonNode>,
+    outcome: Outcome,
+}
+
+impl DecisionBuilder {
+    pub fn new(guard: &str, action: &str) -> Self {
+        Self {
+            guard: guard.to_string(),
+            action: action.to_string(),
+            checks: Vec::new(),
+            outcome: Outcome::Allow,
+        }
+    }
+
+    fn push(&mut self, kind: CheckKind, label: &str, detail: String, result: CheckResult, decisive: bool) {
+        self.checks.push(ReasonNode {
+            kind,
+            label: label.to_string(),
+            detail,
+            result,
+            decisive,
+            children: Vec::new(),
+        });
+    }
+
+    pub fn pass(&mut self, kind: CheckKind, label: &str, detail: impl Into<String>) {
+        self.push(kind, label, detail.into(), CheckResult::Pass, false);
+    }
+
+    pub fn skip(&mut self, kind: CheckKind, label: &str, detail: impl Into<String>) {
+        self.push(kind, label, detail.into(), CheckResult::NotApplicable, false);
+    }
+
+    /// Record the failing check that decides a denial.
+    pub fn fail(&mut self, kind: CheckKind, label: &str, detail: impl Into<String>) {
+        self.push(kind, label, detail.into(), CheckResult::Fail, true);
+        self.outcome = Outcome::Deny;
+    }
+
+    /// Record the check that downgrades an allow to a redacted result.
+    pub fn redact(&mut self, kind: CheckKind, label: &str, detail: impl Into<String>) {
+        self.push(kind, label, detail.into(), CheckResult::Pass, true);
+        if self.outcome == Outcome::Allow {
+            self.outcome = Outcome::Redact;
+        }
+    }
+
+    /// Nest another guard's tree under this one.
+    pub fn include(&mut self, decision: GuardDecision) {
+        let failed = decision.outcome == Outcome::Deny;
+        let redacted = decision.outcome == Outcome::Redact;
+        let mut reason = decision.reason;
+        if decision.outcome == Outcome::Allow {
+            // A plain allow never decides the enclosing outcome.
+            reason.decisive = false;
+        }
+        self.checks.push(reason);
+        if failed {
+            self.outcome = Outcome::Deny;
+        } else if redacted && self.outcome == Outcome::Allow {
+            self.outcome = Outcome::Redact;
+        }
+    }
+
+    pub fn is_denied(&self) -> bool {
+        self.outcome == Outcome::Deny
+    }
+
+    pub fn finish(self) -> GuardDecision {
+        let denied = self.outcome == Outcome::Deny;
+        let decided_by_child = self.checks.iter().any(|c| c.find_decisive(denied).is_some());
+        let (result, detail) = match self.outcome {
+            Outcome::Allow => (CheckResult::Pass, format!("{} allowed: all checks passed", self.action)),
+            Outcome::Redact => (CheckResult::Pass, format!("{} allowed with redaction", self.action)),
+            Outcome::Deny => (CheckResult::Fail, format!("{} denied", self.action)),
+        };
+        GuardDecision {
+            guard: self.guard.clone(),
+            outcome: self.outcome,
+            reason: ReasonNode {
+                kind: CheckKind::Guard,
+                label: self.guard,
+                detail,
+                result,
+                decisive: !decided_by_child,
+                children: self.checks,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn redacting_plugin() -> GuardDecision {
+        let mut d = DecisionBuilder::new("plugin-1", "read");
+        d.redact(CheckKind::Rule, "strip-location", "location fields redacted");
+        d.finish()
+    }
+
+    fn denying_plugin() -> GuardDecision {
+        let mut d = DecisionBuilder::new("plugin-2", "read");
+        d.pass(CheckKind::Flag, "consent", "consent on file");
+        d.fail(CheckKind::Rule, "site-freeze", "site change freeze");
+        d.finish()
+    }
+
+    #[test]
+    fn denials_are_explained_by_a_failing_node() {
+        let mut d = DecisionBuilder::new("Plugins", "read");
+        d.include(redacting_plugin());
+        d.include(denying_plugin());
+        let decision = d.finish();
+        assert_eq!(decision.outcome, Outcome::Deny);
+        assert_eq!(decision.summary(), "Plugins: site change freeze");
+        match decision.into_result().unwrap_err() {
+            FsError::Denied(d) => assert_eq!(d.summary(), "Plugins: site change freeze"),
+            other => panic!("expected a guard decision, got {}", other),
+        }
+    }
+
+    #[test]
+    fn redactions_and_plain_allows_explain_themselves() {
+        let mut d = DecisionBuilder::new("Plugins", "read");
+        d.include(redacting_plugin());
+        assert_eq!(d.finish().summary(), "Plugins: location fields redacted");
+
+        let mut d = DecisionBuilder::new("Plugins", "read");
+        d.pass(CheckKind::Flag, "consent", "consent on file");
+        let decision = d.finish();
+        assert_eq!(decision.summary(), "Plugins: read allowed: all checks passed");
+        assert!(decision.reason.decisive);
+    }
+
+    #[test]
+    fn included_allows_never_decide() {
+        let mut inner = DecisionBuilder::new("inner", "read");
+        inner.pass(CheckKind::Flag, "consent", "consent on file");
+        let mut d = DecisionBuilder::new("outer", "read");
+        d.include(inner.finish());
+        let decision = d.finish();
+        assert!(!decision.reason.children[0].decisive);
+        assert!(decision.reason.decisive);
+    }
+
+    #[test]
+    fn json_keeps_the_whole_tree() {
+        let mut d = DecisionBuilder::new("Plugins", "read");
+        d.include(denying_plugin());
+        let json = d.finish().to_json();
+        assert_eq!(json["guard"], "Plugins");
+        assert_eq!(json["outcome"], "Deny");
+        assert_eq!(json["reason"]["kind"], "Guard");
+        assert_eq!(json["reason"]["result"], "Fail");
+        assert_eq!(json["reason"]["decisive"], false);
+        let plugin = &json["reason"]["children"][0];
+        assert_eq!(plugin["label"], "plugin-2");
+        assert_eq!(plugin["detail"], "read denied");
+        let checks = plugin["children"].as_array().unwrap();
+        assert_eq!(checks.len(), 2);
+        assert_eq!(checks[1]["label"], "site-freeze");
+        assert_eq!(checks[1]["kind"], "Rule");
+        assert_eq!(checks[1]["decisive"], true);
+        assert!(checks[1]["children"].as_array().unwrap().is_empty());
+    }
+
+    #[test]
+    fn text_indents_children_and_marks_the_decisive_node() {
+        let mut d = DecisionBuilder::new("Plugins", "read");
+        d.skip(CheckKind::Token, "evolve-token", "no token presented");
+        d.include(denying_plugin());
+        assert_eq!(
+            d.finish().render_text(),
+            "Deny by Plugins\n\
+             \x20 [FAIL] Guard Plugins: read denied\n\
+             \x20   [n/a] Token evolve-token: no token presented\n\
+             \x20   [FAIL] Guard plugin-2: read denied\n\
+             \x20     [pass] Flag consent: consent on file\n\
+             \x20     [FAIL] <- decisive Rule site-freeze: site change freeze\n"
+        );
+    }
+}
@@ -1,10 +1,16 @@
 use crate::artifact::SovereignArtifact;
-use crate::guards::{AuraBoundaryGuard, SovereignKernelLock};
+use crate::caller::CallerContext;
+use crate::dual_control::{requires_dual_control, ParameterSchema, StimulationGuard};
+use crate::guards::GuardSet;
 use crate::error::FsError;
-use std::io::{Read, Write};
+use crate::explain::{CheckKind, DecisionBuilder, GuardDecision, Outcome};
+use crate::liberty::HandleLease;
+use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
 use std::fs::{File, OpenOptions};
 
-#[derive(Debug, Clone, Copy)]
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
 pub enum FsMode {
     ReadOnly,
     WriteOnly,
@@ -16,36 +22,62 @@ pub struct FsHandle {
     artifact: SovereignArtifact,
     file: File,
     mode: FsMode,
-    aura_guard: AuraBoundaryGuard,
-    kernel_lock: SovereignKernelLock,
-    caller_subject: String,
+    caller: CallerContext,
     via_evolve_token: bool,
+    decision: GuardDecision,
+    lease: Option<HandleLease>,
+    /// Set on single-party writes to dual-control configs; see `StimulationGuard`.
+    stimulation: Option<ParameterSchema>,
 }
 
 impl FsHandle {
     pub fn open(
         artifact: SovereignArtifact,
         mode: FsMode,
-        caller_subject: String,
+        caller: CallerContext,
         via_evolve_token: bool,
+        guards: &GuardSet,
     ) -> Result<Self, FsError> {
-        let aura_guard = AuraBoundaryGuard;
-        let kernel_lock = SovereignKernelLock;
+        let decision = guards.evaluate(&caller, &artifact, mode, via_evolve_token);
+        guards.record(&caller, &artifact, mode, "open", &decision)?;
+        let decision = decision.into_result()?;
 
-        match mode {
-            FsMode::ReadOnly => aura_guard
-                .check_read(&caller_subject, &artifact)
-                .map_err(FsError::GuardError)?,
-            FsMode::WriteOnly | FsMode::ReadWrite => {
-                aura_guard
-                    .check_write(&caller_subject, &artifact)
-                    .map_err(FsError::GuardError)?;
-                kernel_lock
-                    .check_mutation(&artifact, via_evolve_token)
-                    .map_err(FsError::GuardError)?;
+        let stimulation = match mode {
+            FsMode::WriteOnly | FsMode::ReadWrite if requires_dual_control(&artifact) => {
+                guards.stimulation.schema(&artifact.path).cloned()
             }
-        }
+            _ => None,
+        };
+        let mut handle = Self::open_file(artifact, mode, caller, via_evolve_token, decision)?;
+        handle.stimulation = stimulation;
+        Ok(handle)
+    }
+
+    /// Open under a decision the caller already reached; only for paths that
+    /// run their own checks (break-glass, guardian proposals, dual control).
+    /// The decision is recorded on `guards.audit` as `action` first, the same
+    /// way `open` records its own.
+    pub(crate) fn open_admitted(
+        artifact: SovereignArtifact,
+        mode: FsMode,
+        caller: CallerContext,
+        via_evolve_token: bool,
+        decision: GuardDecision,
+        guards: &GuardSet,
+        action: &str,
+    ) -> Result<Self, FsError> {
+        debug_assert!(decision.is_allowed(), "open_admitted needs an allowing decision");
+        guards.record(&caller, &artifact, mode, action, &decision)?;
+        Self::open_file(artifact, mode, caller, via_evolve_token, decision)
+    }
 
+    fn open_file(
+        artifact: SovereignArtifact,
+        mode: FsMode,
+        caller: CallerContext,
+        via_evolve_token: bool,
+        decision: GuardDecision,
+    ) -> Result<Self, FsError> {
         let mut opts = OpenOptions::new();
         match mode {
             FsMode::ReadOnly => {
@@ -64,26 +96,96 @@ impl FsHandle {
             artifact,
             file,
             mode,
-            aura_guard,
-            kernel_lock,
-            caller_subject,
+            caller,
             via_evolve_token,
+            decision,
+            lease: None,
+            stimulation: None,
         })
     }
 
+    /// Tie this handle to a cognitive-liberty lease; I/O fails once it lapses.
+    pub fn bind_lease(&mut self, lease: HandleLease) {
+        self.lease = Some(lease);
+    }
+
+    fn check_lease(&self) -> Result<(), FsError> {
+        match &self.lease {
+            Some(lease) if !lease.is_valid() => {
+                let mut d = DecisionBuilder::new("FsHandle", "io");
+                d.fail(
+                    CheckKind::Rule,
+                    "cognitive_liberty",
+                    format!("handle invalidated by the subject's cognitive liberty switch (lease epoch {})", lease.epoch()),
+                );
+                Err(FsError::Denied(Box::new(d.finish())))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Full content. Refused when the guards allowed only a redacted view:
+    /// nothing here can produce one, so the bytes are withheld.
     pub fn read_all(&mut self) -> Result<Vec<u8>, FsError> {
+        self.check_lease()?;
         if !matches!(self.mode, FsMode::ReadOnly | FsMode::ReadWrite) {
             return Err(FsError::ModeError("handle not opened for read".into()));
         }
+        if self.decision.outcome == Outcome::Redact {
+            let mut d = DecisionBuilder::new("FsHandle", "read");
+            d.fail(
+                CheckKind::Rule,
+                "redaction",
+                format!("full content withheld, only a redacted view is allowed ({})", self.decision.summary()),
+            );
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
         let mut buf = Vec::new();
         self.file.read_to_end(&mut buf).map_err(FsError::Io)?;
         Ok(buf)
     }
 
+    /// Telemetry-only changes to a dual-control config, checked against what is on disk.
+    fn check_stimulation(&self, data: &[u8]) -> Result<(), FsError> {
+        let Some(schema) = &self.stimulation else {
+            return Ok(());
+        };
+        let old = match std::fs::read(&self.artifact.path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(FsError::Io(e)),
+        };
+        StimulationGuard::check_payload(schema, &old, data).into_result()?;
+        Ok(())
+    }
+
     pub fn write_all(&mut self, data: &[u8]) -> Result<(), FsError> {
+        self.check_lease()?;
         if !matches!(self.mode, FsMode::WriteOnly | FsMode::ReadWrite) {
             return Err(FsError::ModeError("handle not opened for write".into()));
         }
+        if self.stimulation.is_some() {
+            let mut d = DecisionBuilder::new("StimulationGuard", "append");
+            d.fail(
+                CheckKind::Rule,
+                "mental_integrity",
+                "appends to a dual-control config cannot be checked; rewrite it with replace_all",
+            );
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+        self.file.write_all(data).map_err(FsError::Io)?;
+        Ok(())
+    }
+
+    /// Truncate and rewrite the whole file (config artifacts are rewritten, not appended).
+    pub fn replace_all(&mut self, data: &[u8]) -> Result<(), FsError> {
+        self.check_lease()?;
+        if !matches!(self.mode, FsMode::WriteOnly | FsMode::ReadWrite) {
+            return Err(FsError::ModeError("handle not opened for write".into()));
+        }
+        self.check_stimulation(data)?;
+        self.file.set_len(0).map_err(FsError::Io)?;
+        self.file.seek(SeekFrom::Start(0)).map_err(FsError::Io)?;
         self.file.write_all(data).map_err(FsError::Io)?;
         Ok(())
     }
@@ -91,4 +193,17 @@ impl FsHandle {
     pub fn artifact(&self) -> &SovereignArtifact {
         &self.artifact
     }
+
+    pub fn caller(&self) -> &CallerContext {
+        &self.caller
+    }
+
+    pub fn via_evolve_token(&self) -> bool {
+        self.via_evolve_token
+    }
+
+    /// Explanation for the guard decision that admitted this handle.
+    pub fn decision(&self) -> &GuardDecision {
+        &self.decision
+    }
 }
@@ -0,0 +1,408 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+
+use crate::artifact::{ArtifactKind, SovereignArtifact};
+use crate::caller::{CallerContext, Purpose};
+use crate::error::FsError;
+use crate::fs_handle::FsHandle;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GrantOperation {
+    Read,
+    Write,
+}
+
+/// Which of the issuer's artifacts a grant covers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactSelector {
+    pub kinds: Vec<ArtifactKind>, // empty = any kind
+    pub path_prefix: Option<String>, // whole path components: `/a/b` covers `/a/b/c`, not `/a/bc` or `/a/b/../c`
+}
+
+impl ArtifactSelector {
+    pub fn matches(&self, art: &SovereignArtifact) -> bool {
+        let kind_ok = self.kinds.is_empty() || self.kinds.contains(&art.kind);
+        let path_ok = self
+            .path_prefix
+            .as_ref()
+            .is_none_or(|p| {
+                // Components are compared lexically, so `.` and `..` could walk out of the prefix.
+                let path = Path::new(&art.path);
+                let plain = path.components().all(|c| !matches!(c, Component::ParentDir | Component::CurDir));
+                plain && path.starts_with(p)
+            });
+        kind_ok && path_ok
+    }
+}
+
+/// Cross-subject delegation issued by the owning subject.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DelegationGrant {
+    pub grant_id: String,
+    pub issuer_subject: String,
+    pub grantee: String,
+    pub selector: ArtifactSelector,
+    pub operations: Vec<GrantOperation>,
+    pub purpose: Purpose,
+    pub issued_at: u64,
+    pub expires_at: u64,
+    pub revocable: bool,
+}
+
+impl DelegationGrant {
+    fn signing_bytes(&self) -> Vec<u8> {
+        // Field order is fixed by the struct definition, so this is stable.
+        serde_json::to_vec(self).expect("grant serialization is infallible")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedGrant {
+    pub grant: DelegationGrant,
+    pub signature: String, // hex-encoded Ed25519 over the JSON grant body
+}
+
+impl SignedGrant {
+    pub fn sign(grant: DelegationGrant, key: &SigningKey) -> Self {
+        let signature = key.sign(&grant.signing_bytes());
+        Self {
+            grant,
+            signature: hex::encode(signature.to_bytes()),
+        }
+    }
+
+    pub fn verify(&self, key: &VerifyingKey) -> Result<(), FsError> {
+        verify_hex(key, &self.grant.signing_bytes(), &self.signature)
+            .map_err(|e| FsError::PolicyError(format!("grant {}: {}", self.grant.grant_id, e)))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedRevocation {
+    pub grant_id: String,
+    pub revoked_at: u64,
+    pub signature: String,
+}
+
+impl SignedRevocation {
+    fn signing_bytes(grant_id: &str, revoked_at: u64) -> Vec<u8> {
+        format!("revoke:{}:{}", grant_id, revoked_at).into_bytes()
+    }
+
+    pub fn verify(&self, key: &VerifyingKey) -> Result<(), FsError> {
+        let msg = Self::signing_bytes(&self.grant_id, self.revoked_at);
+        verify_hex(key, &msg, &self.signature)
+            .map_err(|e| FsError::PolicyError(format!("revocation {}: {}", self.grant_id, e)))
+    }
+}
+
+/// On-disk form of a subject's grants: `<subject>.grants.neurorights.json`,
+/// a SovereignConfig artifact, so rewriting it goes through SovereignKernelLock.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrantBook {
+    pub subject_id: String,
+    pub grants: Vec<SignedGrant>,
+    pub revocations: Vec<SignedRevocation>,
+}
+
+impl GrantBook {
+    pub fn new(subject_id: impl Into<String>) -> Self {
+        Self {
+            subject_id: subject_id.into(),
+            grants: Vec::new(),
+            revocations: Vec::new(),
+        }
+    }
+
+    pub fn file_name(subject_id: &str) -> String {
+        format!("{}.grants.neurorights.json", subject_id)
+    }
+
+    /// Artifact descriptor for the book stored under `dir`.
+    pub fn artifact(&self, dir: &str) -> SovereignArtifact {
+        SovereignArtifact::sovereign_config(
+            format!("{}/{}", dir.trim_end_matches('/'), Self::file_name(&self.subject_id)),
+            &self.subject_id,
+        )
+    }
+
+    pub fn issue(&mut self, grant: DelegationGrant, key: &SigningKey) -> Result<(), FsError> {
+        if grant.issuer_subject != self.subject_id {
+            return Err(FsError::PolicyError(
+                "grant issuer must be the subject owning the grant book".into(),
+            ));
+        }
+        if grant.expires_at <= grant.issued_at {
+            return Err(FsError::PolicyError("grant expires before it is issued".into()));
+        }
+        self.grants.push(SignedGrant::sign(grant, key));
+        Ok(())
+    }
+
+    pub fn revoke(&mut self, grant_id: &str, revoked_at: u64, key: &SigningKey) -> Result<(), FsError> {
+        let grant = self
+            .grants
+            .iter()
+            .find(|g| g.grant.grant_id == grant_id)
+            .ok_or_else(|| FsError::PolicyError(format!("unknown grant {}", grant_id)))?;
+        if !grant.grant.revocable {
+            return Err(FsError::PolicyError(format!("grant {} is not revocable", grant_id)));
+        }
+        let signature = key.sign(&SignedRevocation::signing_bytes(grant_id, revoked_at));
+        self.revocations.push(SignedRevocation {
+            grant_id: grant_id.to_string(),
+            revoked_at,
+            signature: hex::encode(signature.to_bytes()),
+        });
+        Ok(())
+    }
+
+    pub fn load(handle: &mut FsHandle) -> Result<Self, FsError> {
+        let bytes = handle.read_all()?;
+        serde_json::from_slice(&bytes)
+            .map_err(|e| FsError::PolicyError(format!("grant book parse error: {}", e)))
+    }
+
+    /// Rewrite the book; the handle must have passed the kernel lock for write.
+    pub fn store(&self, handle: &mut FsHandle) -> Result<(), FsError> {
+        let bytes = serde_json::to_vec_pretty(self)
+            .map_err(|e| FsError::PolicyError(format!("grant book serialization error: {}", e)))?;
+        handle.replace_all(&bytes)
+    }
+}
+
+/// Public keys subjects sign their grants with.
+#[derive(Debug, Clone, Default)]
+pub struct SubjectKeyring {
+    keys: HashMap<String, VerifyingKey>,
+}
+
+impl SubjectKeyring {
+    pub fn insert(&mut self, subject_id: impl Into<String>, key: VerifyingKey) {
+        self.keys.insert(subject_id.into(), key);
+    }
+
+    pub fn get(&self, subject_id: &str) -> Option<&VerifyingKey> {
+        self.keys.get(subject_id)
+    }
+}
+
+/// Verified grants the guards evaluate at access time, keyed by (issuer, grant_id).
+#[derive(Debug, Clone, Default)]
+pub struct GrantRegistry {
+    keyring: SubjectKeyring,
+    grants: BTreeMap<(String, String), DelegationGrant>,
+    revoked: HashMap<(String, String), u64>, // revoked_at, earliest signed revocation
+}
+
+impl GrantRegistry {
+    pub fn new(keyring: SubjectKeyring) -> Self {
+        Self {
+            keyring,
+            grants: BTreeMap::new(),
+            revoked: HashMap::new(),
+        }
+    }
+
+    pub fn keyring(&self) -> &SubjectKeyring {
+        &self.keyring
+    }
+
+    /// Admit a grant book; any bad signature rejects the whole book. Loading
+    /// the same book again replaces its grants rather than duplicating them.
+    pub fn load_book(&mut self, book: &GrantBook) -> Result<(), FsError> {
+        let key = self.keyring.get(&book.subject_id).ok_or_else(|| {
+            FsError::PolicyError(format!("no signing key registered for {}", book.subject_id))
+        })?;
+        for signed in &book.grants {
+            if signed.grant.issuer_subject != book.subject_id {
+                return Err(FsError::PolicyError(format!(
+                    "grant {} was not issued by {}",
+                    signed.grant.grant_id, book.subject_id
+                )));
+            }
+            signed.verify(key)?;
+        }
+        for rev in &book.revocations {
+            rev.verify(key)?;
+        }
+
+        for signed in &book.grants {
+            let id = (book.subject_id.clone(), signed.grant.grant_id.clone());
+            self.grants.insert(id, signed.grant.clone());
+        }
+        for rev in &book.revocations {
+            let revocable = book
+                .grants
+                .iter()
+                .any(|g| g.grant.grant_id == rev.grant_id && g.grant.revocable);
+            if revocable {
+                let at = self
+                    .revoked
+                    .entry((book.subject_id.clone(), rev.grant_id.clone()))
+                    .or_insert(rev.revoked_at);
+                *at = (*at).min(rev.revoked_at);
+            }
+        }
+        Ok(())
+    }
+
+    /// First unexpired grant covering this caller, artifact and operation that
+    /// was not revoked at or before `now`.
+    pub fn find_active(
+        &self,
+        caller: &CallerContext,
+        art: &SovereignArtifact,
+        op: GrantOperation,
+        now: u64,
+    ) -> Option<&DelegationGrant> {
+        self.grants.iter().find_map(|(id, g)| {
+            let revoked = self.revoked.get(id).is_some_and(|&at| at <= now);
+            let active = g.issuer_subject == art.subject_id
+                && g.grantee == caller.principal()
+                && g.purpose == caller.purpose()
+                && g.operations.contains(&op)
+                && g.issued_at <= now
+                && now < g.expires_at
+                && g.selector.matches(art)
+                && !revoked;
+            active.then_some(g)
+        })
+    }
+}
+
+pub(crate) fn verify_hex(key: &VerifyingKey, msg: &[u8], sig_hex: &str) -> Result<(), String> {
+    let bytes = hex::decode(sig_hex).map_err(|_| "signature is not valid hex".to_string())?;
+    let sig = Signature::from_slice(&bytes).map_err(|_| "malformed signature".to_string())?;
+    key.verify(msg, &sig)
+        .map_err(|_| "signature verification failed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caller::Role;
+    use crate::routes::Route;
+
+    fn key() -> SigningKey {
+        SigningKey::from_bytes(&[5; 32])
+    }
+
+    fn registry() -> GrantRegistry {
+        let mut keyring = SubjectKeyring::default();
+        keyring.insert("subject-1", key().verifying_key());
+        GrantRegistry::new(keyring)
+    }
+
+    fn grant(id: &str, path_prefix: Option<&str>) -> DelegationGrant {
+        DelegationGrant {
+            grant_id: id.into(),
+            issuer_subject: "subject-1".into(),
+            grantee: "lab".into(),
+            selector: ArtifactSelector { kinds: Vec::new(), path_prefix: path_prefix.map(str::to_string) },
+            operations: vec![GrantOperation::Read],
+            purpose: Purpose::Research,
+            issued_at: 10,
+            expires_at: 1_000,
+            revocable: true,
+        }
+    }
+
+    fn art(path: &str) -> SovereignArtifact {
+        SovereignArtifact::sovereign_config(path, "subject-1")
+    }
+
+    fn lab() -> CallerContext {
+        CallerContext::new("lab", Role::Researcher, Purpose::Research, Route::Chat).unwrap()
+    }
+
+    #[test]
+    fn grants_apply_within_their_window_only() {
+        let mut book = GrantBook::new("subject-1");
+        book.issue(grant("g1", None), &key()).unwrap();
+        let mut reg = registry();
+        reg.load_book(&book).unwrap();
+
+        let a = art("/data/x.json");
+        assert!(reg.find_active(&lab(), &a, GrantOperation::Read, 5).is_none());
+        assert_eq!(reg.find_active(&lab(), &a, GrantOperation::Read, 10).unwrap().grant_id, "g1");
+        assert!(reg.find_active(&lab(), &a, GrantOperation::Read, 1_000).is_none());
+        assert!(reg.find_active(&lab(), &a, GrantOperation::Write, 10).is_none());
+        let clinic = CallerContext::new("lab", Role::Clinician, Purpose::ClinicalCare, Route::Chat).unwrap();
+        assert!(reg.find_active(&clinic, &a, GrantOperation::Read, 10).is_none());
+    }
+
+    #[test]
+    fn revocations_take_effect_at_revoked_at() {
+        let mut book = GrantBook::new("subject-1");
+        book.issue(grant("g1", None), &key()).unwrap();
+        book.revoke("g1", 50, &key()).unwrap();
+        let mut reg = registry();
+        reg.load_book(&book).unwrap();
+
+        let a = art("/data/x.json");
+        assert!(reg.find_active(&lab(), &a, GrantOperation::Read, 49).is_some());
+        assert!(reg.find_active(&lab(), &a, GrantOperation::Read, 50).is_none());
+        assert!(reg.find_active(&lab(), &a, GrantOperation::Read, 999).is_none());
+    }
+
+    #[test]
+    fn reloading_a_book_does_not_duplicate_grants() {
+        let mut book = GrantBook::new("subject-1");
+        book.issue(grant("g1", None), &key()).unwrap();
+        let mut reg = registry();
+        reg.load_book(&book).unwrap();
+        reg.load_book(&book).unwrap();
+        assert_eq!(reg.grants.len(), 1);
+
+        // A reissued grant under the same id replaces the old terms.
+        let mut narrower = GrantBook::new("subject-1");
+        narrower.issue(grant("g1", Some("/other")), &key()).unwrap();
+        reg.load_book(&narrower).unwrap();
+        assert_eq!(reg.grants.len(), 1);
+        assert!(reg.find_active(&lab(), &art("/data/x.json"), GrantOperation::Read, 10).is_none());
+    }
+
+    #[test]
+    fn path_prefixes_match_whole_components() {
+        let mut book = GrantBook::new("subject-1");
+        book.issue(grant("g1", Some("/a/b")), &key()).unwrap();
+        let mut reg = registry();
+        reg.load_book(&book).unwrap();
+
+        for path in ["/a/b", "/a/b/c.json", "/a/b/c/d.json"] {
+            assert!(reg.find_active(&lab(), &art(path), GrantOperation::Read, 10).is_some(), "{}", path);
+        }
+        for path in ["/a/bc", "/a/bc/d.json", "/a"] {
+            assert!(reg.find_active(&lab(), &art(path), GrantOperation::Read, 10).is_none(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn dot_components_fall_outside_prefix_grants() {
+        let mut book = GrantBook::new("subject-1");
+        book.issue(grant("g1", Some("/a/b")), &key()).unwrap();
+        let mut reg = registry();
+        reg.load_book(&book).unwrap();
+
+        for path in ["/a/b/../../etc/x", "/a/b/..", "./a/b/c.json"] {
+            assert!(reg.find_active(&lab(), &art(path), GrantOperation::Read, 10).is_none(), "{}", path);
+        }
+    }
+
+    #[test]
+    fn forged_books_are_rejected() {
+        let mut book = GrantBook::new("subject-1");
+        book.issue(grant("g1", None), &SigningKey::from_bytes(&[6; 32])).unwrap();
+        let mut reg = registry();
+        assert!(matches!(reg.load_book(&book), Err(FsError::PolicyError(_))));
+        assert!(reg.grants.is_empty());
+
+        let mut unknown = GrantBook::new("subject-2");
+        unknown.grants = book.grants.clone();
+        assert!(reg.load_book(&unknown).is_err());
+    }
+}
@@ -1,48 +1,401 @@
+use std::sync::{Arc, Mutex};
+
 use crate::artifact::SovereignArtifact;
+use crate::audit::{AuditChain, AuditSeverity};
+use crate::caller::CallerContext;
+use crate::clock::unix_now;
+use crate::dual_control::StimulationGuard;
+use crate::error::FsError;
+use crate::explain::{CheckKind, DecisionBuilder, GuardDecision};
+use crate::fs_handle::FsMode;
+use crate::grants::{GrantOperation, GrantRegistry};
+use crate::kernel_lock::GuardianQuorum;
+use crate::plugins::WasmGuardPlugin;
+use crate::routes::{allowed_operations, Route};
+use crate::shard_signatures::ShardSignatureGuard;
+use crate::tags::TagGuard;
+
+#[derive(Debug, Clone, Default)]
+pub struct AuraBoundaryGuard {
+    grants: GrantRegistry,
+}
+
+/// With a guardian quorum configured, kernel artifacts change only via `ProposalBook::apply`.
+#[derive(Debug, Clone, Default)]
+pub struct SovereignKernelLock {
+    quorum: Option<GuardianQuorum>,
+}
+
+/// Guards consulted by `FsHandle::open`.
+#[derive(Debug, Clone, Default)]
+pub struct GuardSet {
+    pub aura: AuraBoundaryGuard,
+    pub kernel_lock: SovereignKernelLock,
+    pub tags: TagGuard,
+    pub shard_signatures: ShardSignatureGuard,
+    pub stimulation: StimulationGuard,
+    /// Site-specific WebAssembly guards, run after the built-in ones.
+    pub plugins: Vec<WasmGuardPlugin>,
+    /// Where `FsHandle::open` records every decision, allowed or denied,
+    /// before the handle is returned.
+    pub audit: Option<Arc<Mutex<AuditChain>>>,
+}
+
+/// How a write reached `GuardSet`: directly, or through a path that already
+/// collected the extra consent a single caller cannot give.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Admission {
+    Ordinary { via_evolve_token: bool },
+    /// Co-signed by the subject and a clinician; stands in for `StimulationGuard`.
+    DualControlled,
+    /// Carried by a guardian-approved proposal; stands in for the kernel lock.
+    GuardianApproved,
+}
+
+impl GuardSet {
+    /// Run every guard that applies to opening `art` in `mode`; one combined tree.
+    pub fn evaluate(
+        &self,
+        caller: &CallerContext,
+        art: &SovereignArtifact,
+        mode: FsMode,
+        via_evolve_token: bool,
+    ) -> GuardDecision {
+        self.evaluate_inner(caller, art, mode, Admission::Ordinary { via_evolve_token })
+    }
 
-#[derive(Debug, Clone)]
-pub struct AuraBoundaryGuard;
+    /// As `evaluate`, for a write the subject and a clinician have co-signed:
+    /// only the single-party `StimulationGuard` is skipped.
+    pub(crate) fn evaluate_dual_controlled(&self, caller: &CallerContext, art: &SovereignArtifact, mode: FsMode) -> GuardDecision {
+        self.evaluate_inner(caller, art, mode, Admission::DualControlled)
+    }
 
-#[derive(Debug, Clone)]
-pub struct SovereignKernelLock;
+    /// As `evaluate`, for a kernel write whose proposal reached guardian quorum:
+    /// only the kernel lock's own quorum check is skipped.
+    pub(crate) fn evaluate_guardian_approved(&self, caller: &CallerContext, art: &SovereignArtifact, mode: FsMode) -> GuardDecision {
+        self.evaluate_inner(caller, art, mode, Admission::GuardianApproved)
+    }
+
+    /// Append `decision` for `action` on `art` to the audit chain, if one is configured.
+    pub(crate) fn record(
+        &self,
+        caller: &CallerContext,
+        art: &SovereignArtifact,
+        mode: FsMode,
+        action: &str,
+        decision: &GuardDecision,
+    ) -> Result<(), FsError> {
+        let Some(audit) = &self.audit else {
+            return Ok(());
+        };
+        let mut audit = audit
+            .lock()
+            .map_err(|_| FsError::PolicyError("audit chain lock poisoned".into()))?;
+        audit.append(
+            unix_now(),
+            if decision.is_allowed() { AuditSeverity::Info } else { AuditSeverity::Warning },
+            caller.principal(),
+            &format!("{:?}", caller.purpose()),
+            &art.subject_id,
+            &art.path,
+            action,
+            &format!("{:?} {:?}: {}", mode, decision.outcome, decision.summary()),
+        )?;
+        Ok(())
+    }
+
+    fn evaluate_inner(&self, caller: &CallerContext, art: &SovereignArtifact, mode: FsMode, admission: Admission) -> GuardDecision {
+        let mut d = DecisionBuilder::new("GuardSet", &format!("{:?}", mode));
+        let via_evolve_token = match admission {
+            Admission::Ordinary { via_evolve_token } => via_evolve_token,
+            Admission::DualControlled => false,
+            Admission::GuardianApproved => true,
+        };
+        match mode {
+            FsMode::ReadOnly => d.include(self.aura.check_read(caller, art)),
+            FsMode::WriteOnly | FsMode::ReadWrite => {
+                d.include(self.aura.check_write(caller, art));
+                if !d.is_denied() {
+                    match admission {
+                        Admission::GuardianApproved => {
+                            d.pass(CheckKind::Threshold, "guardian-quorum", "carried by a guardian-approved proposal")
+                        }
+                        _ => d.include(self.kernel_lock.check_mutation(art, via_evolve_token)),
+                    }
+                }
+            }
+        }
+        if !d.is_denied() {
+            d.include(self.tags.check(art, mode, via_evolve_token));
+        }
+        if !d.is_denied() {
+            d.include(self.shard_signatures.check(art, mode));
+        }
+        if !d.is_denied() {
+            if admission == Admission::DualControlled {
+                d.pass(CheckKind::Token, "dual-control", "co-signed by the subject and a clinician");
+            } else {
+                d.include(self.stimulation.check(art, mode));
+            }
+        }
+        for plugin in &self.plugins {
+            if d.is_denied() {
+                break;
+            }
+            d.include(plugin.check(caller, art, mode));
+        }
+        d.finish()
+    }
+}
 
 impl AuraBoundaryGuard {
+    pub fn new(grants: GrantRegistry) -> Self {
+        Self { grants }
+    }
+
+    pub fn grants(&self) -> &GrantRegistry {
+        &self.grants
+    }
+
+    /// Cross-subject access needs an active grant from the owning subject.
+    fn check_subject(&self, d: &mut DecisionBuilder, caller: &CallerContext, art: &SovereignArtifact, op: GrantOperation) {
+        if caller.principal() == art.subject_id {
+            d.pass(CheckKind::Rule, "same-subject", format!("{} owns {}", caller.principal(), art.path));
+            return;
+        }
+        match self.grants.find_active(caller, art, op, unix_now()) {
+            Some(g) => d.pass(
+                CheckKind::Grant,
+                "delegation-grant",
+                format!("grant {} from {} ({:?}, expires_at={})", g.grant_id, g.issuer_subject, g.purpose, g.expires_at),
+            ),
+            None => d.fail(
+                CheckKind::Grant,
+                "delegation-grant",
+                format!(
+                    "cross-subject {:?} denied: no active grant from {} to {} for {:?}",
+                    op, art.subject_id, caller.principal(), caller.purpose()
+                ),
+            ),
+        }
+    }
+
+    /// The caller's route must be declared by the artifact and allow `op`.
+    fn check_route(&self, d: &mut DecisionBuilder, caller: &CallerContext, art: &SovereignArtifact, op: GrantOperation) {
+        if art.routes.is_empty() {
+            d.fail(CheckKind::Rule, "route", format!("{} declares no routes", art.path));
+            return;
+        }
+        let ops = allowed_operations(art, caller.route());
+        if ops.contains(&op) {
+            d.pass(CheckKind::Rule, "route", format!("{:?} allows {:?} on {:?}", art.routes, op, caller.route()));
+        } else if ops.is_empty() {
+            d.fail(
+                CheckKind::Rule,
+                "route",
+                format!("not exposed on {:?} (declared: {:?})", caller.route(), art.routes),
+            );
+        } else {
+            d.fail(
+                CheckKind::Rule,
+                "route",
+                format!("{} allows only {:?} on {:?}", art.path, ops, caller.route()),
+            );
+        }
+    }
+
     /// Enforce cross-subject + neurorights boundaries (no foreign NEUROSTREAM, no dream export).
-    pub fn check_read(&self, caller_subject: &str, art: &SovereignArtifact) -> Result<(), String> {
-        if caller_subject != art.subject_id {
-            return Err("AuraBoundaryGuard: cross-subject access denied".into());
+    pub fn check_read(&self, caller: &CallerContext, art: &SovereignArtifact) -> GuardDecision {
+        let mut d = DecisionBuilder::new("AuraBoundaryGuard", "read");
+        self.check_route(&mut d, caller, art, GrantOperation::Read);
+        if d.is_denied() {
+            return d.finish();
         }
-        if art.neurorights.mental_privacy && matches!(art.kind, crate::artifact::ArtifactKind::NeuralShard) {
-            return Err("AuraBoundaryGuard: mental privacy forbids raw neural shard export".into());
+        self.check_subject(&mut d, caller, art, GrantOperation::Read);
+        if d.is_denied() {
+            return d.finish();
         }
+
+        let raw_shard = matches!(art.kind, crate::artifact::ArtifactKind::NeuralShard);
+        if art.neurorights.mental_privacy && raw_shard {
+            d.fail(CheckKind::Flag, "mental_privacy", "mental privacy forbids raw neural shard export");
+            return d.finish();
+        }
+        d.pass(
+            CheckKind::Flag,
+            "mental_privacy",
+            format!("mental_privacy={}, raw neural shard={}", art.neurorights.mental_privacy, raw_shard),
+        );
+
+        if art.neurorights.forbid_decision_use && caller.purpose().is_decision_making() {
+            d.fail(
+                CheckKind::Flag,
+                "forbid_decision_use",
+                "forbid_decision_use: read for automated decision-making denied",
+            );
+            return d.finish();
+        }
+        d.pass(
+            CheckKind::Flag,
+            "forbid_decision_use",
+            format!("forbid_decision_use={}, purpose {:?}", art.neurorights.forbid_decision_use, caller.purpose()),
+        );
+
         if art.neurorights.dreamstate_sensitive && art.neurorights.forbid_decision_use {
             // Allow only local introspection routes, not generic AI agents.
-            if !art.routes.contains(&"INTROSPECT".to_string()) {
-                return Err("AuraBoundaryGuard: dreamstate-sensitive shard not exposed on this route".into());
+            if caller.route() != Route::Introspect {
+                d.fail(
+                    CheckKind::Flag,
+                    "dreamstate_sensitive",
+                    format!("dreamstate-sensitive shard not exposed on {:?}", caller.route()),
+                );
+                return d.finish();
             }
+            d.pass(CheckKind::Flag, "dreamstate_sensitive", "requested over INTROSPECT");
+        } else {
+            d.skip(CheckKind::Flag, "dreamstate_sensitive", "not dreamstate-sensitive with forbid_decision_use");
         }
-        Ok(())
+        d.finish()
     }
 
-    pub fn check_write(&self, caller_subject: &str, art: &SovereignArtifact) -> Result<(), String> {
-        if caller_subject != art.subject_id {
-            return Err("AuraBoundaryGuard: cross-subject write denied".into());
-        }
-        if art.neurorights.soul_non_tradeable && art.governance_tags.contains(&"EXPORT".to_string()) {
-            return Err("AuraBoundaryGuard: soul-non-tradeable artifact cannot be exported or tokenized".into());
+    pub fn check_write(&self, caller: &CallerContext, art: &SovereignArtifact) -> GuardDecision {
+        let mut d = DecisionBuilder::new("AuraBoundaryGuard", "write");
+        self.check_route(&mut d, caller, art, GrantOperation::Write);
+        if d.is_denied() {
+            return d.finish();
         }
-        Ok(())
+        // EXPORT / soul-non-tradeable checks are bound to the tag, see TagGuard.
+        self.check_subject(&mut d, caller, art, GrantOperation::Write);
+        d.finish()
     }
 }
 
 impl SovereignKernelLock {
-    /// Only EVOLVE path may change sovereign-kernel artifacts.
-    pub fn check_mutation(&self, art: &SovereignArtifact, via_evolve_token: bool) -> Result<(), String> {
+    pub fn with_quorum(quorum: GuardianQuorum) -> Self {
+        Self { quorum: Some(quorum) }
+    }
+
+    pub fn quorum(&self) -> Option<&GuardianQuorum> {
+        self.quorum.as_ref()
+    }
+
+    pub fn is_kernel(art: &SovereignArtifact) -> bool {
         use crate::artifact::ArtifactKind::*;
-        let is_kernel = matches!(art.kind, SovereignConfig | EvolveStream | DonutLedger | BChainProof);
-        if is_kernel && !via_evolve_token {
-            return Err("SovereignKernelLock: mutation requires EVOLVE token path".into());
+        matches!(art.kind, SovereignConfig | EvolveStream | DonutLedger | BChainProof)
+    }
+
+    /// Only EVOLVE path may change sovereign-kernel artifacts.
+    pub fn check_mutation(&self, art: &SovereignArtifact, via_evolve_token: bool) -> GuardDecision {
+        let mut d = DecisionBuilder::new("SovereignKernelLock", "mutation");
+        if !Self::is_kernel(art) {
+            d.skip(CheckKind::Rule, "kernel-artifact", format!("{:?} is not a kernel artifact", art.kind));
+            return d.finish();
+        }
+        d.pass(CheckKind::Rule, "kernel-artifact", format!("{:?} is a kernel artifact", art.kind));
+
+        if let Some(q) = &self.quorum {
+            d.fail(
+                CheckKind::Threshold,
+                "guardian-quorum",
+                format!(
+                    "kernel mutation requires a guardian-approved proposal (threshold {} of weight {})",
+                    q.threshold(),
+                    q.total_weight()
+                ),
+            );
+            return d.finish();
+        }
+        if !via_evolve_token {
+            d.fail(CheckKind::Token, "evolve-token", "mutation requires EVOLVE token path");
+            return d.finish();
+        }
+        d.pass(CheckKind::Token, "evolve-token", "EVOLVE token present");
+        d.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifact::ArtifactKind;
+    use crate::caller::{Purpose, Role};
+
+    /// A subject's notes, readable over CHAT, not dreamstate-sensitive.
+    fn notes(forbid_decision_use: bool) -> SovereignArtifact {
+        let mut art = SovereignArtifact::sovereign_config("subject-1.notes", "subject-1");
+        art.kind = ArtifactKind::GenericData;
+        art.routes = vec![Route::Chat];
+        art.route_allowances.clear();
+        art.neurorights.forbid_decision_use = forbid_decision_use;
+        art
+    }
+
+    fn agent(purpose: Purpose) -> CallerContext {
+        CallerContext::new("subject-1", Role::Agent, purpose, Route::Chat).unwrap()
+    }
+
+    #[test]
+    fn automated_decisions_are_refused_on_forbid_decision_use() {
+        let aura = AuraBoundaryGuard::default();
+        let decision = aura.check_read(&agent(Purpose::AutomatedDecision), &notes(true));
+        assert!(!decision.is_allowed());
+        assert!(decision.summary().contains("forbid_decision_use"), "{}", decision.summary());
+
+        assert!(aura.check_read(&agent(Purpose::Introspection), &notes(true)).is_allowed());
+        assert!(aura.check_read(&agent(Purpose::AutomatedDecision), &notes(false)).is_allowed());
+    }
+
+    #[test]
+    fn open_records_every_decision() {
+        use crate::error::FsError;
+        use crate::fs_handle::FsHandle;
+
+        let dir = std::env::temp_dir().join(format!("neuroxfs-guard-audit-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut art = notes(true);
+        art.path = dir.join("notes.json").display().to_string();
+        std::fs::write(&art.path, "{}").unwrap();
+        let guards = GuardSet {
+            audit: Some(Arc::new(Mutex::new(AuditChain::new()))),
+            ..GuardSet::default()
+        };
+
+        FsHandle::open(art.clone(), FsMode::ReadOnly, agent(Purpose::Introspection), false, &guards).unwrap();
+        let err = FsHandle::open(art, FsMode::ReadOnly, agent(Purpose::AutomatedDecision), false, &guards).unwrap_err();
+        assert!(matches!(err, FsError::Denied(_)));
+
+        let audit = guards.audit.as_ref().unwrap().lock().unwrap();
+        let records: Vec<_> = audit.records().collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].body.purpose, "Introspection");
+        assert!(records[0].body.detail.starts_with("ReadOnly Allow"), "{}", records[0].body.detail);
+        assert!(records[1].body.detail.contains("forbid_decision_use"), "{}", records[1].body.detail);
+        audit.verify().unwrap();
+    }
+
+    #[test]
+    fn redacting_plugins_withhold_full_content() {
+        use crate::error::FsError;
+        use crate::fs_handle::FsHandle;
+        use crate::plugins::fixtures;
+
+        let dir = std::env::temp_dir().join(format!("neuroxfs-guard-redact-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut art = notes(false);
+        art.path = dir.join("notes.json").display().to_string();
+        std::fs::write(&art.path, "{\"mood\":\"calm\"}").unwrap();
+        let guards = GuardSet {
+            plugins: vec![fixtures::fixed_verdict(
+                "site-mask",
+                r#"{"outcome":"Redact","label":"site-mask","detail":"mood is masked on this site"}"#,
+            )],
+            ..GuardSet::default()
+        };
+
+        let mut handle = FsHandle::open(art, FsMode::ReadOnly, agent(Purpose::Introspection), false, &guards).unwrap();
+        match handle.read_all().unwrap_err() {
+            FsError::Denied(d) => assert!(d.summary().contains("mood is masked"), "{}", d.summary()),
+            other => panic!("expected a guard decision, got {}", other),
         }
-        Ok(())
     }
 }
@@ -0,0 +1,550 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::artifact::SovereignArtifact;
+use crate::caller::CallerContext;
+use crate::clock::unix_now;
+use crate::error::FsError;
+use crate::explain::{CheckKind, DecisionBuilder};
+use crate::fs_handle::{FsHandle, FsMode};
+use crate::guards::{GuardSet, SovereignKernelLock};
+use crate::liberty::LibertyBoard;
+
+const KERNEL_APPLY: &str = "kernel-apply";
+
+/// Kernel proposals lapse after this long unless applied, approved or not.
+pub const DEFAULT_PROPOSAL_TTL_SECS: u64 = 72 * 3600;
+
+#[derive(Debug, Clone)]
+pub struct Guardian {
+    pub guardian_id: String,
+    pub weight: f32,
+    pub key: VerifyingKey,
+}
+
+/// Guardian weights and approval threshold, read from `.stake.aln` files.
+///
+/// Parsed with `aln_core`; rows used (other rows are ignored):
+/// `ROW,guardian,<guardian_id>,scalar,weight,<float>,...`
+/// `ROW,guardian,<guardian_id>,scalar,pubkey,<ed25519 hex>,...`
+/// `ROW,quorum,policy,scalar,threshold,<fraction of total weight>,...`
+#[derive(Debug, Clone, Default)]
+pub struct GuardianQuorum {
+    guardians: BTreeMap<String, Guardian>,
+    threshold: f32,
+}
+
+impl GuardianQuorum {
+    pub fn from_stake_files(sources: &[(&str, &str)]) -> Result<Self, FsError> {
+        let mut weights: BTreeMap<String, f32> = BTreeMap::new();
+        let mut keys: BTreeMap<String, VerifyingKey> = BTreeMap::new();
+        let mut threshold = None;
+
+        for (name, text) in sources {
+            if !name.ends_with(".stake.aln") {
+                return Err(FsError::PolicyError(format!("{} is not a .stake.aln file", name)));
+            }
+            let shard = aln_core::parse_named(name, text).map_err(|e| FsError::PolicyError(e.to_string()))?;
+            for (_, row) in shard.rows() {
+                let value = row.value.trim();
+                let bad = |what: &str| {
+                    FsError::PolicyError(format!("{}:{}: invalid {}", name, row.spans.value.line, what))
+                };
+                match (row.entity_type.as_str(), row.key.as_str()) {
+                    ("guardian", "weight") => {
+                        let w: f32 = value.parse().map_err(|_| bad("guardian weight"))?;
+                        if w.is_nan() || w <= 0.0 {
+                            return Err(bad("guardian weight"));
+                        }
+                        if weights.insert(row.field.clone(), w).is_some() {
+                            return Err(bad("duplicate guardian weight"));
+                        }
+                    }
+                    ("guardian", "pubkey") => {
+                        let bytes = hex::decode(value).map_err(|_| bad("guardian pubkey"))?;
+                        let arr: [u8; 32] = bytes.try_into().map_err(|_| bad("guardian pubkey"))?;
+                        let key = VerifyingKey::from_bytes(&arr).map_err(|_| bad("guardian pubkey"))?;
+                        if keys.insert(row.field.clone(), key).is_some() {
+                            return Err(bad("duplicate guardian pubkey"));
+                        }
+                    }
+                    ("quorum", "threshold") => {
+                        let t: f32 = value.parse().map_err(|_| bad("quorum threshold"))?;
+                        if t.is_nan() || t <= 0.0 || t > 1.0 {
+                            return Err(bad("quorum threshold"));
+                        }
+                        threshold = Some(t);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        let mut guardians = BTreeMap::new();
+        for (id, weight) in weights {
+            let key = keys
+                .remove(&id)
+                .ok_or_else(|| FsError::PolicyError(format!("guardian {} has no pubkey", id)))?;
+            guardians.insert(id.clone(), Guardian { guardian_id: id, weight, key });
+        }
+        if guardians.is_empty() {
+            return Err(FsError::PolicyError("stake files declare no guardians".into()));
+        }
+        let threshold = threshold
+            .ok_or_else(|| FsError::PolicyError("stake files declare no quorum threshold".into()))?;
+        Ok(Self { guardians, threshold })
+    }
+
+    pub fn total_weight(&self) -> f32 {
+        self.guardians.values().map(|g| g.weight).sum()
+    }
+
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    pub fn guardian(&self, guardian_id: &str) -> Option<&Guardian> {
+        self.guardians.get(guardian_id)
+    }
+
+    fn is_met(&self, approvals: &[GuardianApproval]) -> bool {
+        let approved: f32 = approvals
+            .iter()
+            .filter_map(|a| self.guardians.get(&a.guardian_id))
+            .map(|g| g.weight)
+            .sum();
+        approved >= self.threshold * self.total_weight()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProposalStatus {
+    Pending,
+    Approved,
+    Expired,
+    Applied,
+    /// The subject has suspended agent access; nothing moves until they resume.
+    Frozen,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GuardianApproval {
+    pub guardian_id: String,
+    pub signature: String, // hex Ed25519 over `KernelProposal::signing_bytes`
+}
+
+/// One proposed rewrite of a kernel artifact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KernelProposal {
+    pub proposal_id: u64, // local to the book; `nonce` is what makes approvals unique
+    pub nonce: String,    // hex, 16 random bytes drawn at proposal time
+    pub subject_id: String,
+    pub artifact_path: String,
+    pub payload_hash: String, // hex SHA-256 of the new content
+    pub proposer: String,
+    pub created_at: u64,
+    pub expires_at: u64,
+    pub approvals: Vec<GuardianApproval>,
+    pub applied: bool,
+}
+
+impl KernelProposal {
+    /// Binds an approval to this proposal alone, so it cannot be replayed
+    /// against a later proposal for the same payload, e.g. after a restart.
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        format!(
+            "kernel-approve:{}:{}:{}:{}:{}:{}",
+            self.nonce, self.proposal_id, self.subject_id, self.artifact_path, self.payload_hash, self.expires_at
+        )
+        .into_bytes()
+    }
+
+    /// Helper for guardians holding their own key.
+    pub fn sign(&self, guardian_id: &str, key: &SigningKey) -> GuardianApproval {
+        GuardianApproval {
+            guardian_id: guardian_id.to_string(),
+            signature: hex::encode(key.sign(&self.signing_bytes()).to_bytes()),
+        }
+    }
+}
+
+pub fn payload_hash(payload: &[u8]) -> String {
+    hex::encode(Sha256::digest(payload))
+}
+
+/// Collects guardian signatures and applies kernel changes once quorum is met.
+#[derive(Debug, Clone)]
+pub struct ProposalBook {
+    quorum: GuardianQuorum,
+    ttl_secs: u64,
+    proposals: Vec<KernelProposal>,
+    next_id: u64, // never reused, even once older proposals are gone
+    liberty: Option<Arc<LibertyBoard>>,
+}
+
+impl ProposalBook {
+    pub fn new(quorum: GuardianQuorum) -> Self {
+        Self {
+            quorum,
+            ttl_secs: DEFAULT_PROPOSAL_TTL_SECS,
+            proposals: Vec::new(),
+            next_id: 1,
+            liberty: None,
+        }
+    }
+
+    /// Freeze a subject's pending proposals while their liberty switch is on.
+    pub fn with_liberty(mut self, liberty: Arc<LibertyBoard>) -> Self {
+        self.liberty = Some(liberty);
+        self
+    }
+
+    pub fn with_ttl(mut self, ttl_secs: u64) -> Self {
+        self.ttl_secs = ttl_secs;
+        self
+    }
+
+    pub fn propose(&mut self, art: &SovereignArtifact, payload: &[u8], proposer: &str) -> Result<u64, FsError> {
+        if !SovereignKernelLock::is_kernel(art) {
+            return Err(FsError::PolicyError(
+                "only kernel artifacts go through guardian proposals".into(),
+            ));
+        }
+        let mut nonce = [0u8; 16];
+        getrandom::getrandom(&mut nonce)
+            .map_err(|e| FsError::GuardError(format!("no randomness for a proposal nonce: {}", e)))?;
+        let now = unix_now();
+        let proposal_id = self.next_id;
+        self.next_id += 1;
+        self.proposals.push(KernelProposal {
+            proposal_id,
+            nonce: hex::encode(nonce),
+            subject_id: art.subject_id.clone(),
+            artifact_path: art.path.clone(),
+            payload_hash: payload_hash(payload),
+            proposer: proposer.to_string(),
+            created_at: now,
+            expires_at: now + self.ttl_secs,
+            approvals: Vec::new(),
+            applied: false,
+        });
+        Ok(proposal_id)
+    }
+
+    /// Drop applied and expired proposals; their ids are not handed out again.
+    pub fn prune(&mut self) {
+        let now = unix_now();
+        let keep: Vec<bool> = self
+            .proposals
+            .iter()
+            .map(|p| !matches!(self.status_of(p, now), ProposalStatus::Applied | ProposalStatus::Expired))
+            .collect();
+        let mut keep = keep.into_iter();
+        self.proposals.retain(|_| keep.next().unwrap_or(true));
+    }
+
+    pub fn get(&self, proposal_id: u64) -> Option<&KernelProposal> {
+        self.proposals.iter().find(|p| p.proposal_id == proposal_id)
+    }
+
+    pub fn status(&self, proposal_id: u64) -> Option<ProposalStatus> {
+        self.get(proposal_id).map(|p| self.status_of(p, unix_now()))
+    }
+
+    fn status_of(&self, p: &KernelProposal, now: u64) -> ProposalStatus {
+        let frozen = self
+            .liberty
+            .as_ref()
+            // A poisoned board fails closed.
+            .is_some_and(|l| l.is_suspended(&p.subject_id).unwrap_or(true));
+        if p.applied {
+            ProposalStatus::Applied
+        } else if frozen {
+            ProposalStatus::Frozen
+        } else if now >= p.expires_at {
+            // Checked before quorum: approved proposals must be applied by the deadline too.
+            ProposalStatus::Expired
+        } else if self.quorum.is_met(&p.approvals) {
+            ProposalStatus::Approved
+        } else {
+            ProposalStatus::Pending
+        }
+    }
+
+    pub fn approve(&mut self, proposal_id: u64, approval: GuardianApproval) -> Result<ProposalStatus, FsError> {
+        let now = unix_now();
+        let guardian = self
+            .quorum
+            .guardian(&approval.guardian_id)
+            .ok_or_else(|| FsError::PolicyError(format!("{} is not a guardian", approval.guardian_id)))?;
+        let idx = self
+            .proposals
+            .iter()
+            .position(|p| p.proposal_id == proposal_id)
+            .ok_or_else(|| FsError::PolicyError(format!("unknown proposal {}", proposal_id)))?;
+
+        let status = self.status_of(&self.proposals[idx], now);
+        if status != ProposalStatus::Pending {
+            return Err(FsError::PolicyError(format!(
+                "proposal {} is {:?}, not pending",
+                proposal_id, status
+            )));
+        }
+
+        let proposal = &self.proposals[idx];
+        let sig_bytes = hex::decode(&approval.signature)
+            .map_err(|_| FsError::PolicyError("approval signature is not valid hex".into()))?;
+        let sig = Signature::from_slice(&sig_bytes)
+            .map_err(|_| FsError::PolicyError("malformed approval signature".into()))?;
+        guardian
+            .key
+            .verify(&proposal.signing_bytes(), &sig)
+            .map_err(|_| FsError::PolicyError("approval signature verification failed".into()))?;
+
+        let proposal = &mut self.proposals[idx];
+        if !proposal.approvals.iter().any(|a| a.guardian_id == approval.guardian_id) {
+            proposal.approvals.push(approval);
+        }
+        Ok(self.status_of(&self.proposals[idx], now))
+    }
+
+    /// Write `payload` to the artifact once its proposal has reached quorum.
+    ///
+    /// Quorum replaces only the kernel lock: every other guard in `guards` still
+    /// runs, and the decision is recorded on `guards.audit` either way.
+    pub fn apply(
+        &mut self,
+        proposal_id: u64,
+        art: SovereignArtifact,
+        payload: &[u8],
+        caller: CallerContext,
+        guards: &GuardSet,
+    ) -> Result<(), FsError> {
+        let now = unix_now();
+        let mut d = DecisionBuilder::new("SovereignKernelLock", "apply");
+        let Some(idx) = self.proposals.iter().position(|p| p.proposal_id == proposal_id) else {
+            d.fail(CheckKind::Rule, "proposal", format!("unknown proposal {}", proposal_id));
+            return Err(FsError::Denied(Box::new(d.finish())));
+        };
+        let proposal = &self.proposals[idx];
+        let status = self.status_of(proposal, now);
+        if status != ProposalStatus::Approved {
+            d.fail(
+                CheckKind::Threshold,
+                "guardian-quorum",
+                format!("proposal {} is {:?}, not approved", proposal_id, status),
+            );
+        } else if proposal.artifact_path != art.path || proposal.payload_hash != payload_hash(payload) {
+            d.fail(CheckKind::Rule, "payload-hash", "payload does not match the approved proposal");
+        } else {
+            d.pass(
+                CheckKind::Threshold,
+                "guardian-quorum",
+                format!("proposal {} approved for payload {}", proposal_id, proposal.payload_hash),
+            );
+        }
+        if !d.is_denied() {
+            d.include(guards.evaluate_guardian_approved(&caller, &art, FsMode::WriteOnly));
+        }
+        let decision = d.finish();
+        if !decision.is_allowed() {
+            guards.record(&caller, &art, FsMode::WriteOnly, KERNEL_APPLY, &decision)?;
+        }
+        let decision = decision.into_result()?;
+
+        let mut handle = FsHandle::open_admitted(art, FsMode::WriteOnly, caller, true, decision, guards, KERNEL_APPLY)?;
+        handle.replace_all(payload)?;
+        self.proposals[idx].applied = true;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(seed: u8) -> SigningKey {
+        SigningKey::from_bytes(&[seed; 32])
+    }
+
+    fn quorum() -> GuardianQuorum {
+        let stake = format!(
+            "ROW,guardian,g1,scalar,weight,1.0,float,,\n\
+             ROW,guardian,g1,scalar,pubkey,{},string,,\n\
+             ROW,quorum,policy,scalar,threshold,0.5,float,,\n",
+            hex::encode(key(1).verifying_key().to_bytes())
+        );
+        GuardianQuorum::from_stake_files(&[("family.stake.aln", &stake)]).unwrap()
+    }
+
+    fn approved(book: &mut ProposalBook) -> KernelProposal {
+        let art = SovereignArtifact::sovereign_config("subject-1.sovereign.aln", "subject-1");
+        let id = book.propose(&art, b"new config", "subject-1").unwrap();
+        let approval = book.get(id).unwrap().sign("g1", &key(1));
+        assert_eq!(book.approve(id, approval).unwrap(), ProposalStatus::Approved);
+        book.get(id).unwrap().clone()
+    }
+
+    #[test]
+    fn approved_proposals_expire_at_the_deadline() {
+        let mut book = ProposalBook::new(quorum());
+        let p = approved(&mut book);
+        assert_eq!(book.status_of(&p, p.expires_at - 1), ProposalStatus::Approved);
+        assert_eq!(book.status_of(&p, p.expires_at), ProposalStatus::Expired);
+        assert_eq!(book.status_of(&p, p.expires_at + DEFAULT_PROPOSAL_TTL_SECS), ProposalStatus::Expired);
+    }
+
+    #[test]
+    fn expired_proposals_take_no_approvals() {
+        let mut book = ProposalBook::new(quorum()).with_ttl(0);
+        let art = SovereignArtifact::sovereign_config("subject-1.sovereign.aln", "subject-1");
+        let id = book.propose(&art, b"new config", "subject-1").unwrap();
+        assert_eq!(book.status(id), Some(ProposalStatus::Expired));
+        let approval = book.get(id).unwrap().sign("g1", &key(1));
+        assert!(book.approve(id, approval).is_err());
+    }
+
+    #[test]
+    fn apply_explains_why_a_proposal_is_refused() {
+        let dir = std::env::temp_dir().join(format!("neuroxfs-kernel-apply-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let art = SovereignArtifact::sovereign_config(dir.join("subject-1.sovereign.aln").display().to_string(), "subject-1");
+        let subject = CallerContext::subject("subject-1", crate::routes::Route::Introspect);
+        let guards = GuardSet::default();
+        let mut book = ProposalBook::new(quorum());
+        let id = book.propose(&art, b"new config", "subject-1").unwrap();
+
+        let err = book.apply(id, art.clone(), b"new config", subject.clone(), &guards).unwrap_err();
+        match err {
+            FsError::Denied(d) => assert!(d.summary().contains("Pending, not approved"), "{}", d.summary()),
+            other => panic!("expected a guard decision, got {}", other),
+        }
+
+        let approval = book.get(id).unwrap().sign("g1", &key(1));
+        book.approve(id, approval).unwrap();
+        let err = book.apply(id, art.clone(), b"other config", subject.clone(), &guards).unwrap_err();
+        assert!(err.to_string().contains("payload does not match"), "{}", err);
+
+        book.apply(id, art.clone(), b"new config", subject, &guards).unwrap();
+        assert_eq!(std::fs::read(&art.path).unwrap(), b"new config");
+        assert_eq!(book.status(id), Some(ProposalStatus::Applied));
+    }
+
+    #[test]
+    fn approved_proposals_still_pass_every_guard() {
+        use crate::audit::AuditChain;
+        use crate::plugins::fixtures;
+        use std::sync::Mutex;
+
+        let dir = std::env::temp_dir().join(format!("neuroxfs-kernel-guards-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let art = SovereignArtifact::sovereign_config(dir.join("subject-1.sovereign.aln").display().to_string(), "subject-1");
+        std::fs::write(&art.path, "old config").unwrap();
+        let subject = CallerContext::subject("subject-1", crate::routes::Route::Introspect);
+        let guards = GuardSet {
+            plugins: vec![fixtures::fixed_verdict(
+                "site-freeze",
+                r#"{"outcome":"Deny","label":"change-freeze","detail":"site change freeze"}"#,
+            )],
+            audit: Some(Arc::new(Mutex::new(AuditChain::new()))),
+            ..GuardSet::default()
+        };
+        let mut book = ProposalBook::new(quorum());
+        let id = book.propose(&art, b"new config", "subject-1").unwrap();
+        let approval = book.get(id).unwrap().sign("g1", &key(1));
+        assert_eq!(book.approve(id, approval).unwrap(), ProposalStatus::Approved);
+
+        match book.apply(id, art.clone(), b"new config", subject, &guards).unwrap_err() {
+            FsError::Denied(d) => assert!(d.summary().contains("site change freeze"), "{}", d.summary()),
+            other => panic!("expected a guard decision, got {}", other),
+        }
+        assert_eq!(std::fs::read(&art.path).unwrap(), b"old config");
+        assert_eq!(book.status(id), Some(ProposalStatus::Approved));
+        let audit = guards.audit.as_ref().unwrap().lock().unwrap();
+        let records: Vec<_> = audit.records().collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].body.action, "kernel-apply");
+        assert!(records[0].body.detail.contains("Deny"), "{}", records[0].body.detail);
+    }
+
+    #[test]
+    fn applied_proposals_are_recorded_once() {
+        use crate::audit::AuditChain;
+        use std::sync::Mutex;
+
+        let dir = std::env::temp_dir().join(format!("neuroxfs-kernel-record-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let art = SovereignArtifact::sovereign_config(dir.join("subject-1.sovereign.aln").display().to_string(), "subject-1");
+        std::fs::write(&art.path, "old config").unwrap();
+        let subject = CallerContext::subject("subject-1", crate::routes::Route::Introspect);
+        let guards = GuardSet {
+            audit: Some(Arc::new(Mutex::new(AuditChain::new()))),
+            ..GuardSet::default()
+        };
+        let mut book = ProposalBook::new(quorum());
+        let id = book.propose(&art, b"new config", "subject-1").unwrap();
+        let approval = book.get(id).unwrap().sign("g1", &key(1));
+        book.approve(id, approval).unwrap();
+
+        book.apply(id, art.clone(), b"new config", subject, &guards).unwrap();
+        assert_eq!(std::fs::read(&art.path).unwrap(), b"new config");
+        let audit = guards.audit.as_ref().unwrap().lock().unwrap();
+        let records: Vec<_> = audit.records().collect();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].body.action, "kernel-apply");
+        assert!(records[0].body.detail.starts_with("WriteOnly Allow"), "{}", records[0].body.detail);
+    }
+
+    #[test]
+    fn approvals_do_not_carry_over_to_a_fresh_book() {
+        let art = SovereignArtifact::sovereign_config("subject-1.sovereign.aln", "subject-1");
+        let mut old = ProposalBook::new(quorum());
+        let old_id = old.propose(&art, b"old config", "subject-1").unwrap();
+        let replayed = old.get(old_id).unwrap().sign("g1", &key(1));
+
+        // Same id, artifact and payload as the proposal that was approved before.
+        let mut fresh = ProposalBook::new(quorum());
+        let id = fresh.propose(&art, b"old config", "subject-1").unwrap();
+        assert_eq!(id, old_id);
+        let err = fresh.approve(id, replayed).unwrap_err();
+        assert!(err.to_string().contains("verification failed"), "{}", err);
+        assert_eq!(fresh.status(id), Some(ProposalStatus::Pending));
+    }
+
+    #[test]
+    fn duplicate_guardians_are_rejected() {
+        let pubkey = hex::encode(key(1).verifying_key().to_bytes());
+        let family = format!(
+            "ROW,guardian,g1,scalar,weight,1.0,float,,\n\
+             ROW,guardian,g1,scalar,pubkey,{},string,,\n\
+             ROW,quorum,policy,scalar,threshold,0.5,float,,\n",
+            pubkey
+        );
+        let clinic = format!(
+            "ROW,guardian,g1,scalar,weight,9.0,float,,\n\
+             ROW,guardian,g1,scalar,pubkey,{},string,,\n",
+            hex::encode(key(2).verifying_key().to_bytes())
+        );
+        let err = GuardianQuorum::from_stake_files(&[("family.stake.aln", &family), ("clinic.stake.aln", &clinic)])
+            .unwrap_err();
+        assert!(err.to_string().contains("clinic.stake.aln:1: invalid duplicate guardian weight"), "{}", err);
+
+        let twice = format!("{}ROW,guardian,g1,scalar,pubkey,{},string,,\n", family, pubkey);
+        assert!(GuardianQuorum::from_stake_files(&[("family.stake.aln", &twice)]).is_err());
+    }
+
+    #[test]
+    fn proposal_ids_are_not_reused_after_pruning() {
+        let mut book = ProposalBook::new(quorum()).with_ttl(0);
+        let art = SovereignArtifact::sovereign_config("subject-1.sovereign.aln", "subject-1");
+        let first = book.propose(&art, b"a", "subject-1").unwrap();
+        book.prune();
+        assert!(book.get(first).is_none());
+        let second = book.propose(&art, b"b", "subject-1").unwrap();
+        assert!(second > first);
+    }
+}
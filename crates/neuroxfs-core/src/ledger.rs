@@ -0,0 +1,411 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::artifact::{ArtifactKind, NeurorightsProfile, SovereignArtifact};
+use crate::error::FsError;
+use crate::fs_handle::FsHandle;
+use crate::grants::GrantOperation;
+use crate::routes::{Route, RouteAllowance};
+
+// Merkle hashing follows RFC 9162: leaves and interior nodes are domain
+// separated, and an unbalanced tree splits at the largest power of two.
+
+fn leaf_hash(bytes: &[u8]) -> [u8; 32] {
+    let mut h = Sha256::new();
+    h.update([0x00]);
+    h.update(bytes);
+    h.finalize().into()
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut h = Sha256::new();
+    h.update([0x01]);
+    h.update(left);
+    h.update(right);
+    h.finalize().into()
+}
+
+fn split_point(n: usize) -> usize {
+    let mut k = 1;
+    while k * 2 < n {
+        k *= 2;
+    }
+    k
+}
+
+fn subtree_root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    match leaves.len() {
+        0 => Sha256::digest([]).into(),
+        1 => leaves[0],
+        n => {
+            let k = split_point(n);
+            node_hash(&subtree_root(&leaves[..k]), &subtree_root(&leaves[k..]))
+        }
+    }
+}
+
+fn audit_path(index: usize, leaves: &[[u8; 32]]) -> Vec<[u8; 32]> {
+    if leaves.len() <= 1 {
+        return Vec::new();
+    }
+    let k = split_point(leaves.len());
+    let (mut path, sibling) = if index < k {
+        (audit_path(index, &leaves[..k]), subtree_root(&leaves[k..]))
+    } else {
+        (audit_path(index - k, &leaves[k..]), subtree_root(&leaves[..k]))
+    };
+    path.push(sibling);
+    path
+}
+
+fn decode_hash(s: &str) -> Option<[u8; 32]> {
+    hex::decode(s).ok()?.try_into().ok()
+}
+
+/// One DonutLedger record; its leaf hash covers the JSON encoding.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub seq: u64,
+    pub timestamp: u64,
+    pub subject_id: String,
+    pub kind: String,
+    pub payload: serde_json::Value,
+}
+
+impl LedgerEntry {
+    pub fn leaf_hash(&self) -> String {
+        hex::encode(leaf_hash(&serde_json::to_vec(self).expect("ledger entry serialization is infallible")))
+    }
+}
+
+/// Root of the first `tree_size` entries at the time it was taken.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub tree_size: u64,
+    pub root: String,
+    pub timestamp: u64,
+}
+
+/// Evidence that one entry is included under a checkpoint root.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub leaf_index: u64,
+    pub tree_size: u64,
+    pub audit_path: Vec<String>,
+}
+
+impl InclusionProof {
+    /// Offline check: needs only the entry, this proof and a trusted root.
+    pub fn verify(&self, entry: &LedgerEntry, root: &str) -> bool {
+        let (Some(mut r), Some(root)) = (decode_hash(&entry.leaf_hash()), decode_hash(root)) else {
+            return false;
+        };
+        if entry.seq != self.leaf_index || self.leaf_index >= self.tree_size {
+            return false;
+        }
+        let (mut fnode, mut snode) = (self.leaf_index, self.tree_size - 1);
+        for p in &self.audit_path {
+            let Some(p) = decode_hash(p) else {
+                return false;
+            };
+            if snode == 0 {
+                return false;
+            }
+            if fnode & 1 == 1 || fnode == snode {
+                r = node_hash(&p, &r);
+                while fnode & 1 == 0 && fnode != 0 {
+                    fnode >>= 1;
+                    snode >>= 1;
+                }
+            } else {
+                r = node_hash(&r, &p);
+            }
+            fnode >>= 1;
+            snode >>= 1;
+        }
+        snode == 0 && r == root
+    }
+}
+
+/// On-disk line of a `.donutloop.jsonl` ledger.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum LedgerLine {
+    Entry(LedgerEntry),
+    Checkpoint(Checkpoint),
+}
+
+/// Append-only ledger with periodic Merkle checkpoints.
+#[derive(Debug, Clone, Default)]
+pub struct DonutLedger {
+    subject_id: String,
+    entries: Vec<LedgerEntry>,
+    leaves: Vec<[u8; 32]>,
+    checkpoints: Vec<Checkpoint>,
+}
+
+impl DonutLedger {
+    pub fn new(subject_id: &str) -> Self {
+        Self {
+            subject_id: subject_id.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn file_name(subject_id: &str) -> String {
+        format!("{}.donutloop.jsonl", subject_id)
+    }
+
+    pub fn entries(&self) -> &[LedgerEntry] {
+        &self.entries
+    }
+
+    pub fn checkpoints(&self) -> &[Checkpoint] {
+        &self.checkpoints
+    }
+
+    pub fn append(&mut self, timestamp: u64, kind: &str, payload: serde_json::Value) -> &LedgerEntry {
+        let entry = LedgerEntry {
+            seq: self.entries.len() as u64,
+            timestamp,
+            subject_id: self.subject_id.clone(),
+            kind: kind.to_string(),
+            payload,
+        };
+        self.push(entry);
+        self.entries.last().expect("just pushed")
+    }
+
+    fn push(&mut self, entry: LedgerEntry) {
+        self.leaves
+            .push(leaf_hash(&serde_json::to_vec(&entry).expect("ledger entry serialization is infallible")));
+        self.entries.push(entry);
+    }
+
+    pub fn root_at(&self, tree_size: u64) -> Option<String> {
+        let n = usize::try_from(tree_size).ok().filter(|n| *n <= self.leaves.len())?;
+        Some(hex::encode(subtree_root(&self.leaves[..n])))
+    }
+
+    /// Seal the current tree. A no-op when nothing was appended since the last one.
+    pub fn checkpoint(&mut self, timestamp: u64) -> &Checkpoint {
+        let tree_size = self.entries.len() as u64;
+        if self.checkpoints.last().is_none_or(|c| c.tree_size != tree_size) {
+            let root = self.root_at(tree_size).expect("current size is always in range");
+            self.checkpoints.push(Checkpoint { tree_size, root, timestamp });
+        }
+        self.checkpoints.last().expect("at least one checkpoint")
+    }
+
+    pub fn inclusion_proof(&self, seq: u64, tree_size: u64) -> Result<InclusionProof, FsError> {
+        if seq >= tree_size || tree_size > self.entries.len() as u64 {
+            return Err(FsError::PolicyError(format!(
+                "no entry {} in a tree of size {} (ledger holds {})",
+                seq,
+                tree_size,
+                self.entries.len()
+            )));
+        }
+        let path = audit_path(seq as usize, &self.leaves[..tree_size as usize]);
+        Ok(InclusionProof {
+            leaf_index: seq,
+            tree_size,
+            audit_path: path.iter().map(hex::encode).collect(),
+        })
+    }
+
+    pub fn to_jsonl(&self) -> String {
+        let mut lines = Vec::new();
+        let mut cps = self.checkpoints.iter().peekable();
+        for entry in &self.entries {
+            lines.push(LedgerLine::Entry(entry.clone()));
+            while let Some(cp) = cps.next_if(|c| c.tree_size == entry.seq + 1) {
+                lines.push(LedgerLine::Checkpoint(cp.clone()));
+            }
+        }
+        lines
+            .iter()
+            .map(|l| serde_json::to_string(l).expect("ledger line serialization is infallible") + "\n")
+            .collect()
+    }
+
+    /// Parse and re-check every stored checkpoint against the entries before it.
+    pub fn from_jsonl(subject_id: &str, text: &str) -> Result<Self, FsError> {
+        let mut ledger = Self::new(subject_id);
+        for (idx, line) in text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty()) {
+            let parsed: LedgerLine = serde_json::from_str(line)
+                .map_err(|e| FsError::PolicyError(format!("ledger line {}: {}", idx + 1, e)))?;
+            match parsed {
+                LedgerLine::Entry(entry) => {
+                    if entry.seq != ledger.entries.len() as u64 || entry.subject_id != subject_id {
+                        return Err(FsError::PolicyError(format!("ledger line {}: entry out of sequence", idx + 1)));
+                    }
+                    ledger.push(entry);
+                }
+                LedgerLine::Checkpoint(cp) => {
+                    if ledger.root_at(cp.tree_size).as_deref() != Some(cp.root.as_str()) {
+                        return Err(FsError::PolicyError(format!(
+                            "ledger line {}: checkpoint root does not match entries",
+                            idx + 1
+                        )));
+                    }
+                    ledger.checkpoints.push(cp);
+                }
+            }
+        }
+        Ok(ledger)
+    }
+
+    pub fn load(subject_id: &str, handle: &mut FsHandle) -> Result<Self, FsError> {
+        let bytes = handle.read_all()?;
+        let text = String::from_utf8(bytes).map_err(|_| FsError::PolicyError("ledger is not UTF-8".into()))?;
+        Self::from_jsonl(subject_id, &text)
+    }
+
+    pub fn store(&self, handle: &mut FsHandle) -> Result<(), FsError> {
+        handle.replace_all(self.to_jsonl().as_bytes())
+    }
+
+    /// Export a checkpoint for anchoring; the file is self-contained.
+    pub fn export_proof(&self, checkpoint: &Checkpoint, ledger_path: &str) -> BChainProof {
+        let prev_root = self
+            .checkpoints
+            .iter()
+            .take_while(|c| c.tree_size < checkpoint.tree_size)
+            .last()
+            .map(|c| c.root.clone());
+        BChainProof {
+            subject_id: self.subject_id.clone(),
+            ledger_path: ledger_path.to_string(),
+            checkpoint: checkpoint.clone(),
+            prev_root,
+        }
+    }
+}
+
+/// A checkpoint root published outside the ledger. Third parties keep the
+/// root and check `InclusionProof`s against it; no live chain is involved.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BChainProof {
+    pub subject_id: String,
+    pub ledger_path: String,
+    pub checkpoint: Checkpoint,
+    pub prev_root: Option<String>,
+}
+
+impl BChainProof {
+    pub fn file_name(&self) -> String {
+        format!("{}.{}.bchainproof.json", self.subject_id, self.checkpoint.tree_size)
+    }
+
+    /// Descriptor for the exported proof: public, read-only everywhere but INTROSPECT.
+    pub fn artifact(&self, dir: &str) -> SovereignArtifact {
+        SovereignArtifact {
+            path: format!("{}/{}", dir.trim_end_matches('/'), self.file_name()),
+            subject_id: self.subject_id.clone(),
+            kind: ArtifactKind::BChainProof,
+            routes: vec![Route::Introspect, Route::Chat, Route::Ota],
+            route_allowances: vec![RouteAllowance {
+                route: Route::Introspect,
+                operations: vec![GrantOperation::Read, GrantOperation::Write],
+            }],
+            roh_before: 0.0,
+            roh_after: 0.0,
+            neurorights: NeurorightsProfile::default(),
+            lifeforce_cost: 0.0,
+            governance_tags: Vec::new(),
+            inputs: Vec::new(), // a root reveals nothing of the entries; no taint
+        }
+    }
+
+    pub fn verify_entry(&self, entry: &LedgerEntry, proof: &InclusionProof) -> bool {
+        entry.subject_id == self.subject_id
+            && proof.tree_size == self.checkpoint.tree_size
+            && proof.verify(entry, &self.checkpoint.root)
+    }
+
+    pub fn store(&self, handle: &mut FsHandle) -> Result<(), FsError> {
+        let bytes = serde_json::to_vec_pretty(self)
+            .map_err(|e| FsError::PolicyError(format!("proof serialization error: {}", e)))?;
+        handle.replace_all(&bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ledger(n: u64) -> DonutLedger {
+        let mut ledger = DonutLedger::new("subject-1");
+        for i in 0..n {
+            ledger.append(1_000 + i, "read", serde_json::json!({ "i": i }));
+        }
+        ledger
+    }
+
+    fn leaf(ledger: &DonutLedger, i: usize) -> [u8; 32] {
+        decode_hash(&ledger.entries()[i].leaf_hash()).unwrap()
+    }
+
+    #[test]
+    fn unbalanced_roots_split_at_the_largest_power_of_two() {
+        let l = ledger(5);
+        let (a, b, c, d, e) = (leaf(&l, 0), leaf(&l, 1), leaf(&l, 2), leaf(&l, 3), leaf(&l, 4));
+        let three = node_hash(&node_hash(&a, &b), &c);
+        assert_eq!(l.root_at(3).unwrap(), hex::encode(three));
+        let five = node_hash(&node_hash(&node_hash(&a, &b), &node_hash(&c, &d)), &e);
+        assert_eq!(l.root_at(5).unwrap(), hex::encode(five));
+    }
+
+    #[test]
+    fn every_entry_verifies_for_every_tree_size() {
+        let l = ledger(17);
+        for size in 1..=17u64 {
+            let root = l.root_at(size).unwrap();
+            for seq in 0..size {
+                let proof = l.inclusion_proof(seq, size).unwrap();
+                assert!(proof.verify(&l.entries()[seq as usize], &root), "seq {} of {}", seq, size);
+            }
+        }
+    }
+
+    #[test]
+    fn proofs_against_older_checkpoints_still_verify() {
+        let mut l = ledger(6);
+        let old = l.checkpoint(2_000).clone();
+        for i in 0..5 {
+            l.append(3_000 + i, "write", serde_json::json!({}));
+        }
+        let proof = l.inclusion_proof(5, old.tree_size).unwrap();
+        assert!(proof.verify(&l.entries()[5], &old.root));
+        assert!(!proof.verify(&l.entries()[5], &l.root_at(11).unwrap()));
+    }
+
+    #[test]
+    fn altered_proofs_and_entries_fail() {
+        let l = ledger(7);
+        let root = l.root_at(7).unwrap();
+        let proof = l.inclusion_proof(6, 7).unwrap();
+        let entry = &l.entries()[6];
+        assert!(proof.verify(entry, &root));
+
+        let mut tampered = entry.clone();
+        tampered.payload = serde_json::json!({ "i": 99 });
+        assert!(!proof.verify(&tampered, &root));
+        assert!(!proof.verify(&l.entries()[5], &root));
+
+        for size in [6, 8] {
+            let resized = InclusionProof { tree_size: size, ..proof.clone() };
+            assert!(!resized.verify(entry, &root), "tree size {}", size);
+        }
+        let mut short = proof.clone();
+        short.audit_path.pop();
+        assert!(!short.verify(entry, &root));
+        let mut long = proof.clone();
+        long.audit_path.push(root.clone());
+        assert!(!long.verify(entry, &root));
+
+        assert!(l.inclusion_proof(7, 7).is_err());
+        assert!(l.inclusion_proof(0, 8).is_err());
+    }
+}
@@ -1,8 +1,25 @@
 pub mod artifact;
+pub mod caller;
+pub mod clock;
 pub mod guards;
+pub mod grants;
 pub mod fs_handle;
-pub mod kernel_lock; // optional wrapper re-export if you want
+pub mod kernel_lock; // m-of-n guardian proposals for kernel artifacts
 pub mod aura_boundary; // can re-export from guards or split
+pub mod routes;
+pub mod tags;
+pub mod shard_signatures; // .aln.sig checks for signed-shard workspaces
+pub mod plugins; // sandboxed WebAssembly guards
+pub mod registry;
+pub mod lineage;
+pub mod liberty;
+pub mod dual_control; // subject + clinician writes for BCI stimulation configs
 pub mod policy; // future Tsafe / RoH integration
 pub mod error;
+pub mod explain;
 pub mod agent_adapter;
+pub mod audit;
+pub mod evolve; // .evolve.jsonl events, replay and drift detection
+pub mod ledger; // DonutLedger Merkle checkpoints, BChainProof export
+pub mod break_glass;
+pub mod simulation;
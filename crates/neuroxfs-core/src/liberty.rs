@@ -0,0 +1,386 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use ed25519_dalek::{Signature, Signer, SigningKey, Verifier};
+use serde::{Deserialize, Serialize};
+
+use crate::artifact::SovereignArtifact;
+use crate::error::FsError;
+use crate::explain::{CheckKind, DecisionBuilder};
+use crate::grants::SubjectKeyring;
+
+/// A subject's own signed suspend / resume action.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibertyAction {
+    pub subject_id: String,
+    pub suspend: bool,
+    pub issued_at: u64,
+    pub epoch: u64, // switch epoch this action moves to; each action is good once
+    pub signature: String, // hex Ed25519 over `signing_bytes`
+}
+
+impl LibertyAction {
+    fn signing_bytes(&self) -> Vec<u8> {
+        let verb = if self.suspend { "suspend" } else { "resume" };
+        format!("liberty:{}:{}:{}:{}", self.subject_id, verb, self.issued_at, self.epoch).into_bytes()
+    }
+
+    pub fn sign(subject_id: &str, suspend: bool, issued_at: u64, epoch: u64, key: &SigningKey) -> Self {
+        let mut action = Self {
+            subject_id: subject_id.to_string(),
+            suspend,
+            issued_at,
+            epoch,
+            signature: String::new(),
+        };
+        action.signature = hex::encode(key.sign(&action.signing_bytes()).to_bytes());
+        action
+    }
+}
+
+/// On-disk form: `<subject>.liberty.neurorights.json`, the last accepted action;
+/// its `epoch` is the switch epoch a restarted board resumes at. Only
+/// `LibertyBoard` reads and writes it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibertyRecord {
+    pub last_action: LibertyAction,
+}
+
+impl LibertyRecord {
+    pub fn file_name(subject_id: &str) -> String {
+        format!("{}.liberty.neurorights.json", subject_id)
+    }
+
+    pub fn artifact(&self, dir: &str) -> SovereignArtifact {
+        let subject_id = &self.last_action.subject_id;
+        SovereignArtifact::liberty_switch(
+            format!("{}/{}", dir.trim_end_matches('/'), Self::file_name(subject_id)),
+            subject_id,
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+struct SubjectSwitch {
+    last_action: Option<LibertyAction>,
+    epoch: Arc<AtomicU64>,
+}
+
+impl SubjectSwitch {
+    fn is_suspended(&self) -> bool {
+        self.last_action.as_ref().is_some_and(|a| a.suspend)
+    }
+}
+
+/// Snapshot of a subject's switch epoch taken when a handle was opened.
+#[derive(Debug, Clone)]
+pub struct HandleLease {
+    epoch: Arc<AtomicU64>,
+    issued_epoch: u64,
+}
+
+impl HandleLease {
+    /// False once the subject has toggled the switch since the lease was issued.
+    pub fn is_valid(&self) -> bool {
+        self.epoch.load(Ordering::SeqCst) == self.issued_epoch
+    }
+
+    /// Epoch the lease was issued at.
+    pub fn epoch(&self) -> u64 {
+        self.issued_epoch
+    }
+}
+
+/// Cognitive-liberty switches for every subject, shared between the subject's
+/// control surface and the agent adapter.
+///
+/// A board opened on a directory keeps each subject's last action, and with it
+/// the switch epoch, in that subject's `LibertySwitch` artifact there; `apply`
+/// writes it before the new state takes effect, so a restart resumes at the
+/// same epoch.
+#[derive(Debug, Default)]
+pub struct LibertyBoard {
+    keyring: SubjectKeyring,
+    switches: Mutex<HashMap<String, SubjectSwitch>>,
+    dir: Option<PathBuf>,
+}
+
+impl LibertyBoard {
+    pub fn new(keyring: SubjectKeyring) -> Self {
+        Self {
+            keyring,
+            switches: Mutex::new(HashMap::new()),
+            dir: None,
+        }
+    }
+
+    /// Load every `<subject>.liberty.neurorights.json` under `dir` (signatures
+    /// re-checked) and persist later actions there.
+    pub fn open(keyring: SubjectKeyring, dir: impl Into<PathBuf>) -> Result<Self, FsError> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir).map_err(FsError::Io)?;
+        let board = Self {
+            dir: Some(dir.clone()),
+            ..Self::new(keyring)
+        };
+        let mut switches = board.switches()?;
+        for entry in std::fs::read_dir(&dir).map_err(FsError::Io)? {
+            let path = entry.map_err(FsError::Io)?.path();
+            let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            let Some(subject_id) = name.strip_suffix(".liberty.neurorights.json") else {
+                continue;
+            };
+            let bytes = std::fs::read(&path).map_err(FsError::Io)?;
+            let record: LibertyRecord = serde_json::from_slice(&bytes)
+                .map_err(|e| FsError::PolicyError(format!("{}: liberty record parse error: {}", name, e)))?;
+            let action = record.last_action;
+            if action.subject_id != subject_id {
+                return Err(FsError::PolicyError(format!("{} holds a record for {}", name, action.subject_id)));
+            }
+            board.verify(&action)?;
+            switches.insert(
+                action.subject_id.clone(),
+                SubjectSwitch {
+                    epoch: Arc::new(AtomicU64::new(action.epoch)),
+                    last_action: Some(action),
+                },
+            );
+        }
+        drop(switches);
+        Ok(board)
+    }
+
+    fn switches(&self) -> Result<MutexGuard<'_, HashMap<String, SubjectSwitch>>, FsError> {
+        self.switches
+            .lock()
+            .map_err(|_| FsError::PolicyError("liberty board lock poisoned".into()))
+    }
+
+    fn verify(&self, action: &LibertyAction) -> Result<(), FsError> {
+        let key = self.keyring.get(&action.subject_id).ok_or_else(|| {
+            FsError::PolicyError(format!("no signing key registered for {}", action.subject_id))
+        })?;
+        let bytes = hex::decode(&action.signature)
+            .map_err(|_| FsError::PolicyError("liberty action signature is not valid hex".into()))?;
+        let sig = Signature::from_slice(&bytes)
+            .map_err(|_| FsError::PolicyError("malformed liberty action signature".into()))?;
+        key.verify(&action.signing_bytes(), &sig)
+            .map_err(|_| FsError::PolicyError("liberty action must be signed by the subject".into()))
+    }
+
+    /// Write `record` to the subject's switch file: temp file, fsync, rename.
+    fn persist(dir: &Path, record: &LibertyRecord) -> Result<(), FsError> {
+        let path = dir.join(LibertyRecord::file_name(&record.last_action.subject_id));
+        let tmp = path.with_extension("json.tmp");
+        let bytes = serde_json::to_vec_pretty(record)
+            .map_err(|e| FsError::PolicyError(format!("liberty record serialization error: {}", e)))?;
+        let mut file = File::create(&tmp).map_err(FsError::Io)?;
+        file.write_all(&bytes).map_err(FsError::Io)?;
+        file.sync_all().map_err(FsError::Io)?;
+        std::fs::rename(&tmp, &path).map_err(FsError::Io)
+    }
+
+    /// Apply a signed suspend or resume. Either bumps the epoch, so every lease
+    /// issued before it stops working; the action must name the epoch it moves
+    /// to, so a captured action cannot be played again.
+    pub fn apply(&self, action: LibertyAction) -> Result<LibertyRecord, FsError> {
+        self.verify(&action)?;
+
+        let mut switches = self.switches()?;
+        let switch = switches.entry(action.subject_id.clone()).or_default();
+        if let Some(last) = &switch.last_action {
+            if action.issued_at <= last.issued_at {
+                return Err(FsError::PolicyError("liberty action is older than the current state".into()));
+            }
+        }
+        let current = switch.epoch.load(Ordering::SeqCst);
+        if action.epoch != current + 1 {
+            return Err(FsError::PolicyError(format!(
+                "liberty action is for epoch {}, the switch is at epoch {}",
+                action.epoch, current
+            )));
+        }
+        let record = LibertyRecord { last_action: action.clone() };
+        if let Some(dir) = &self.dir {
+            Self::persist(dir, &record)?;
+        }
+        switch.epoch.store(action.epoch, Ordering::SeqCst);
+        switch.last_action = Some(action);
+        Ok(record)
+    }
+
+    /// The subject's current switch epoch (0 before any action).
+    pub fn epoch(&self, subject_id: &str) -> Result<u64, FsError> {
+        Ok(self
+            .switches()?
+            .get(subject_id)
+            .map_or(0, |s| s.epoch.load(Ordering::SeqCst)))
+    }
+
+    pub fn is_suspended(&self, subject_id: &str) -> Result<bool, FsError> {
+        Ok(self.switches()?.get(subject_id).is_some_and(SubjectSwitch::is_suspended))
+    }
+
+    /// Lease the subject's current epoch for a new handle. Refused while the
+    /// subject is suspended; the check and the lease happen under one lock, so
+    /// a suspend cannot land between them.
+    pub fn lease(&self, subject_id: &str) -> Result<HandleLease, FsError> {
+        let mut switches = self.switches()?;
+        let switch = switches.entry(subject_id.to_string()).or_default();
+        if switch.is_suspended() {
+            let mut d = DecisionBuilder::new("LibertyBoard", "lease");
+            d.fail(CheckKind::Rule, "cognitive_liberty", "subject has suspended all agent access");
+            return Err(FsError::Denied(Box::new(d.finish())));
+        }
+        Ok(HandleLease {
+            epoch: switch.epoch.clone(),
+            issued_epoch: switch.epoch.load(Ordering::SeqCst),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifact::ArtifactKind;
+    use crate::caller::CallerContext;
+    use crate::fs_handle::{FsHandle, FsMode};
+    use crate::guards::{GuardSet, SovereignKernelLock};
+    use crate::kernel_lock::GuardianQuorum;
+    use crate::routes::Route;
+
+    fn key() -> SigningKey {
+        SigningKey::from_bytes(&[3; 32])
+    }
+
+    fn board() -> LibertyBoard {
+        LibertyBoard::new(keyring())
+    }
+
+    fn action(suspend: bool, issued_at: u64, epoch: u64) -> LibertyAction {
+        LibertyAction::sign("subject-1", suspend, issued_at, epoch, &key())
+    }
+
+    #[test]
+    fn subject_saves_their_switch_under_a_guardian_quorum() {
+        let stake = format!(
+            "ROW,guardian,g1,scalar,weight,1.0,float,,\n\
+             ROW,guardian,g1,scalar,pubkey,{},string,,\n\
+             ROW,quorum,policy,scalar,threshold,0.5,float,,\n",
+            hex::encode(SigningKey::from_bytes(&[1; 32]).verifying_key().to_bytes())
+        );
+        let guards = GuardSet {
+            kernel_lock: SovereignKernelLock::with_quorum(
+                GuardianQuorum::from_stake_files(&[("family.stake.aln", &stake)]).unwrap(),
+            ),
+            ..GuardSet::default()
+        };
+        let subject = CallerContext::subject("subject-1", Route::Introspect);
+        let record = board().apply(action(true, 10, 1)).unwrap();
+
+        let decision = guards.evaluate(&subject, &record.artifact("/var/neuroxfs"), FsMode::WriteOnly, false);
+        assert!(decision.is_allowed(), "{}", decision.render_text());
+        let config = SovereignArtifact::sovereign_config("/var/neuroxfs/subject-1.sovereign.aln", "subject-1");
+        assert!(!guards.evaluate(&subject, &config, FsMode::WriteOnly, false).is_allowed());
+    }
+
+    #[test]
+    fn actions_apply_once_and_in_epoch_order() {
+        let board = board();
+        let suspend = action(true, 10, 1);
+        board.apply(suspend.clone()).unwrap();
+        assert!(board.is_suspended("subject-1").unwrap());
+        assert!(board.apply(suspend).is_err());
+
+        // A later action that skips an epoch, or reuses one, is refused.
+        assert!(board.apply(action(false, 20, 3)).is_err());
+        assert!(board.apply(action(false, 20, 1)).is_err());
+        board.apply(action(false, 20, 2)).unwrap();
+        assert!(!board.is_suspended("subject-1").unwrap());
+        assert_eq!(board.epoch("subject-1").unwrap(), 2);
+    }
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("neuroxfs-liberty-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    fn keyring() -> SubjectKeyring {
+        let mut keyring = SubjectKeyring::default();
+        keyring.insert("subject-1", key().verifying_key());
+        keyring
+    }
+
+    #[test]
+    fn switch_state_and_epoch_survive_a_restart() {
+        let dir = scratch("restart");
+        let running = LibertyBoard::open(keyring(), &dir).unwrap();
+        let resume = action(false, 10, 1);
+        running.apply(resume.clone()).unwrap();
+        running.apply(action(true, 20, 2)).unwrap();
+        drop(running);
+
+        let fresh = LibertyBoard::open(keyring(), &dir).unwrap();
+        assert!(fresh.is_suspended("subject-1").unwrap());
+        assert_eq!(fresh.epoch("subject-1").unwrap(), 2);
+        // An old but validly signed resume cannot lift the suspend after the restart.
+        assert!(fresh.apply(resume).is_err());
+        assert!(fresh.lease("subject-1").is_err());
+        // The next action continues from the persisted epoch.
+        fresh.apply(action(false, 30, 3)).unwrap();
+        assert_eq!(fresh.lease("subject-1").unwrap().epoch(), 3);
+    }
+
+    #[test]
+    fn forged_switch_files_are_refused() {
+        let dir = scratch("forged");
+        std::fs::create_dir_all(&dir).unwrap();
+        let forged = LibertyRecord {
+            last_action: LibertyAction::sign("subject-1", false, 10, 9, &SigningKey::from_bytes(&[4; 32])),
+        };
+        std::fs::write(dir.join(LibertyRecord::file_name("subject-1")), serde_json::to_vec(&forged).unwrap()).unwrap();
+        assert!(LibertyBoard::open(keyring(), &dir).is_err());
+    }
+
+    #[test]
+    fn no_lease_while_suspended() {
+        let board = board();
+        assert_eq!(board.lease("subject-1").unwrap().epoch(), 0);
+        board.apply(action(true, 10, 1)).unwrap();
+        match board.lease("subject-1").unwrap_err() {
+            FsError::Denied(d) => assert!(d.summary().contains("suspended"), "{}", d.summary()),
+            other => panic!("expected a guard decision, got {}", other),
+        }
+    }
+
+    #[test]
+    fn leased_handles_are_denied_after_a_suspend() {
+        let dir = std::env::temp_dir().join(format!("neuroxfs-liberty-lease-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("notes.json");
+        std::fs::write(&path, "{\"mood\":\"calm\"}").unwrap();
+        let art = SovereignArtifact {
+            kind: ArtifactKind::GenericData,
+            governance_tags: Vec::new(),
+            ..SovereignArtifact::sovereign_config(path.display().to_string(), "subject-1")
+        };
+        let board = board();
+        let subject = CallerContext::subject("subject-1", Route::Introspect);
+
+        let mut handle = FsHandle::open(art, FsMode::ReadOnly, subject, false, &GuardSet::default()).unwrap();
+        handle.bind_lease(board.lease("subject-1").unwrap());
+        board.apply(action(true, 10, 1)).unwrap();
+        match handle.read_all().unwrap_err() {
+            FsError::Denied(d) => assert!(d.summary().contains("cognitive liberty switch"), "{}", d.summary()),
+            other => panic!("expected a guard decision, got {}", other),
+        }
+    }
+}
+
@@ -0,0 +1,79 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use crate::artifact::{NeurorightsProfile, SovereignArtifact};
+use crate::error::FsError;
+use crate::tags::inherit_tags;
+
+/// Derivation edges between artifacts, keyed by path.
+#[derive(Debug, Default)]
+pub struct LineageGraph<'a> {
+    by_path: HashMap<&'a str, &'a SovereignArtifact>,
+    children: HashMap<&'a str, Vec<&'a str>>,
+}
+
+impl<'a> LineageGraph<'a> {
+    pub fn build(artifacts: impl IntoIterator<Item = &'a SovereignArtifact>) -> Self {
+        let mut graph = LineageGraph::default();
+        for art in artifacts {
+            graph.by_path.insert(art.path.as_str(), art);
+            for input in &art.inputs {
+                graph
+                    .children
+                    .entry(input.as_str())
+                    .or_default()
+                    .push(art.path.as_str());
+            }
+        }
+        graph
+    }
+
+    /// Declared profile joined with the effective profile of every ancestor.
+    pub fn effective_profile(&self, path: &str) -> Result<NeurorightsProfile, FsError> {
+        let mut visiting = BTreeSet::new();
+        self.effective_inner(path, &mut visiting)
+    }
+
+    fn effective_inner(&self, path: &str, visiting: &mut BTreeSet<String>) -> Result<NeurorightsProfile, FsError> {
+        let art = self
+            .by_path
+            .get(path)
+            .ok_or_else(|| FsError::PolicyError(format!("lineage: unknown artifact {}", path)))?;
+        if !visiting.insert(path.to_string()) {
+            return Err(FsError::PolicyError(format!("lineage: cycle through {}", path)));
+        }
+        let mut profile = art.neurorights.clone();
+        for input in &art.inputs {
+            profile = profile.union(&self.effective_inner(input, visiting)?);
+        }
+        visiting.remove(path);
+        Ok(profile)
+    }
+
+    /// Every artifact transitively derived from `path`, nearest first.
+    pub fn descendants(&self, path: &str) -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        let mut out = Vec::new();
+        let mut queue: VecDeque<&str> = VecDeque::from([path]);
+        while let Some(p) = queue.pop_front() {
+            for child in self.children.get(p).into_iter().flatten() {
+                if seen.insert(*child) {
+                    out.push(child.to_string());
+                    queue.push_back(child);
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Stamp a derived artifact with its inputs, tainted neurorights and inherited tags.
+pub fn derive(mut art: SovereignArtifact, inputs: &[&SovereignArtifact]) -> SovereignArtifact {
+    for input in inputs {
+        if !art.inputs.contains(&input.path) {
+            art.inputs.push(input.path.clone());
+        }
+        art.neurorights = art.neurorights.union(&input.neurorights);
+    }
+    art.governance_tags = inherit_tags(&art.governance_tags, inputs);
+    art
+}
@@ -0,0 +1,309 @@
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use wasmi::{Config, Engine, Linker, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::artifact::{ArtifactKind, NeurorightsProfile, SovereignArtifact};
+use crate::caller::CallerContext;
+use crate::error::FsError;
+use crate::explain::{CheckKind, DecisionBuilder, GuardDecision, Outcome};
+use crate::fs_handle::FsMode;
+use crate::routes::{Route, RouteAllowance};
+use crate::tags::GovernanceTag;
+
+// Plugin ABI (no imports are provided, so a plugin can only compute):
+//   (export "memory" (memory ..))
+//   (export "alloc" (func (param i32) (result i32)))           ;; buffer for the input
+//   (export "evaluate" (func (param i32 i32) (result i64)))    ;; (ptr << 32) | len of output
+// Input is a JSON `PluginInput`, output a JSON `PluginVerdict`, both UTF-8.
+
+#[derive(Debug, Clone, Copy)]
+pub struct PluginLimits {
+    pub fuel: u64,
+    pub max_memory_bytes: usize,
+    pub max_output_bytes: usize,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self {
+            fuel: 1_000_000,
+            max_memory_bytes: 1 << 20,
+            max_output_bytes: 16 << 10,
+        }
+    }
+}
+
+/// Read-only view of an artifact handed to plugins (a copy, never the descriptor).
+#[derive(Debug, Serialize)]
+pub struct ArtifactView<'a> {
+    pub path: &'a str,
+    pub subject_id: &'a str,
+    pub kind: &'a ArtifactKind,
+    pub routes: &'a [Route],
+    pub route_allowances: &'a [RouteAllowance],
+    pub neurorights: &'a NeurorightsProfile,
+    pub governance_tags: &'a [GovernanceTag],
+    pub roh_before: f32,
+    pub roh_after: f32,
+    pub lifeforce_cost: f32,
+    pub inputs: &'a [String],
+}
+
+impl<'a> From<&'a SovereignArtifact> for ArtifactView<'a> {
+    fn from(art: &'a SovereignArtifact) -> Self {
+        Self {
+            path: &art.path,
+            subject_id: &art.subject_id,
+            kind: &art.kind,
+            routes: &art.routes,
+            route_allowances: &art.route_allowances,
+            neurorights: &art.neurorights,
+            governance_tags: &art.governance_tags,
+            roh_before: art.roh_before,
+            roh_after: art.roh_after,
+            lifeforce_cost: art.lifeforce_cost,
+            inputs: &art.inputs,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PluginInput<'a> {
+    pub mode: FsMode,
+    pub artifact: ArtifactView<'a>,
+    pub caller: &'a CallerContext,
+}
+
+/// What a plugin returns; turned into a standard `GuardDecision`.
+#[derive(Debug, Deserialize)]
+pub struct PluginVerdict {
+    pub outcome: Outcome,
+    pub label: String,
+    pub detail: String,
+}
+
+struct PluginState {
+    limits: StoreLimits,
+}
+
+/// A site-specific guard compiled to WebAssembly, run in a fresh sandbox per call.
+#[derive(Debug, Clone)]
+pub struct WasmGuardPlugin {
+    name: String,
+    engine: Engine,
+    module: Arc<Module>,
+    limits: PluginLimits,
+}
+
+impl WasmGuardPlugin {
+    pub fn load(name: impl Into<String>, wasm: &[u8], limits: PluginLimits) -> Result<Self, FsError> {
+        let name = name.into();
+        let mut config = Config::default();
+        config.consume_fuel(true);
+        let engine = Engine::new(&config);
+        let module = Module::new(&engine, wasm)
+            .map_err(|e| FsError::PolicyError(format!("plugin {}: invalid module: {}", name, e)))?;
+        if let Some(import) = module.imports().next() {
+            return Err(FsError::PolicyError(format!(
+                "plugin {}: imports are not provided ({}::{})",
+                name,
+                import.module(),
+                import.name()
+            )));
+        }
+        for export in ["memory", "alloc", "evaluate"] {
+            if module.get_export(export).is_none() {
+                return Err(FsError::PolicyError(format!("plugin {}: missing export `{}`", name, export)));
+            }
+        }
+        Ok(Self {
+            name,
+            engine,
+            module: Arc::new(module),
+            limits,
+        })
+    }
+
+    pub fn load_file(name: impl Into<String>, path: &str, limits: PluginLimits) -> Result<Self, FsError> {
+        let wasm = std::fs::read(path).map_err(FsError::Io)?;
+        Self::load(name, &wasm, limits)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Run the plugin. Traps, fuel or memory exhaustion and malformed output all deny.
+    pub fn check(&self, caller: &CallerContext, art: &SovereignArtifact, mode: FsMode) -> GuardDecision {
+        let mut d = DecisionBuilder::new(&format!("WasmPlugin:{}", self.name), &format!("{:?}", mode));
+        let input = PluginInput {
+            mode,
+            artifact: ArtifactView::from(art),
+            caller,
+        };
+        match self.run(&input) {
+            Ok(v) => match v.outcome {
+                Outcome::Allow => d.pass(CheckKind::Rule, &v.label, v.detail),
+                Outcome::Redact => d.redact(CheckKind::Rule, &v.label, v.detail),
+                Outcome::Deny => d.fail(CheckKind::Rule, &v.label, v.detail),
+            },
+            Err(fault) => d.fail(CheckKind::Guard, "plugin-fault", fault),
+        }
+        d.finish()
+    }
+
+    fn run(&self, input: &PluginInput<'_>) -> Result<PluginVerdict, String> {
+        let bytes = serde_json::to_vec(input).map_err(|e| format!("input serialization: {}", e))?;
+        let limits = StoreLimitsBuilder::new()
+            .memory_size(self.limits.max_memory_bytes)
+            .instances(1)
+            .memories(1)
+            .trap_on_grow_failure(true)
+            .build();
+        let mut store = Store::new(&self.engine, PluginState { limits });
+        store.limiter(|s| &mut s.limits);
+        store.set_fuel(self.limits.fuel).map_err(|e| e.to_string())?;
+
+        let linker = Linker::<PluginState>::new(&self.engine);
+        let instance = linker
+            .instantiate(&mut store, &self.module)
+            .and_then(|pre| pre.start(&mut store))
+            .map_err(|e| format!("instantiate: {}", e))?;
+        let memory = instance
+            .get_memory(&store, "memory")
+            .ok_or("export `memory` is not a memory")?;
+        let alloc = instance
+            .get_typed_func::<i32, i32>(&store, "alloc")
+            .map_err(|e| format!("alloc: {}", e))?;
+        let evaluate = instance
+            .get_typed_func::<(i32, i32), i64>(&store, "evaluate")
+            .map_err(|e| format!("evaluate: {}", e))?;
+
+        let len = i32::try_from(bytes.len()).map_err(|_| "input too large")?;
+        let ptr = alloc.call(&mut store, len).map_err(|e| format!("alloc trapped: {}", e))?;
+        memory
+            .write(&mut store, ptr as u32 as usize, &bytes)
+            .map_err(|e| format!("input write: {}", e))?;
+        let packed = evaluate
+            .call(&mut store, (ptr, len))
+            .map_err(|e| format!("evaluate trapped: {}", e))? as u64;
+
+        let (out_ptr, out_len) = ((packed >> 32) as usize, (packed & 0xffff_ffff) as usize);
+        if out_len > self.limits.max_output_bytes {
+            return Err(format!("output of {} bytes exceeds limit", out_len));
+        }
+        let mut out = vec![0u8; out_len];
+        memory
+            .read(&store, out_ptr, &mut out)
+            .map_err(|e| format!("output read: {}", e))?;
+        serde_json::from_slice(&out).map_err(|e| format!("malformed verdict: {}", e))
+    }
+}
+
+/// Test plugins built from WebAssembly text.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use super::*;
+
+    pub(crate) fn from_wat(name: &str, wat: &str) -> WasmGuardPlugin {
+        WasmGuardPlugin::load(name, &wat::parse_str(wat).unwrap(), PluginLimits::default()).unwrap()
+    }
+
+    /// A plugin that answers every input with the `PluginVerdict` JSON `verdict`.
+    pub(crate) fn fixed_verdict(name: &str, verdict: &str) -> WasmGuardPlugin {
+        let escaped = verdict.replace('\\', "\\\\").replace('"', "\\\"");
+        from_wat(
+            name,
+            &format!(
+                r#"(module
+                    (memory (export "memory") 1)
+                    (data (i32.const 0) "{escaped}")
+                    (func (export "alloc") (param i32) (result i32) i32.const 4096)
+                    (func (export "evaluate") (param i32 i32) (result i64) i64.const {len}))"#,
+                len = verdict.len()
+            ),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caller::CallerContext;
+    use crate::grants::GrantOperation;
+
+    fn notes() -> SovereignArtifact {
+        SovereignArtifact::sovereign_config("subject-1.notes", "subject-1")
+    }
+
+    fn check(plugin: &WasmGuardPlugin) -> GuardDecision {
+        plugin.check(&CallerContext::subject("subject-1", Route::Introspect), &notes(), FsMode::ReadOnly)
+    }
+
+    /// Exports the ABI around an `evaluate` body that never returns normally.
+    fn faulting(body: &str) -> String {
+        format!(
+            r#"(module
+                (memory (export "memory") 1)
+                (func (export "alloc") (param i32) (result i32) i32.const 0)
+                (func (export "evaluate") (param i32 i32) (result i64) {body}))"#
+        )
+    }
+
+    fn assert_fault(decision: &GuardDecision, needle: &str) {
+        assert_eq!(decision.outcome, Outcome::Deny);
+        let text = decision.render_text();
+        assert!(text.contains("plugin-fault") && text.contains(needle), "{}", text);
+    }
+
+    #[test]
+    fn verdicts_become_guard_decisions() {
+        let allow = fixtures::fixed_verdict("site", r#"{"outcome":"Allow","label":"site-policy","detail":"ok"}"#);
+        assert_eq!(check(&allow).outcome, Outcome::Allow);
+        let redact = fixtures::fixed_verdict("site", r#"{"outcome":"Redact","label":"site-policy","detail":"mask"}"#);
+        assert_eq!(check(&redact).outcome, Outcome::Redact);
+        let garbage = fixtures::fixed_verdict("site", "not json");
+        assert_fault(&check(&garbage), "malformed verdict");
+    }
+
+    #[test]
+    fn running_out_of_fuel_denies() {
+        let spin = fixtures::from_wat("spin", &faulting("(loop $l (br $l)) unreachable"));
+        assert_fault(&check(&spin), "evaluate trapped");
+    }
+
+    #[test]
+    fn exceeding_the_memory_limit_denies() {
+        // 1 MiB limit = 16 pages; the module starts at one and asks for 32 more.
+        let grow = fixtures::from_wat("grow", &faulting("(drop (memory.grow (i32.const 32))) i64.const 0"));
+        assert_fault(&check(&grow), "evaluate trapped");
+
+        let big = r#"(module
+            (memory (export "memory") 64)
+            (func (export "alloc") (param i32) (result i32) i32.const 0)
+            (func (export "evaluate") (param i32 i32) (result i64) i64.const 0))"#;
+        assert_fault(&check(&fixtures::from_wat("big", big)), "instantiate");
+    }
+
+    #[test]
+    fn traps_deny() {
+        let trap = fixtures::from_wat("trap", &faulting("unreachable"));
+        assert_fault(&check(&trap), "evaluate trapped");
+    }
+
+    #[test]
+    fn imports_are_refused_at_load() {
+        let wat = r#"(module (import "env" "now" (func)) (memory (export "memory") 1))"#;
+        let err = WasmGuardPlugin::load("clock", &wat::parse_str(wat).unwrap(), PluginLimits::default()).unwrap_err();
+        assert!(err.to_string().contains("imports are not provided"), "{}", err);
+    }
+
+    #[test]
+    fn plugins_see_route_allowances() {
+        let json = serde_json::to_value(ArtifactView::from(&notes())).unwrap();
+        assert_eq!(json["route_allowances"][0]["route"], "INTROSPECT");
+        assert_eq!(json["route_allowances"][0]["operations"], serde_json::json!(["Read", "Write"]));
+        assert_eq!(notes().route_allowances[0].operations, [GrantOperation::Read, GrantOperation::Write]);
+    }
+}
@@ -0,0 +1 @@
+//! Placeholder for Tsafe / RoH policy integration; no policy sources are wired in yet.
@@ -0,0 +1,203 @@
+use std::collections::HashMap;
+
+use crate::agent_adapter::ArtifactResolver;
+use crate::artifact::SovereignArtifact;
+use crate::error::FsError;
+use crate::lineage::{derive, LineageGraph};
+use crate::tags::validate_tags;
+
+/// In-memory manifest: (subject_id, artifact_id) -> artifact, one artifact per path.
+#[derive(Debug, Clone, Default)]
+pub struct ArtifactRegistry {
+    artifacts: HashMap<(String, String), SovereignArtifact>,
+    paths: HashMap<String, (String, String)>,
+}
+
+impl ArtifactRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Artifacts are stored as declared. Their lineage taint is applied on
+    /// every lookup, so re-registering an ancestor with a stricter profile
+    /// reaches every descendant. Registration fails, and changes nothing, if
+    /// the path already belongs to another artifact, or if the artifact or any
+    /// descendant would end up invalid.
+    pub fn register(&mut self, artifact_id: &str, art: SovereignArtifact) -> Result<(), FsError> {
+        validate_tags(&art)?;
+        let path = art.path.clone();
+        let key = (art.subject_id.clone(), artifact_id.to_string());
+        if let Some(owner) = self.paths.get(&path) {
+            if *owner != key {
+                return Err(FsError::PolicyError(format!(
+                    "{} is already registered as {} for {}",
+                    path, owner.1, owner.0
+                )));
+            }
+        }
+        let previous = self.artifacts.insert(key.clone(), art);
+        if let Some(prev) = &previous {
+            self.paths.remove(&prev.path);
+        }
+        self.paths.insert(path.clone(), key.clone());
+        let checked = std::iter::once(path.clone())
+            .chain(self.descendants(&path))
+            .try_for_each(|p| self.by_path(&p).and_then(|a| validate_tags(&a)));
+        if let Err(e) = checked {
+            self.paths.remove(&path);
+            match previous {
+                Some(prev) => {
+                    self.paths.insert(prev.path.clone(), key.clone());
+                    self.artifacts.insert(key, prev);
+                }
+                None => {
+                    self.artifacts.remove(&key);
+                }
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    /// The artifact as registered, before lineage taint. Not for guards.
+    pub fn declared(&self, path: &str) -> Option<&SovereignArtifact> {
+        self.paths.get(path).and_then(|key| self.artifacts.get(key))
+    }
+
+    /// The artifact as guards must see it: its neurorights joined with every
+    /// ancestor's, and the inheritable tags of its inputs.
+    pub fn by_path(&self, path: &str) -> Result<SovereignArtifact, FsError> {
+        let art = self
+            .declared(path)
+            .ok_or_else(|| FsError::PolicyError(format!("lineage: unknown artifact {}", path)))?;
+        if art.inputs.is_empty() {
+            return Ok(art.clone());
+        }
+        let graph = LineageGraph::build(self.artifacts.values());
+        let profile = graph.effective_profile(path)?;
+        let inputs = art
+            .inputs
+            .iter()
+            .map(|p| self.by_path(p))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut derived = derive(art.clone(), &inputs.iter().collect::<Vec<_>>());
+        derived.neurorights = profile;
+        Ok(derived)
+    }
+
+    /// Everything derived from `path`, for cascading revocation or erasure.
+    pub fn descendants(&self, path: &str) -> Vec<String> {
+        LineageGraph::build(self.artifacts.values()).descendants(path)
+    }
+
+    /// Register from manifest JSON; unknown governance tags fail here.
+    pub fn register_json(&mut self, artifact_id: &str, json: &str) -> Result<(), FsError> {
+        let art: SovereignArtifact = serde_json::from_str(json)
+            .map_err(|e| FsError::PolicyError(format!("artifact {}: {}", artifact_id, e)))?;
+        self.register(artifact_id, art)
+    }
+
+    /// Every registered artifact, tainted, in path order.
+    pub fn artifacts(&self) -> Result<Vec<SovereignArtifact>, FsError> {
+        let mut paths: Vec<&String> = self.paths.keys().collect();
+        paths.sort();
+        paths.into_iter().map(|p| self.by_path(p)).collect()
+    }
+}
+
+impl ArtifactResolver for ArtifactRegistry {
+    fn resolve(&self, subject_id: &str, artifact_id: &str) -> Result<SovereignArtifact, FsError> {
+        let art = self
+            .artifacts
+            .get(&(subject_id.to_string(), artifact_id.to_string()))
+            .ok_or_else(|| FsError::PolicyError(format!("unknown artifact {} for {}", artifact_id, subject_id)))?;
+        self.by_path(&art.path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::artifact::{ArtifactKind, NeurorightsProfile};
+    use crate::tags::GovernanceTag;
+
+    fn artifact(path: &str, inputs: &[&str]) -> SovereignArtifact {
+        let mut art = SovereignArtifact::sovereign_config(path, "subject-1");
+        art.kind = ArtifactKind::GenericData;
+        art.neurorights = NeurorightsProfile::default();
+        art.governance_tags.clear();
+        art.inputs = inputs.iter().map(|p| p.to_string()).collect();
+        art
+    }
+
+    /// raw -> features -> model
+    fn chain() -> ArtifactRegistry {
+        let mut registry = ArtifactRegistry::new();
+        registry.register("raw", artifact("raw.neuroaln", &[])).unwrap();
+        registry.register("features", artifact("features.dat", &["raw.neuroaln"])).unwrap();
+        registry.register("model", artifact("model.nnetw", &["features.dat"])).unwrap();
+        registry
+    }
+
+    #[test]
+    fn stricter_ancestors_reach_registered_descendants() {
+        let mut registry = chain();
+        assert!(!registry.resolve("subject-1", "model").unwrap().neurorights.dreamstate_sensitive);
+
+        let mut raw = artifact("raw.neuroaln", &[]);
+        raw.neurorights.dreamstate_sensitive = true;
+        raw.neurorights.forbid_decision_use = true;
+        raw.governance_tags = vec![GovernanceTag::Smart];
+        registry.register("raw", raw).unwrap();
+
+        for id in ["features", "model"] {
+            let art = registry.resolve("subject-1", id).unwrap();
+            assert!(art.neurorights.dreamstate_sensitive && art.neurorights.forbid_decision_use, "{}", id);
+            assert_eq!(art.governance_tags, vec![GovernanceTag::Smart], "{}", id);
+        }
+        // Path lookups see the taint too; the declared profile is kept, so
+        // relaxing the ancestor relaxes its descendants again.
+        assert!(registry.by_path("model.nnetw").unwrap().neurorights.dreamstate_sensitive);
+        assert!(registry.artifacts().unwrap().iter().all(|a| a.neurorights.dreamstate_sensitive));
+        assert!(!registry.declared("model.nnetw").unwrap().neurorights.dreamstate_sensitive);
+        registry.register("raw", artifact("raw.neuroaln", &[])).unwrap();
+        assert!(!registry.resolve("subject-1", "model").unwrap().neurorights.dreamstate_sensitive);
+    }
+
+    #[test]
+    fn registration_that_would_invalidate_a_descendant_is_refused() {
+        let mut registry = chain();
+        let mut model = artifact("model.nnetw", &["features.dat"]);
+        model.governance_tags = vec![GovernanceTag::Export];
+        registry.register("model", model).unwrap();
+
+        let mut raw = artifact("raw.neuroaln", &[]);
+        raw.neurorights.soul_non_tradeable = true;
+        let err = registry.register("raw", raw).unwrap_err();
+        assert!(err.to_string().contains("model.nnetw"), "{}", err);
+        assert!(!registry.by_path("raw.neuroaln").unwrap().neurorights.soul_non_tradeable);
+        assert!(registry.by_path("model.nnetw").is_ok());
+    }
+
+    #[test]
+    fn unknown_inputs_are_refused() {
+        let mut registry = ArtifactRegistry::new();
+        assert!(registry.register("features", artifact("features.dat", &["raw.neuroaln"])).is_err());
+        assert!(registry.by_path("features.dat").is_err());
+        assert!(registry.declared("features.dat").is_none());
+    }
+
+    #[test]
+    fn a_path_belongs_to_one_artifact() {
+        let mut registry = chain();
+        let err = registry.register("raw-copy", artifact("raw.neuroaln", &[])).unwrap_err();
+        assert!(err.to_string().contains("already registered as raw"), "{}", err);
+        assert!(registry.resolve("subject-1", "raw-copy").is_err());
+
+        // Moving an artifact to a new path frees the old one.
+        registry.register("model", artifact("model.v2.nnetw", &["features.dat"])).unwrap();
+        assert!(registry.declared("model.nnetw").is_none());
+        registry.register("old-model", artifact("model.nnetw", &[])).unwrap();
+        assert_eq!(registry.artifacts().unwrap().len(), 4);
+    }
+}
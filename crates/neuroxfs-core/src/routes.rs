@@ -0,0 +1,36 @@
+use serde::{Deserialize, Serialize};
+
+use crate::artifact::SovereignArtifact;
+use crate::grants::GrantOperation;
+
+/// Channel a request arrived on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum Route {
+    Chat,
+    Bci,
+    Ota,
+    Introspect,
+    BreakGlass,
+}
+
+/// Operations an artifact permits on one route.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RouteAllowance {
+    pub route: Route,
+    pub operations: Vec<GrantOperation>,
+}
+
+/// Operations `art` permits on `route`.
+///
+/// No declared routes means nothing is reachable. A declared route without an
+/// explicit allowance is read-only.
+pub fn allowed_operations(art: &SovereignArtifact, route: Route) -> Vec<GrantOperation> {
+    if !art.routes.contains(&route) {
+        return Vec::new();
+    }
+    match art.route_allowances.iter().find(|a| a.route == route) {
+        Some(allowance) => allowance.operations.clone(),
+        None => vec![GrantOperation::Read],
+    }
+}
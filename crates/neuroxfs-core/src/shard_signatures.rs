@@ -0,0 +1,144 @@
+use std::path::Path;
+
+use aln_core::canonical::{sig_path, ShardSignature, SignaturePolicy};
+
+use crate::artifact::SovereignArtifact;
+use crate::explain::{CheckKind, DecisionBuilder, GuardDecision};
+use crate::fs_handle::FsMode;
+
+/// Refuses to hand out `.aln` shards whose `.aln.sig` is missing or does not
+/// verify, when the workspace requires signatures.
+///
+/// Without a fixed policy, each shard is checked against the nearest
+/// `aln-workspace.aln` above it; with none, signatures are optional.
+#[derive(Debug, Clone, Default)]
+pub struct ShardSignatureGuard {
+    policy: Option<SignaturePolicy>,
+}
+
+impl ShardSignatureGuard {
+    pub fn with_policy(policy: SignaturePolicy) -> Self {
+        Self { policy: Some(policy) }
+    }
+
+    pub fn check(&self, art: &SovereignArtifact, mode: FsMode) -> GuardDecision {
+        let mut d = DecisionBuilder::new("ShardSignatureGuard", &format!("{:?}", mode));
+        let path = Path::new(&art.path);
+        if path.extension().and_then(|e| e.to_str()) != Some("aln") {
+            d.skip(CheckKind::Rule, "shard-signature", "not an .aln shard");
+            return d.finish();
+        }
+        // Writes invalidate the signature anyway; a shard is checked when it is read.
+        if matches!(mode, FsMode::WriteOnly) || !path.exists() {
+            d.skip(CheckKind::Rule, "shard-signature", "nothing to verify before a write");
+            return d.finish();
+        }
+
+        let discovered;
+        let policy = match &self.policy {
+            Some(policy) => policy,
+            None => match SignaturePolicy::discover(path) {
+                Ok(policy) => {
+                    discovered = policy;
+                    &discovered
+                }
+                Err(e) => {
+                    d.fail(CheckKind::Rule, "shard-signature", format!("workspace policy: {}", e));
+                    return d.finish();
+                }
+            },
+        };
+        let loaded = aln_core::migrate::load_checked(path).and_then(|shard| {
+            let sig_file = sig_path(path);
+            let sig = if sig_file.exists() { Some(ShardSignature::load(&sig_file)?) } else { None };
+            Ok((shard, sig))
+        });
+        let (shard, sig) = match loaded {
+            Ok(loaded) => loaded,
+            Err(e) => {
+                d.fail(CheckKind::Rule, "shard-signature", e.to_string());
+                return d.finish();
+            }
+        };
+        match (policy.check(&shard, sig.as_ref()), &sig) {
+            (Err(e), _) => d.fail(CheckKind::Rule, "shard-signature", e),
+            (Ok(()), Some(sig)) => d.pass(
+                CheckKind::Rule,
+                "shard-signature",
+                format!("signed by {} (sha256 {})", sig.signer, sig.content_hash),
+            ),
+            (Ok(()), None) => d.skip(CheckKind::Rule, "shard-signature", "unsigned; workspace does not require signatures"),
+        }
+        d.finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+
+    const SHARD: &str = "\
+destination-path,demo.v1.aln
+ROW,derived,session,scalar,rmax,0.35,float,range0,1,Psych-risk ceiling
+FOOTER,END-OF-SHARD
+";
+
+    fn shard_file(name: &str, text: &str) -> SovereignArtifact {
+        let dir = std::env::temp_dir().join(format!("neuroxfs-shard-sig-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("shard.aln");
+        std::fs::write(&path, text).unwrap();
+        SovereignArtifact::sovereign_config(path.display().to_string(), "subject-1")
+    }
+
+    fn guard() -> ShardSignatureGuard {
+        let mut policy = SignaturePolicy::default().trust("alice", SigningKey::from_bytes(&[1; 32]).verifying_key());
+        policy.required = true;
+        ShardSignatureGuard::with_policy(policy)
+    }
+
+    fn sign(art: &SovereignArtifact, signer: &str, seed: u8) {
+        let path = Path::new(&art.path);
+        let shard = aln_core::parse_file(path).unwrap();
+        ShardSignature::sign(&shard, signer, &SigningKey::from_bytes(&[seed; 32]))
+            .store(&sig_path(path))
+            .unwrap();
+    }
+
+    #[test]
+    fn signed_shard_is_readable() {
+        let art = shard_file("signed", SHARD);
+        sign(&art, "alice", 1);
+        assert!(guard().check(&art, FsMode::ReadOnly).is_allowed());
+    }
+
+    #[test]
+    fn missing_or_bad_signature_denies_reads() {
+        let art = shard_file("missing", SHARD);
+        let decision = guard().check(&art, FsMode::ReadOnly);
+        assert!(!decision.is_allowed());
+        assert!(decision.summary().contains("unsigned shard"), "{}", decision.summary());
+
+        let art = shard_file("untrusted", SHARD);
+        sign(&art, "alice", 2);
+        assert!(!guard().check(&art, FsMode::ReadWrite).is_allowed());
+
+        let art = shard_file("garbled", SHARD);
+        std::fs::write(sig_path(Path::new(&art.path)), "not a signature").unwrap();
+        assert!(!guard().check(&art, FsMode::ReadOnly).is_allowed());
+
+        // Writes are checked on the next read, not before.
+        assert!(guard().check(&art, FsMode::WriteOnly).is_allowed());
+    }
+
+    #[test]
+    fn outdated_shard_is_denied_even_when_signed() {
+        let art = shard_file("outdated", &SHARD.replace("demo.v1.aln", "quantum-roaming-debug.v1.aln"));
+        sign(&art, "alice", 1);
+        let decision = guard().check(&art, FsMode::ReadOnly);
+        assert!(!decision.is_allowed());
+        assert!(decision.summary().contains("aln migrate"), "{}", decision.summary());
+    }
+}
@@ -0,0 +1,355 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::artifact::{ArtifactKind, SovereignArtifact};
+use crate::caller::CallerContext;
+use crate::explain::{CheckKind, DecisionBuilder, GuardDecision, Outcome};
+use crate::fs_handle::FsMode;
+use crate::guards::GuardSet;
+use crate::routes::Route;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SimOperation {
+    Read,
+    Write,
+    /// Agent summary read; allowed summaries count as redacted access.
+    Summary,
+}
+
+/// Who, over which routes and with which operations, to try against every artifact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimulationSpace {
+    pub principals: Vec<CallerContext>, // the route field is overridden per row
+    pub routes: Vec<Route>,
+    pub operations: Vec<SimOperation>,
+    pub via_evolve_token: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct CellKey {
+    pub artifact_path: String,
+    pub principal: String,
+    pub role: String,
+    pub purpose: String,
+    pub route: Route,
+    pub op: SimOperation,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MatrixCell {
+    pub key: CellKey,
+    pub outcome: Outcome,
+    pub summary: String,
+    pub sensitive: bool, // mental_privacy or dreamstate_sensitive
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DecisionMatrix {
+    pub cells: Vec<MatrixCell>,
+}
+
+impl DecisionMatrix {
+    pub fn count(&self, outcome: Outcome) -> usize {
+        self.cells.iter().filter(|c| c.outcome == outcome).count()
+    }
+
+    fn by_key(&self) -> BTreeMap<&CellKey, &MatrixCell> {
+        self.cells.iter().map(|c| (&c.key, c)).collect()
+    }
+
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        for c in &self.cells {
+            out.push_str(&format!(
+                "{:<6?} {} {} ({}/{}) via {:?} {:?} -- {}\n",
+                c.outcome,
+                c.key.artifact_path,
+                c.key.principal,
+                c.key.role,
+                c.key.purpose,
+                c.key.route,
+                c.key.op,
+                c.summary
+            ));
+        }
+        out
+    }
+}
+
+/// Dry-run evaluator: runs the guards without opening any file.
+pub struct PolicySimulator<'a> {
+    guards: &'a GuardSet,
+}
+
+impl<'a> PolicySimulator<'a> {
+    pub fn new(guards: &'a GuardSet) -> Self {
+        Self { guards }
+    }
+
+    pub fn evaluate(
+        &self,
+        caller: &CallerContext,
+        art: &SovereignArtifact,
+        op: SimOperation,
+        via_evolve_token: bool,
+    ) -> GuardDecision {
+        match op {
+            SimOperation::Read => self.guards.evaluate(caller, art, FsMode::ReadOnly, via_evolve_token),
+            SimOperation::Write => self.guards.evaluate(caller, art, FsMode::ReadWrite, via_evolve_token),
+            SimOperation::Summary => {
+                // Mirrors NeuroxfsAgentAdapter's ReadSummary path.
+                let mut d = DecisionBuilder::new("PolicySimulator", "summary");
+                if matches!(art.kind, ArtifactKind::SovereignConfig | ArtifactKind::BChainProof) {
+                    d.fail(
+                        CheckKind::Rule,
+                        "agent-kernel-read",
+                        "Agent cannot read sovereign-config or proof artifacts",
+                    );
+                    return d.finish();
+                }
+                d.include(self.guards.evaluate(caller, art, FsMode::ReadOnly, via_evolve_token));
+                if !d.is_denied() {
+                    d.redact(CheckKind::Rule, "summary-only", "agent receives a summary, not raw content");
+                }
+                d.finish()
+            }
+        }
+    }
+
+    pub fn run(&self, workspace: &[SovereignArtifact], space: &SimulationSpace) -> DecisionMatrix {
+        let mut cells = Vec::new();
+        for art in workspace {
+            let sensitive = art.neurorights.mental_privacy || art.neurorights.dreamstate_sensitive;
+            for principal in &space.principals {
+                for route in &space.routes {
+                    let caller = principal.clone().with_route(*route);
+                    for &op in &space.operations {
+                        let decision = self.evaluate(&caller, art, op, space.via_evolve_token);
+                        cells.push(MatrixCell {
+                            key: cell_key(art, &caller, op),
+                            outcome: decision.outcome,
+                            summary: decision.summary(),
+                            sensitive,
+                        });
+                    }
+                }
+            }
+        }
+        DecisionMatrix { cells }
+    }
+
+    /// Run the same space under two guard configurations and diff them.
+    pub fn compare(
+        before: &GuardSet,
+        after: &GuardSet,
+        workspace: &[SovereignArtifact],
+        space: &SimulationSpace,
+    ) -> PolicyDiff {
+        let old = PolicySimulator::new(before).run(workspace, space);
+        let new = PolicySimulator::new(after).run(workspace, space);
+        PolicyDiff::between(&old, &new)
+    }
+}
+
+fn cell_key(art: &SovereignArtifact, caller: &CallerContext, op: SimOperation) -> CellKey {
+    CellKey {
+        artifact_path: art.path.clone(),
+        principal: caller.principal().to_string(),
+        role: format!("{:?}", caller.role()),
+        purpose: format!("{:?}", caller.purpose()),
+        route: caller.route(),
+        op,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CellChange {
+    pub key: CellKey,
+    pub before: Option<Outcome>,
+    pub after: Option<Outcome>,
+    pub sensitive: bool,
+}
+
+/// How much of an artifact an outcome exposes; a denied or absent cell exposes nothing.
+fn exposure(outcome: Option<Outcome>) -> u8 {
+    match outcome {
+        None | Some(Outcome::Deny) => 0,
+        Some(Outcome::Redact) => 1,
+        Some(Outcome::Allow) => 2,
+    }
+}
+
+impl CellChange {
+    /// Mental-privacy / dream data that is now more exposed than before:
+    /// newly reachable, or full bytes where only a redacted view was.
+    pub fn newly_allows_sensitive(&self) -> bool {
+        self.sensitive && exposure(self.after) > exposure(self.before)
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PolicyDiff {
+    pub changes: Vec<CellChange>,
+}
+
+impl PolicyDiff {
+    pub fn between(before: &DecisionMatrix, after: &DecisionMatrix) -> Self {
+        let old = before.by_key();
+        let new = after.by_key();
+        let mut keys: Vec<&CellKey> = old.keys().chain(new.keys()).copied().collect();
+        keys.sort();
+        keys.dedup();
+
+        let changes = keys
+            .into_iter()
+            .filter_map(|k| {
+                let b = old.get(k);
+                let a = new.get(k);
+                let before = b.map(|c| c.outcome);
+                let after = a.map(|c| c.outcome);
+                if before == after {
+                    return None;
+                }
+                Some(CellChange {
+                    key: k.clone(),
+                    before,
+                    after,
+                    sensitive: b.or(a).is_some_and(|c| c.sensitive),
+                })
+            })
+            .collect();
+        Self { changes }
+    }
+
+    pub fn newly_allowed_sensitive(&self) -> impl Iterator<Item = &CellChange> {
+        self.changes.iter().filter(|c| c.newly_allows_sensitive())
+    }
+
+    pub fn render_text(&self) -> String {
+        let mut out = String::new();
+        for c in &self.changes {
+            out.push_str(&format!(
+                "{} {} {} ({}/{}) via {:?} {:?}: {:?} -> {:?}\n",
+                if c.newly_allows_sensitive() { "!!" } else { "  " },
+                c.key.artifact_path,
+                c.key.principal,
+                c.key.role,
+                c.key.purpose,
+                c.key.route,
+                c.key.op,
+                c.before,
+                c.after
+            ));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::caller::{Purpose, Role};
+    use crate::grants::{ArtifactSelector, DelegationGrant, GrantBook, GrantOperation, GrantRegistry, SubjectKeyring};
+    use crate::guards::AuraBoundaryGuard;
+    use ed25519_dalek::SigningKey;
+
+    /// Private subject notes, readable over CHAT.
+    fn notes() -> SovereignArtifact {
+        SovereignArtifact {
+            kind: ArtifactKind::GenericData,
+            routes: vec![Route::Chat],
+            route_allowances: Vec::new(),
+            ..SovereignArtifact::sovereign_config("subject-1.notes", "subject-1")
+        }
+    }
+
+    fn space() -> SimulationSpace {
+        SimulationSpace {
+            principals: vec![
+                CallerContext::subject("subject-1", Route::Chat),
+                CallerContext::new("lab", Role::Researcher, Purpose::Research, Route::Chat).unwrap(),
+            ],
+            routes: vec![Route::Chat, Route::Bci],
+            operations: vec![SimOperation::Read, SimOperation::Write],
+            via_evolve_token: false,
+        }
+    }
+
+    fn granting_lab() -> GuardSet {
+        let key = SigningKey::from_bytes(&[8; 32]);
+        let mut keyring = SubjectKeyring::default();
+        keyring.insert("subject-1", key.verifying_key());
+        let mut book = GrantBook::new("subject-1");
+        book.issue(
+            DelegationGrant {
+                grant_id: "study".into(),
+                issuer_subject: "subject-1".into(),
+                grantee: "lab".into(),
+                selector: ArtifactSelector { kinds: Vec::new(), path_prefix: None },
+                operations: vec![GrantOperation::Read],
+                purpose: Purpose::Research,
+                issued_at: 0,
+                expires_at: u64::MAX,
+                revocable: true,
+            },
+            &key,
+        )
+        .unwrap();
+        let mut grants = GrantRegistry::new(keyring);
+        grants.load_book(&book).unwrap();
+        GuardSet {
+            aura: AuraBoundaryGuard::new(grants),
+            ..GuardSet::default()
+        }
+    }
+
+    #[test]
+    fn matrix_covers_every_combination() {
+        let guards = GuardSet::default();
+        let matrix = PolicySimulator::new(&guards).run(&[notes()], &space());
+        assert_eq!(matrix.cells.len(), 2 * 2 * 2);
+        // Only the subject's own CHAT read gets through: no writes on CHAT, no BCI route.
+        assert_eq!(matrix.count(Outcome::Allow), 1);
+        let allowed = matrix.cells.iter().find(|c| c.outcome == Outcome::Allow).unwrap();
+        assert_eq!((allowed.key.principal.as_str(), allowed.key.route, allowed.key.op), ("subject-1", Route::Chat, SimOperation::Read));
+        assert!(matrix.cells.iter().all(|c| c.sensitive));
+    }
+
+    #[test]
+    fn summaries_are_redacted_and_never_cover_sovereign_configs() {
+        let guards = GuardSet::default();
+        let sim = PolicySimulator::new(&guards);
+        let subject = CallerContext::subject("subject-1", Route::Chat);
+        assert_eq!(sim.evaluate(&subject, &notes(), SimOperation::Summary, false).outcome, Outcome::Redact);
+        let mut config = notes();
+        config.kind = ArtifactKind::SovereignConfig;
+        let decision = sim.evaluate(&subject, &config, SimOperation::Summary, false);
+        assert_eq!(decision.outcome, Outcome::Deny);
+        assert!(decision.summary().contains("sovereign-config"), "{}", decision.summary());
+    }
+
+    #[test]
+    fn diff_flags_a_grant_that_opens_private_data() {
+        let diff = PolicySimulator::compare(&GuardSet::default(), &granting_lab(), &[notes()], &space());
+        let flagged: Vec<_> = diff.newly_allowed_sensitive().collect();
+        assert_eq!(flagged.len(), 1);
+        assert_eq!(flagged[0].key.principal, "lab");
+        assert_eq!((flagged[0].before, flagged[0].after), (Some(Outcome::Deny), Some(Outcome::Allow)));
+        assert!(diff.render_text().starts_with("!! "), "{}", diff.render_text());
+        assert!(PolicySimulator::compare(&granting_lab(), &GuardSet::default(), &[notes()], &space())
+            .newly_allowed_sensitive()
+            .next()
+            .is_none());
+    }
+
+    #[test]
+    fn redact_to_allow_on_sensitive_data_is_flagged() {
+        let key = cell_key(&notes(), &CallerContext::subject("subject-1", Route::Chat), SimOperation::Read);
+        let change = |before, after, sensitive| CellChange { key: key.clone(), before, after, sensitive };
+        assert!(change(Some(Outcome::Redact), Some(Outcome::Allow), true).newly_allows_sensitive());
+        assert!(change(None, Some(Outcome::Redact), true).newly_allows_sensitive());
+        assert!(!change(Some(Outcome::Allow), Some(Outcome::Redact), true).newly_allows_sensitive());
+        assert!(!change(Some(Outcome::Redact), Some(Outcome::Allow), false).newly_allows_sensitive());
+    }
+}
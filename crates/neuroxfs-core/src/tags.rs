@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::artifact::{ArtifactKind, SovereignArtifact};
+use crate::clock::unix_now;
+use crate::error::FsError;
+use crate::explain::{CheckKind, DecisionBuilder, GuardDecision};
+use crate::fs_handle::FsMode;
+
+/// Governance vocabulary; anything else is rejected at registration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum GovernanceTag {
+    /// Changes go through the EVOLVE token path.
+    Evolve,
+    /// Access only under an active SmartScope.
+    Smart,
+    /// Artifact is bound for export; export pipeline checks apply.
+    Export,
+}
+
+impl GovernanceTag {
+    pub const ALL: [GovernanceTag; 3] = [GovernanceTag::Evolve, GovernanceTag::Smart, GovernanceTag::Export];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GovernanceTag::Evolve => "EVOLVE",
+            GovernanceTag::Smart => "SMART",
+            GovernanceTag::Export => "EXPORT",
+        }
+    }
+
+    /// Whether artifacts derived from a tagged input carry the tag too.
+    /// Export consent is per artifact and never propagates.
+    pub fn inherited(self) -> bool {
+        match self {
+            GovernanceTag::Evolve | GovernanceTag::Smart => true,
+            GovernanceTag::Export => false,
+        }
+    }
+}
+
+impl fmt::Display for GovernanceTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl TryFrom<String> for GovernanceTag {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        GovernanceTag::ALL
+            .into_iter()
+            .find(|t| t.as_str() == s)
+            .ok_or_else(|| format!("unknown governance tag `{}`", s))
+    }
+}
+
+impl From<GovernanceTag> for String {
+    fn from(tag: GovernanceTag) -> Self {
+        tag.as_str().to_string()
+    }
+}
+
+/// Registration-time checks beyond what the type already guarantees.
+pub fn validate_tags(art: &SovereignArtifact) -> Result<(), FsError> {
+    let mut seen = Vec::new();
+    for tag in &art.governance_tags {
+        if seen.contains(tag) {
+            return Err(FsError::PolicyError(format!("{}: duplicate tag {}", art.path, tag)));
+        }
+        seen.push(*tag);
+    }
+    if seen.contains(&GovernanceTag::Export) && art.neurorights.soul_non_tradeable {
+        return Err(FsError::PolicyError(format!(
+            "{}: soul-non-tradeable artifact cannot carry EXPORT",
+            art.path
+        )));
+    }
+    Ok(())
+}
+
+/// Tags a derived artifact starts with: its own plus every inheritable input tag.
+pub fn inherit_tags(own: &[GovernanceTag], inputs: &[&SovereignArtifact]) -> Vec<GovernanceTag> {
+    let mut tags: Vec<GovernanceTag> = own.to_vec();
+    for input in inputs {
+        tags.extend(input.governance_tags.iter().copied().filter(|t| t.inherited()));
+    }
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+/// Session-level permission for SMART-tagged artifacts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmartScope {
+    pub subject_id: String,
+    pub domains: Vec<String>,
+    pub maxeffectsizel2: f32,
+    pub expires_at: u64,
+    pub physioguard_enabled: bool,
+    pub revoked: bool,
+}
+
+impl SmartScope {
+    pub fn is_active(&self, now: u64) -> bool {
+        !self.revoked && now < self.expires_at
+    }
+}
+
+/// Runs the behaviour bound to each tag on an artifact.
+#[derive(Debug, Clone, Default)]
+pub struct TagGuard {
+    smart_scopes: HashMap<String, SmartScope>,
+}
+
+impl TagGuard {
+    pub fn set_smart_scope(&mut self, scope: SmartScope) {
+        self.smart_scopes.insert(scope.subject_id.clone(), scope);
+    }
+
+    pub fn check(&self, art: &SovereignArtifact, mode: FsMode, via_evolve_token: bool) -> GuardDecision {
+        let mut d = DecisionBuilder::new("TagGuard", &format!("{:?}", mode));
+        if art.governance_tags.is_empty() {
+            d.skip(CheckKind::Rule, "governance-tags", "artifact carries no governance tags");
+            return d.finish();
+        }
+        let writes = !matches!(mode, FsMode::ReadOnly);
+        for tag in &art.governance_tags {
+            match tag {
+                GovernanceTag::Evolve if writes => {
+                    if via_evolve_token {
+                        d.pass(CheckKind::Token, "EVOLVE", "EVOLVE token present");
+                    } else {
+                        d.fail(CheckKind::Token, "EVOLVE", "EVOLVE-tagged artifact requires an evolve token to change");
+                    }
+                }
+                GovernanceTag::Evolve => {
+                    d.skip(CheckKind::Token, "EVOLVE", "evolve token only required for writes");
+                }
+                GovernanceTag::Smart => match self.smart_scopes.get(&art.subject_id) {
+                    Some(scope) if scope.is_active(unix_now()) => d.pass(
+                        CheckKind::Rule,
+                        "SMART",
+                        format!("SmartScope active until {} for {:?}", scope.expires_at, scope.domains),
+                    ),
+                    Some(_) => d.fail(CheckKind::Rule, "SMART", "SmartScope expired or revoked"),
+                    None => d.fail(CheckKind::Rule, "SMART", "SMART-tagged artifact requires an active SmartScope"),
+                },
+                GovernanceTag::Export if writes => Self::export_rules(&mut d, art),
+                GovernanceTag::Export => {
+                    d.skip(CheckKind::Flag, "EXPORT", "export checks apply to writes and exports, not local reads");
+                }
+            }
+        }
+        d.finish()
+    }
+
+    /// For the export pipeline, before an EXPORT-tagged artifact leaves the node.
+    pub fn check_export(&self, art: &SovereignArtifact) -> GuardDecision {
+        let mut d = DecisionBuilder::new("TagGuard", "export");
+        if art.governance_tags.contains(&GovernanceTag::Export) {
+            Self::export_rules(&mut d, art);
+        } else {
+            d.fail(CheckKind::Rule, "EXPORT", format!("{} is not tagged EXPORT", art.path));
+        }
+        d.finish()
+    }
+
+    /// Export pipeline: nothing neurorights-protected leaves the subject's sovereignty.
+    fn export_rules(d: &mut DecisionBuilder, art: &SovereignArtifact) {
+        let n = &art.neurorights;
+        if n.soul_non_tradeable {
+            d.fail(
+                CheckKind::Flag,
+                "EXPORT/soul_non_tradeable",
+                "soul-non-tradeable artifact cannot be exported or tokenized",
+            );
+        } else if n.dreamstate_sensitive {
+            d.fail(CheckKind::Flag, "EXPORT/dreamstate_sensitive", "dreamstate-sensitive data cannot be exported");
+        } else if n.mental_privacy && matches!(art.kind, ArtifactKind::NeuralShard) {
+            d.fail(CheckKind::Flag, "EXPORT/mental_privacy", "raw neural shards under mental privacy cannot be exported");
+        } else {
+            d.pass(CheckKind::Flag, "EXPORT", "no neurorights flag blocks export");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::routes::Route;
+
+    fn tagged(tags: &[GovernanceTag]) -> SovereignArtifact {
+        SovereignArtifact {
+            kind: ArtifactKind::GenericData,
+            routes: vec![Route::Chat],
+            governance_tags: tags.to_vec(),
+            ..SovereignArtifact::sovereign_config("subject-1.dream.json", "subject-1")
+        }
+    }
+
+    #[test]
+    fn export_tagged_artifacts_stay_readable_locally() {
+        let guard = TagGuard::default();
+        let mut art = tagged(&[GovernanceTag::Export]);
+        art.neurorights.dreamstate_sensitive = true;
+
+        assert!(guard.check(&art, FsMode::ReadOnly, false).is_allowed());
+        let write = guard.check(&art, FsMode::WriteOnly, false);
+        assert!(!write.is_allowed());
+        assert!(write.summary().contains("dreamstate-sensitive"), "{}", write.summary());
+        assert!(!guard.check_export(&art).is_allowed());
+
+        art.neurorights.dreamstate_sensitive = false;
+        assert!(guard.check(&art, FsMode::ReadWrite, false).is_allowed());
+        assert!(guard.check_export(&art).is_allowed());
+        assert!(!guard.check_export(&tagged(&[])).is_allowed());
+    }
+
+    #[test]
+    fn evolve_gates_writes_only() {
+        let guard = TagGuard::default();
+        let art = tagged(&[GovernanceTag::Evolve]);
+        assert!(guard.check(&art, FsMode::ReadOnly, false).is_allowed());
+        assert!(!guard.check(&art, FsMode::WriteOnly, false).is_allowed());
+        assert!(guard.check(&art, FsMode::WriteOnly, true).is_allowed());
+    }
+
+    #[test]
+    fn smart_needs_an_active_scope() {
+        let mut guard = TagGuard::default();
+        let art = tagged(&[GovernanceTag::Smart]);
+        assert!(!guard.check(&art, FsMode::ReadOnly, false).is_allowed());
+
+        let scope = SmartScope {
+            subject_id: "subject-1".into(),
+            domains: vec!["sleep".into()],
+            maxeffectsizel2: 0.1,
+            expires_at: u64::MAX,
+            physioguard_enabled: true,
+            revoked: false,
+        };
+        guard.set_smart_scope(scope.clone());
+        assert!(guard.check(&art, FsMode::ReadOnly, false).is_allowed());
+        guard.set_smart_scope(SmartScope { revoked: true, ..scope });
+        assert!(!guard.check(&art, FsMode::ReadOnly, false).is_allowed());
+    }
+
+    #[test]
+    fn tags_are_validated_and_inherited() {
+        assert!("SMART".to_string().try_into().is_ok_and(|t: GovernanceTag| t == GovernanceTag::Smart));
+        assert!(GovernanceTag::try_from("TRADE".to_string()).is_err());
+
+        let mut art = tagged(&[GovernanceTag::Export, GovernanceTag::Export]);
+        assert!(validate_tags(&art).is_err());
+        art.governance_tags = vec![GovernanceTag::Export];
+        art.neurorights.soul_non_tradeable = true;
+        assert!(validate_tags(&art).is_err());
+
+        let input = tagged(&[GovernanceTag::Smart, GovernanceTag::Export]);
+        assert_eq!(inherit_tags(&[GovernanceTag::Evolve], &[&input]), [GovernanceTag::Evolve, GovernanceTag::Smart]);
+    }
+}
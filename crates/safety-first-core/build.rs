@@ -0,0 +1,14 @@
+//! Generates the safety core from the authoritative shard, so the Rust types,
+//! thresholds and rules cannot drift from it.
+
+fn main() {
+    let generated = aln_core::codegen::Codegen::new()
+        .type_name("sleepstage", "SleepStage")
+        .type_name("dreammode", "DreamMode")
+        .type_name("logtype", "LogType")
+        .constants_from("derived")
+        .build("shards/xr-grid.quantum-roaming-debug.v2.aln", "safety_first_core.rs");
+    if let Err(e) = generated {
+        panic!("{}", e);
+    }
+}
@@ -0,0 +1,157 @@
+//! Sleep-stage gated quantum roaming, generated from
+//! `shards/xr-grid.quantum-roaming-debug.v2.aln`. Edit the shard, not this crate.
+//!
+//! The hand-written part is what the shard cannot express: clamping S, R and
+//! Es before the rules run, the dream-mode requirement on top of the shard's
+//! roaming guard, and `adjust_for_cognitive_load`.
+
+include!(concat!(env!("OUT_DIR"), "/safety_first_core.rs"));
+
+/// The shard this crate is generated from, as written.
+pub const SHARD_SOURCE: &str = include_str!("../shards/xr-grid.quantum-roaming-debug.v2.aln");
+
+/// How much detail explanations carry, from the accessibility score
+/// `A = 1 - cognitive load band`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputComplexity {
+    Detailed,
+    Standard,
+    Simplified,
+}
+
+/// Simplified explanations below A = 0.4, standard ones below A = 0.7.
+pub fn adjust_for_cognitive_load(cognitive_load_band: f64) -> OutputComplexity {
+    let accessibility = 1.0 - cognitive_load_band.clamp(0.0, 1.0);
+    if accessibility >= 0.7 {
+        OutputComplexity::Detailed
+    } else if accessibility >= 0.4 {
+        OutputComplexity::Standard
+    } else {
+        OutputComplexity::Simplified
+    }
+}
+
+impl ShardState {
+    /// Clamps S, R and Es into the `range0,1` the shard declares for them, so
+    /// out-of-range readings cannot push E outside [0, 1].
+    pub fn clamp_inputs(&mut self) {
+        let subject = &mut self.subject;
+        subject.sleeptoken = subject.sleeptoken.clamp(0.0, 1.0);
+        subject.psychriskscore = subject.psychriskscore.clamp(0.0, 1.0);
+        subject.enstasisscore = subject.enstasisscore.clamp(0.0, 1.0);
+    }
+
+    /// Runs the shard's rules on clamped inputs. Roaming needs the shard's
+    /// guard to pass and the subject to be in quantum_consciousness dream mode.
+    pub fn quantum_roaming_allowed(&mut self) -> Result<bool, String> {
+        self.clamp_inputs();
+        apply_rules(self)?;
+        Ok(self.derived.quantum_roaming_allowed && self.subject.dreammode == DreamMode::QuantumConsciousness)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state(stage: SleepStage, sleeptoken: f64, psychriskscore: f64, dreammode: DreamMode) -> ShardState {
+        let mut state = ShardState::default();
+        state.subject.sleepstage = stage;
+        state.subject.sleeptoken = sleeptoken;
+        state.subject.psychriskscore = psychriskscore;
+        state.subject.dreammode = dreammode;
+        state
+    }
+
+    #[test]
+    fn eligibility_at_emin_is_enough() {
+        let mut at = state(SleepStage::N2, EMIN, 0.0, DreamMode::QuantumConsciousness);
+        assert!(at.quantum_roaming_allowed().unwrap());
+        assert_eq!(at.derived.eligibility_e, 0.5);
+
+        let mut below = state(SleepStage::N2, 0.49, 0.0, DreamMode::QuantumConsciousness);
+        assert!(!below.quantum_roaming_allowed().unwrap());
+    }
+
+    #[test]
+    fn risk_above_rmax_blocks_and_clamps() {
+        let mut at = state(SleepStage::N3, 1.0, RMAX, DreamMode::QuantumConsciousness);
+        assert!(at.quantum_roaming_allowed().unwrap());
+
+        let mut above = state(SleepStage::N3, 1.0, 0.36, DreamMode::Passive);
+        above.subject.player_state = PlayerState::QuantumRoaming;
+        let fired = apply_rules(&mut above).unwrap();
+        assert!(!above.derived.quantum_roaming_allowed);
+        assert!(fired.contains(&"forceObserverOnHighRisk"));
+        assert_eq!(above.subject.player_state, PlayerState::ObserverOnly);
+    }
+
+    #[test]
+    fn only_deep_sleep_allows_roaming() {
+        for stage in SleepStage::ALL {
+            let mut s = state(stage, 1.0, 0.0, DreamMode::QuantumConsciousness);
+            let deep = matches!(stage, SleepStage::N2 | SleepStage::N3);
+            assert_eq!(s.quantum_roaming_allowed().unwrap(), deep, "{}", stage);
+        }
+    }
+
+    #[test]
+    fn dream_mode_decides_the_player_state() {
+        for mode in DreamMode::ALL {
+            let mut allowed = state(SleepStage::N2, 1.0, 0.0, mode);
+            apply_rules(&mut allowed).unwrap();
+            let roaming = mode == DreamMode::QuantumConsciousness;
+            assert_eq!(allowed.subject.player_state == PlayerState::QuantumRoaming, roaming, "{}", mode);
+
+            let mut awake = state(SleepStage::Wake, 1.0, 0.0, mode);
+            let fired = apply_rules(&mut awake).unwrap();
+            assert_eq!(awake.subject.player_state, PlayerState::ObserverOnly);
+            assert_eq!(fired.contains(&"logRoamingFailure"), roaming, "{}", mode);
+        }
+
+        let mut awake = state(SleepStage::Wake, 1.0, 0.0, DreamMode::QuantumConsciousness);
+        apply_rules(&mut awake).unwrap();
+        assert_eq!(awake.log.logtype, LogType::Debug);
+        assert_eq!(
+            awake.log.message,
+            "Quantum roaming disallowed: sleepstage=wake, eligibilityE=1, psychriskscore=0"
+        );
+    }
+
+    #[test]
+    fn roaming_needs_quantum_consciousness() {
+        for mode in DreamMode::ALL {
+            let mut s = state(SleepStage::N2, 1.0, 0.0, mode);
+            assert_eq!(s.quantum_roaming_allowed().unwrap(), mode == DreamMode::QuantumConsciousness, "{}", mode);
+            assert!(s.derived.quantum_roaming_allowed);
+        }
+    }
+
+    #[test]
+    fn inputs_are_clamped_to_their_declared_range() {
+        let mut s = state(SleepStage::N2, 1.5, -0.2, DreamMode::QuantumConsciousness);
+        s.subject.enstasisscore = 3.0;
+        assert!(s.quantum_roaming_allowed().unwrap());
+        assert_eq!((s.subject.sleeptoken, s.subject.psychriskscore, s.subject.enstasisscore), (1.0, 0.0, 1.0));
+        assert_eq!(s.derived.eligibility_e, 1.0);
+
+        let mut risky = state(SleepStage::N2, 1.0, 4.0, DreamMode::QuantumConsciousness);
+        assert!(!risky.quantum_roaming_allowed().unwrap());
+        assert_eq!(risky.derived.eligibility_e, 0.0);
+    }
+
+    #[test]
+    fn cognitive_load_picks_the_output_complexity() {
+        let cases = [
+            (-1.0, OutputComplexity::Detailed),
+            (0.3, OutputComplexity::Detailed),
+            (0.31, OutputComplexity::Standard),
+            (0.6, OutputComplexity::Standard),
+            (0.61, OutputComplexity::Simplified),
+            (2.0, OutputComplexity::Simplified),
+        ];
+        for (band, expected) in cases {
+            assert_eq!(adjust_for_cognitive_load(band), expected, "{}", band);
+        }
+    }
+}
@@ -1,15 +1,12 @@
 //! xr-safety CLI: Accessible tool for checking safety rules
 //! No complex setup, just compile and run
 
-use std::fs;
-use std::path::Path;
-
 fn main() {
     let args: Vec<String> = std::env::args().collect();
     
     if args.len() < 3 {
         println!("Usage: xr-safety summary <aln-file>");
-        println!("Example: xr-safety summary quantum-roaming-debug.v1.aln");
+        println!("Example: xr-safety summary xr-grid.quantum-roaming-debug.v2.aln");
         return;
     }
     
@@ -25,41 +22,85 @@ fn print_safety_summary(filename: &str) {
     println!("=== XR SAFETY SUMMARY ===");
     println!("File: {}", filename);
     
-    // Read and parse ALN shard (simplified)
-    if let Ok(content) = fs::read_to_string(filename) {
-        // Extract key values (simplified parsing)
-        let sleeptoken = extract_value(&content, "sleeptoken").unwrap_or(0.0);
-        let psychriskscore = extract_value(&content, "psychriskscore").unwrap_or(0.0);
-        let enstasisscore = extract_value(&content, "enstasisscore").unwrap_or(1.0);
-        let sleepstage = extract_string(&content, "sleepstage").unwrap_or("wake".to_string());
-        let dreammode = extract_string(&content, "dreammode").unwrap_or("passive".to_string());
-        
-        // Calculate
-        let e = sleeptoken * (1.0 - psychriskscore) * enstasisscore;
-        let allowed = e >= 0.5 && psychriskscore <= 0.35 && 
-                     (sleepstage == "N2" || sleepstage == "N3") &&
-                     dreammode == "quantum_consciousness";
-        
-        // Simple output
-        println!("Sleep stage: {}", sleepstage);
-        println!("Dream mode: {}", dreammode);
-        println!("Risk score: {:.2}", psychriskscore);
-        println!("Eligibility E: {:.2}", e);
-        println!("Quantum roaming allowed: {}", if allowed { "YES" } else { "NO" });
-        
-        if !allowed {
-            println!("\n⚠️  BLOCKED REASONS:");
-            if e < 0.5 { println!("- Eligibility {:.2} < 0.5", e); }
-            if psychriskscore > 0.35 { println!("- Risk {:.2} > 0.35", psychriskscore); }
-            if sleepstage != "N2" && sleepstage != "N3" { 
-                println!("- Sleep stage {} not N2/N3", sleepstage); 
-            }
-            if dreammode != "quantum_consciousness" { 
-                println!("- Dream mode {} not quantum_consciousness", dreammode); 
+    let shard = match aln_core::load_verified(filename) {
+        Ok(shard) => shard,
+        Err(aln_core::AlnError::Parse(e)) => {
+            println!("Could not parse shard: {}", e);
+            return;
+        }
+        Err(aln_core::AlnError::Signature(e)) => {
+            println!("Refusing shard: {}", e);
+            return;
+        }
+        Err(e @ aln_core::AlnError::Outdated { .. }) => {
+            println!("Refusing shard: {}", e);
+            return;
+        }
+        Err(aln_core::AlnError::Invalid(errors)) => {
+            println!("Refusing shard:");
+            for e in errors { println!("- {}", e); }
+            return;
+        }
+        Err(aln_core::AlnError::Migration(e)) => {
+            println!("Could not migrate shard: {}", e);
+            return;
+        }
+        Err(aln_core::AlnError::Io(e)) => {
+            println!("Could not read file ({}). Here's a template ALN to create:", e);
+            print_aln_template();
+            return;
+        }
+    };
+
+    let violations = aln_core::validate_shard(&shard);
+    if !violations.is_empty() {
+        println!("Shard violates its own constraints:");
+        for v in violations { println!("- {}", v); }
+        return;
+    }
+
+    // The shard's own rules decide; nothing is re-implemented here
+    let rules = match aln_core::expr::RuleSet::from_shard(&shard) {
+        Ok(rules) => rules,
+        Err(errors) => {
+            println!("Shard rules do not type-check:");
+            for e in errors { println!("- {}", e); }
+            return;
+        }
+    };
+    let mut state = rules.default_state();
+    if let Err(e) = rules.evaluate(&mut state) {
+        println!("Rule evaluation failed: {}", e);
+        return;
+    }
+    let (schema, _) = aln_core::Schema::from_shard(&shard);
+    for (key, value) in &state {
+        if let Some(kind) = schema.check_value(key, value) {
+            println!("State violation: {}", aln_core::Violation { key: key.clone(), span: None, record: None, kind });
+        }
+    }
+    let show = |key: &str| state.get(key).map(|v| v.to_string()).unwrap_or_else(|| "-".to_string());
+    let allowed = state.get("quantum_roaming_allowed") == Some(&aln_core::expr::Value::Bool(true));
+    
+    // Simple output
+    println!("Sleep stage: {}", show("sleepstage"));
+    println!("Dream mode: {}", show("dreammode"));
+    println!("Risk score: {}", show("psychriskscore"));
+    println!("Eligibility E: {}", show("eligibilityE"));
+    println!("Player state: {}", show("player_state"));
+    println!("Quantum roaming allowed: {}", if allowed { "YES" } else { "NO" });
+    
+    if !allowed {
+        println!("\n⚠️  BLOCKED REASONS:");
+        let guard = rules.rules.iter().flat_map(|r| r.rule.assignments()).find(|a| a.target == "quantum_roaming_allowed");
+        for check in guard.map(|a| a.value.conjuncts()).unwrap_or_default() {
+            if aln_core::expr::eval(check, &state, &rules.env) != Ok(aln_core::expr::Value::Bool(true)) {
+                println!("- {} is false", check);
             }
         }
-    } else {
-        println!("Could not read file. Here's a template ALN to create:");
-        print_aln_template();
     }
 }
+
+fn print_aln_template() {
+    print!("{}", safety_first_core::SHARD_SOURCE);
+}
@@ -29,7 +29,7 @@ enum NeuroGuard {
     SovereignKernelLock,
 }
 
-fn run_cmd(label: &str, mut cmd: Command) -> std::io::Result<()> {
+fn run_cmd(label: &str, cmd: &mut Command) -> std::io::Result<()> {
     println!("[*] {label}");
     let status = cmd.status()?;
     if !status.success() {
@@ -53,7 +53,7 @@ fn xfs_load_data(cfg: &XfsConfig) -> std::io::Result<()> {
     let pattern = cfg.data_dir.join("*.dat");
     println!("[*] Loading data shards from {:?}", pattern);
     for entry in glob::glob(pattern.to_str().unwrap()).unwrap() {
-        let path = entry?;
+        let path = entry.map_err(glob::GlobError::into_error)?;
         run_cmd(
             &format!("load --data {:?}", path),
             Command::new(&cfg.xfs_iface).arg("load").arg("--data").arg(&path),
@@ -66,7 +66,7 @@ fn xfs_load_exec(cfg: &XfsConfig) -> std::io::Result<()> {
     let pattern = cfg.exec_dir.join("*.xexe");
     println!("[*] Loading exec shards from {:?}", pattern);
     for entry in glob::glob(pattern.to_str().unwrap()).unwrap() {
-        let path = entry?;
+        let path = entry.map_err(glob::GlobError::into_error)?;
         run_cmd(
             &format!("load --exec {:?}", path),
             Command::new(&cfg.xfs_iface).arg("load").arg("--exec").arg(&path),
@@ -1,142 +1,13 @@
 mod fs {
-    pub mod types {
-        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-        pub enum FileType {
-            Root,
-            Data,
-            Exec,
-            NeuroStream,
-            BioSnapshot,
-        }
-
-        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-        pub enum Permission {
-            Exclusive,
-            Open,
-            SharedRead,
-            SharedWrite,
-        }
-
-        #[derive(Debug, Clone)]
-        pub struct NeuroRights {
-            pub mental_privacy: bool,
-            pub mental_integrity: bool,
-            pub cognitive_liberty: bool,
-            pub noncommercial_neural_data: bool,
-            pub soulnontradeable: bool,
-            pub dreamstate_sensitive: bool,
-            pub forbid_decision_use: bool,
-            pub forget_sla_hours: u32,
-        }
-
-        #[derive(Debug, Clone)]
-        pub struct FileAttr {
-            pub name: String,
-            pub owner: String,
-            pub size_words: u32,
-            pub file_type: FileType,
-            pub perm: Permission,
-            pub neurorights: Option<NeuroRights>,
-        }
-    }
-
-    pub mod class {
-        use super::types::FileType;
-
-        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-        pub enum FileClass {
-            Root,
-            SovereignConfig,
-            Ledger,
-            NeuralModel,
-            StreamShard,
-            Biospec,
-            GenericData,
-        }
-
-        pub fn classify(name: &str, ty: FileType) -> FileClass {
-            if ty == FileType::Root {
-                return FileClass::Root;
-            }
-
-            if name.ends_with(".neurorights.json")
-                || name.ends_with(".stake.aln")
-                || name.ends_with("neuro-workspace.manifest.aln")
-                || name.ends_with(".rohmodel.aln")
-            {
-                FileClass::SovereignConfig
-            } else if name.ends_with(".donutloop.aln")
-                || name.ends_with(".evolve.jsonl")
-                || name.ends_with(".answer.ndjson")
-                || name.ends_with(".nnet-loop.aln")
-            {
-                FileClass::Ledger
-            } else if name.ends_with(".nnetx")
-                || name.ends_with(".nnetw")
-                || name.ends_with(".nnetq")
-            {
-                FileClass::NeuralModel
-            } else if name.ends_with(".nstream.neuroaln")
-                || name.ends_with(".neuroaln")
-                || name.ends_with(".lifaln")
-            {
-                FileClass::StreamShard
-            } else if name.ends_with(".biospec.aln")
-                || name.ends_with(".ocpuenv")
-                || name.ends_with(".ocpulog")
-                || name.ends_with(".lifeforce.aln")
-            {
-                FileClass::Biospec
-            } else {
-                FileClass::GenericData
-            }
-        }
-    }
-
-    pub mod root {
-        use std::collections::HashMap;
-        use super::types::FileAttr;
-
-        #[derive(Debug, Clone)]
-        pub struct RootEntry {
-            pub attr: FileAttr,
-            pub start_block: u32,
-            pub block_count: u32,
-        }
-
-        #[derive(Debug)]
-        pub struct RootTable {
-            entries: HashMap<String, RootEntry>,
-        }
-
-        impl RootTable {
-            pub fn new() -> Self {
-                Self { entries: HashMap::new() }
-            }
-
-            pub fn create(&mut self, entry: RootEntry) -> Result<(), String> {
-                if self.entries.contains_key(&entry.attr.name) {
-                    return Err("File already exists".into());
-                }
-                self.entries.insert(entry.attr.name.clone(), entry);
-                Ok(())
-            }
-
-            pub fn get(&self, name: &str) -> Option<&RootEntry> {
-                self.entries.get(name)
-            }
-
-            pub fn delete(&mut self, name: &str) -> Option<RootEntry> {
-                self.entries.remove(name)
-            }
-
-            pub fn list(&self) -> impl Iterator<Item = &RootEntry> {
-                self.entries.values()
-            }
-        }
-    }
+    // The file model is the library's; this binary adds the protections.
+    pub use neuroxfs::fs::{class, root, types};
 
     pub mod protections {
+        use std::fmt;
+
+        use neuroxfs_core::artifact::{ArtifactKind, NeurorightsProfile, SovereignArtifact};
+        use neuroxfs_core::caller::{CallerContext, Purpose};
+        use neuroxfs_core::grants::{GrantOperation, GrantRegistry};
         use super::class::{FileClass, classify};
         use super::types::{FileAttr};
 
@@ -146,6 +17,52 @@ mod fs {
             SoulNonTradeableShield(String),
             DreamSanctumFilter(String),
             SovereignKernelLock(String),
+            PurposeBinding(String),
+        }
+
+        impl fmt::Display for ProtectionViolation {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                let (guard, reason) = match self {
+                    ProtectionViolation::AuraBoundaryGuard(r) => ("AuraBoundaryGuard", r),
+                    ProtectionViolation::SoulNonTradeableShield(r) => ("SoulNonTradeableShield", r),
+                    ProtectionViolation::DreamSanctumFilter(r) => ("DreamSanctumFilter", r),
+                    ProtectionViolation::SovereignKernelLock(r) => ("SovereignKernelLock", r),
+                    ProtectionViolation::PurposeBinding(r) => ("PurposeBinding", r),
+                };
+                write!(f, "{}: {}", guard, reason)
+            }
+        }
+
+        /// The descriptor delegation grants are matched against.
+        pub fn artifact_of(attr: &FileAttr) -> SovereignArtifact {
+            let kind = match classify(&attr.name, attr.file_type) {
+                FileClass::SovereignConfig => ArtifactKind::SovereignConfig,
+                FileClass::Ledger => ArtifactKind::DonutLedger,
+                FileClass::NeuralModel => ArtifactKind::Model,
+                FileClass::StreamShard => ArtifactKind::NeuralShard,
+                FileClass::Biospec => ArtifactKind::Biospec,
+                FileClass::Root | FileClass::GenericData => ArtifactKind::GenericData,
+            };
+            let neurorights = attr.neurorights.as_ref().map(|n| NeurorightsProfile {
+                mental_privacy: n.mental_privacy,
+                dreamstate_sensitive: n.dreamstate_sensitive,
+                soul_non_tradeable: n.soulnontradeable,
+                forbid_decision_use: n.forbid_decision_use,
+                mental_integrity: n.mental_integrity,
+            });
+            SovereignArtifact {
+                path: attr.name.clone(),
+                subject_id: attr.owner.clone(),
+                kind,
+                routes: Vec::new(),
+                route_allowances: Vec::new(),
+                roh_before: 0.0,
+                roh_after: 0.0,
+                neurorights: neurorights.unwrap_or_default(),
+                lifeforce_cost: 0.0,
+                governance_tags: Vec::new(),
+                inputs: Vec::new(),
+            }
         }
 
         pub fn check_on_create(attr: &FileAttr) -> Result<(), ProtectionViolation> {
@@ -169,11 +86,38 @@ mod fs {
             Ok(())
         }
 
-        pub fn check_on_read(attr: &FileAttr) -> Result<(), ProtectionViolation> {
+        /// Non-owners need an active read grant from the owner for their
+        /// declared purpose, whatever that purpose is.
+        pub fn check_on_read(
+            attr: &FileAttr,
+            caller: &CallerContext,
+            grants: &GrantRegistry,
+            now: u64,
+        ) -> Result<(), ProtectionViolation> {
+            // `CallerContext::new` already refused purposes the role may not declare.
+            // Introspection is the subject looking at their own data, nobody else's.
+            if caller.purpose() == Purpose::Introspection && caller.principal() != attr.owner {
+                return Err(ProtectionViolation::AuraBoundaryGuard(
+                    "introspection is only available to the owning subject".into(),
+                ));
+            }
+
+            if caller.principal() != attr.owner
+                && grants.find_active(caller, &artifact_of(attr), GrantOperation::Read, now).is_none()
+            {
+                return Err(ProtectionViolation::AuraBoundaryGuard(format!(
+                    "{} holds no active {:?} read grant from {}",
+                    caller.principal(),
+                    caller.purpose(),
+                    attr.owner
+                )));
+            }
+
             if let Some(neuro) = &attr.neurorights {
-                if neuro.mental_privacy && neuro.forbid_decision_use {
-                    // The actual enforcement would be context-aware; here we only show the pattern.
-                    // A real system would check the caller capability.
+                if neuro.forbid_decision_use && caller.purpose().is_decision_making() {
+                    return Err(ProtectionViolation::PurposeBinding(
+                        "forbid_decision_use: read for automated decision-making denied".into(),
+                    ));
                 }
             }
             Ok(())
@@ -193,51 +137,111 @@ mod fs {
     }
 
     pub mod syscalls {
+        use neuroxfs::fs::audit::{AccessAudit, AccessDecision, AccessOp};
+        use neuroxfs_core::caller::CallerContext;
+        use neuroxfs_core::clock::unix_now;
+        use neuroxfs_core::grants::GrantRegistry;
         use super::root::{RootEntry, RootTable};
-        use super::types::FileAttr;
         use super::protections::{self, ProtectionViolation};
 
+        /// Protected syscalls; every decision, allowed or not, goes to the access trail.
         pub struct FsHandle {
             pub root: RootTable,
+            pub grants: GrantRegistry,
+            pub audit: AccessAudit,
         }
 
         impl FsHandle {
-            pub fn new() -> Self {
-                Self { root: RootTable::new() }
+            pub fn new(grants: GrantRegistry, audit: AccessAudit) -> Self {
+                Self { root: RootTable::new(), grants, audit }
             }
 
-            pub fn create(&mut self, entry: RootEntry) -> Result<(), ProtectionViolation> {
-                protections::check_on_create(&entry.attr)?;
-                self.root.create(entry).map_err(|e| ProtectionViolation::AuraBoundaryGuard(e))
+            /// Record the outcome on the access trail, then hand it back. A
+            /// decision that cannot be recorded is refused.
+            fn logged(
+                &mut self,
+                caller: &CallerContext,
+                owner: &str,
+                name: &str,
+                op: AccessOp,
+                result: Result<(), ProtectionViolation>,
+            ) -> Result<(), ProtectionViolation> {
+                let decision = match &result {
+                    Ok(()) => AccessDecision::Allowed,
+                    Err(v) => AccessDecision::Denied(v.to_string()),
+                };
+                self.audit
+                    .record(caller, owner, name, op, decision)
+                    .map_err(ProtectionViolation::AuraBoundaryGuard)?;
+                result
             }
 
-            pub fn read(&self, name: &str) -> Result<(), ProtectionViolation> {
-                let entry = self.root.get(name)
-                    .ok_or_else(|| ProtectionViolation::AuraBoundaryGuard("No such file".into()))?;
-                protections::check_on_read(&entry.attr)
+            pub fn create(&mut self, entry: RootEntry, caller: &CallerContext) -> Result<(), ProtectionViolation> {
+                let (name, owner) = (entry.attr.name.clone(), entry.attr.owner.clone());
+                let result = protections::check_on_create(&entry.attr)
+                    .and_then(|_| self.root.create(entry).map_err(ProtectionViolation::AuraBoundaryGuard));
+                self.logged(caller, &owner, &name, AccessOp::Create, result)
             }
 
-            pub fn write(&mut self, name: &str) -> Result<(), ProtectionViolation> {
-                let entry = self.root.get(name)
-                    .ok_or_else(|| ProtectionViolation::AuraBoundaryGuard("No such file".into()))?;
-                protections::check_on_write(&entry.attr)
+            pub fn read(&mut self, name: &str, caller: &CallerContext) -> Result<(), ProtectionViolation> {
+                let (owner, result) = match self.root.get(name) {
+                    Some(entry) => (
+                        entry.attr.owner.clone(),
+                        protections::check_on_read(&entry.attr, caller, &self.grants, unix_now()),
+                    ),
+                    None => (String::new(), Err(ProtectionViolation::AuraBoundaryGuard("No such file".into()))),
+                };
+                self.logged(caller, &owner, name, AccessOp::Read, result)
             }
 
-            pub fn delete(&mut self, name: &str) -> Result<(), ProtectionViolation> {
-                let entry = self.root.delete(name)
-                    .ok_or_else(|| ProtectionViolation::AuraBoundaryGuard("No such file".into()))?;
-                protections::check_on_write(&entry.attr)
+            pub fn write(&mut self, name: &str, caller: &CallerContext) -> Result<(), ProtectionViolation> {
+                let (owner, result) = match self.root.get(name) {
+                    Some(entry) => (entry.attr.owner.clone(), protections::check_on_write(&entry.attr)),
+                    None => (String::new(), Err(ProtectionViolation::AuraBoundaryGuard("No such file".into()))),
+                };
+                self.logged(caller, &owner, name, AccessOp::Write, result)
+            }
+
+            /// The decision is on the trail before the entry leaves the root table.
+            pub fn delete(&mut self, name: &str, caller: &CallerContext) -> Result<(), ProtectionViolation> {
+                let (owner, result) = match self.root.get(name) {
+                    Some(entry) if caller.principal() != entry.attr.owner => (
+                        entry.attr.owner.clone(),
+                        Err(ProtectionViolation::AuraBoundaryGuard(format!(
+                            "only {} may delete {}",
+                            entry.attr.owner, name
+                        ))),
+                    ),
+                    Some(entry) => (entry.attr.owner.clone(), protections::check_on_write(&entry.attr)),
+                    None => (String::new(), Err(ProtectionViolation::AuraBoundaryGuard("No such file".into()))),
+                };
+                self.logged(caller, &owner, name, AccessOp::Delete, result)?;
+                self.root.delete(name);
+                Ok(())
             }
         }
     }
 }
 
+use neuroxfs::fs::audit::AccessAudit;
+use neuroxfs_core::caller::{CallerContext, Purpose, Role};
+use neuroxfs_core::grants::GrantRegistry;
+use neuroxfs_core::routes::Route;
 use fs::types::{FileAttr, FileType, Permission, NeuroRights};
 use fs::root::RootEntry;
 use fs::syscalls::FsHandle;
 
 fn main() {
-    let mut fs = FsHandle::new();
+    let trail_dir = std::env::temp_dir().join("sovereign_neurofs");
+    let audit = match std::fs::create_dir_all(&trail_dir).map_err(|e| e.to_string()).and_then(|_| AccessAudit::open(&trail_dir, "access")) {
+        Ok(audit) => audit,
+        Err(e) => {
+            println!("Cannot open the access trail: {}", e);
+            return;
+        }
+    };
+    // Grant books would be loaded here; this demo has none.
+    let mut fs = FsHandle::new(GrantRegistry::default(), audit);
 
     let neurorights = NeuroRights {
         mental_privacy: true,
@@ -265,8 +269,132 @@ fn main() {
         block_count: 4,
     };
 
-    match fs.create(entry) {
+    let subject = CallerContext::subject("subjectA", Route::Introspect);
+    match fs.create(entry, &subject) {
         Ok(()) => println!("Created subjectA.neuroaln with sovereign protections."),
-        Err(e) => println!("Creation blocked by protection: {:?}", e),
+        Err(e) => println!("Creation blocked by protection: {}", e),
+    }
+
+    let callers = [
+        subject.clone(),
+        CallerContext::new("scoring-agent", Role::Agent, Purpose::AutomatedDecision, Route::Chat)
+            .expect("agents may declare automated decision-making"),
+        CallerContext::new("lab", Role::Researcher, Purpose::Research, Route::Chat)
+            .expect("researchers may declare research"),
+    ];
+    if let Err(e) = CallerContext::new("lab", Role::Researcher, Purpose::ClinicalCare, Route::Chat) {
+        println!("Caller refused: {}", e);
+    }
+    for caller in &callers {
+        match fs.read("subjectA.neuroaln", caller) {
+            Ok(()) => println!("Read allowed for {} ({:?}).", caller.principal(), caller.purpose()),
+            Err(e) => println!("Read blocked by protection: {}", e),
+        }
+    }
+    if let Err(e) = fs.write("subjectA.neuroaln", &subject) {
+        println!("Write blocked by protection: {}", e);
+    }
+    if let Err(e) = fs.delete("subjectA.neuroaln", &subject) {
+        println!("Delete blocked by protection: {}", e);
+    }
+    println!("Access trail: {} (head {})", trail_dir.join("access.ocpulog").display(), fs.audit.head_hash());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::SigningKey;
+    use fs::protections::{check_on_read, ProtectionViolation};
+    use neuroxfs_core::grants::{
+        ArtifactSelector, DelegationGrant, GrantBook, GrantOperation, SubjectKeyring,
+    };
+
+    fn attr() -> FileAttr {
+        FileAttr {
+            name: "subjectA.neuroaln".into(),
+            owner: "subjectA".into(),
+            size_words: 0,
+            file_type: FileType::NeuroStream,
+            perm: Permission::Exclusive,
+            neurorights: None,
+        }
+    }
+
+    fn registry_with(purpose: Purpose) -> GrantRegistry {
+        let key = SigningKey::from_bytes(&[7; 32]);
+        let mut keyring = SubjectKeyring::default();
+        keyring.insert("subjectA", key.verifying_key());
+        let mut book = GrantBook::new("subjectA");
+        book.issue(
+            DelegationGrant {
+                grant_id: "g1".into(),
+                issuer_subject: "subjectA".into(),
+                grantee: "lab".into(),
+                selector: ArtifactSelector { kinds: Vec::new(), path_prefix: None },
+                operations: vec![GrantOperation::Read],
+                purpose,
+                issued_at: 100,
+                expires_at: 200,
+                revocable: true,
+            },
+            &key,
+        )
+        .unwrap();
+        let mut grants = GrantRegistry::new(keyring);
+        grants.load_book(&book).unwrap();
+        grants
+    }
+
+    #[test]
+    fn delegated_reads_need_an_active_grant() {
+        let lab = CallerContext::new("lab", Role::Researcher, Purpose::Research, Route::Chat).unwrap();
+        let nurse = CallerContext::new("lab", Role::Clinician, Purpose::ClinicalCare, Route::Chat).unwrap();
+        let grants = registry_with(Purpose::Research);
+
+        assert!(check_on_read(&attr(), &lab, &grants, 150).is_ok());
+        for (caller, now) in [(&lab, 250), (&lab, 50), (&nurse, 150)] {
+            assert!(matches!(
+                check_on_read(&attr(), caller, &grants, now),
+                Err(ProtectionViolation::AuraBoundaryGuard(_))
+            ));
+        }
+        assert!(check_on_read(&attr(), &lab, &GrantRegistry::default(), 150).is_err());
+    }
+
+    #[test]
+    fn decision_purposes_need_a_grant_too() {
+        let agent = CallerContext::new("scoring-agent", Role::Agent, Purpose::AutomatedDecision, Route::Chat).unwrap();
+        assert!(matches!(
+            check_on_read(&attr(), &agent, &registry_with(Purpose::Research), 150),
+            Err(ProtectionViolation::AuraBoundaryGuard(_))
+        ));
+    }
+
+    #[test]
+    fn deletes_are_on_the_trail_before_the_entry_goes() {
+        let dir = std::env::temp_dir().join(format!("sovereign-neurofs-delete-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let mut fs = FsHandle::new(GrantRegistry::default(), AccessAudit::open(&dir, "access").unwrap());
+        let owner = CallerContext::subject("subjectA", Route::Introspect);
+        let other = CallerContext::subject("subjectB", Route::Introspect);
+        fs.create(RootEntry { attr: attr(), start_block: 0, block_count: 1 }, &owner).unwrap();
+
+        assert!(fs.delete("subjectA.neuroaln", &other).is_err());
+        assert!(fs.root.get("subjectA.neuroaln").is_some());
+        fs.delete("subjectA.neuroaln", &owner).unwrap();
+        assert!(fs.root.get("subjectA.neuroaln").is_none());
+
+        let trail = std::fs::read_to_string(dir.join("access.ocpulog")).unwrap();
+        let deletes: Vec<&str> = trail.lines().filter(|l| l.contains(r#""action":"delete""#)).collect();
+        assert_eq!(deletes.len(), 2);
+        assert!(deletes[0].contains(r#""principal":"subjectB""#) && deletes[0].contains("deny: "));
+        assert!(deletes[1].contains(r#""detail":"allow""#));
+    }
+
+    #[test]
+    fn owners_read_without_a_grant() {
+        let owner = CallerContext::subject("subjectA", Route::Introspect);
+        assert!(check_on_read(&attr(), &owner, &GrantRegistry::default(), 150).is_ok());
     }
 }
@@ -0,0 +1,415 @@
+use std::fs::OpenOptions;
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use aln_core::parser::split_cells;
+use neuroxfs_core::audit::{AuditChain, AuditRecord, AuditSeverity, GENESIS_HASH};
+use neuroxfs_core::caller::CallerContext;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccessOp {
+    Create,
+    Open,
+    Read,
+    Write,
+    Delete,
+}
+
+impl AccessOp {
+    fn as_str(self) -> &'static str {
+        match self {
+            AccessOp::Create => "create",
+            AccessOp::Open => "open",
+            AccessOp::Read => "read",
+            AccessOp::Write => "write",
+            AccessOp::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AccessDecision {
+    Allowed,
+    Denied(String),
+}
+
+const ANCHOR_PREFIX: [&str; 4] = ["ROW", "donutloop", "access", "anchor"];
+
+/// `.donutloop.aln` row anchoring an entry's hash.
+pub fn anchor_row(record: &AuditRecord) -> String {
+    format!("{},{},{},sha256,,", ANCHOR_PREFIX.join(","), record.body.seq, record.hash)
+}
+
+/// Append-only access trail: full entries in `<name>.ocpulog` (the
+/// neuroxfs-core audit chain, one JSON record per line), hash anchors in
+/// `<name>.donutloop.aln`, and the current head in `<name>.ocpulog.head`.
+#[derive(Debug)]
+pub struct AccessAudit {
+    chain: AuditChain,
+    anchor_path: PathBuf,
+    head_path: PathBuf,
+}
+
+/// Contents of `path`, or empty if it does not exist yet.
+fn read_or_empty(path: &Path) -> Result<String, String> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => Ok(text),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+        Err(e) => Err(format!("{}: {}", path.display(), e)),
+    }
+}
+
+impl AccessAudit {
+    /// Open (or start) the trail under `dir`, verifying what is already there
+    /// against the recorded head. A `record` interrupted after the log write
+    /// leaves the anchor and head one entry behind; that is completed here,
+    /// anything else is refused.
+    pub fn open(dir: &Path, name: &str) -> Result<Self, String> {
+        let log_path = dir.join(format!("{}.ocpulog", name));
+        let anchor_path = dir.join(format!("{}.donutloop.aln", name));
+        let head_path = dir.join(format!("{}.ocpulog.head", name));
+        let log = read_or_empty(&log_path)?;
+        let anchors = read_or_empty(&anchor_path)?;
+        let head = read_or_empty(&head_path)?;
+        let expected_head = match head.trim() {
+            "" => GENESIS_HASH,
+            head => head,
+        };
+        if let Err(violation) = verify_trail(&log, &anchors, Some(expected_head)) {
+            let damaged = |_| format!("audit trail damaged: {:?}", violation);
+            let last = interrupted_record(&log, &anchors, expected_head).map_err(damaged)?;
+            if let Some(missing) = last.missing_anchor {
+                append_line(&anchor_path, &missing)?;
+            }
+            write_head(&head_path, &last.hash)?;
+        }
+        let chain = AuditChain::open(&log_path).map_err(|e| e.to_string())?;
+        Ok(Self {
+            chain,
+            anchor_path,
+            head_path,
+        })
+    }
+
+    pub fn head_hash(&self) -> &str {
+        self.chain.head_hash()
+    }
+
+    /// Append one entry; it is on disk, anchored and the head updated before
+    /// this returns, in that order, so a crash part-way leaves a trail `open`
+    /// can complete.
+    pub fn record(
+        &mut self,
+        ctx: &CallerContext,
+        owner: &str,
+        artifact: &str,
+        op: AccessOp,
+        decision: AccessDecision,
+    ) -> Result<AuditRecord, String> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        let (severity, detail) = match &decision {
+            AccessDecision::Allowed => (AuditSeverity::Info, "allow".to_string()),
+            AccessDecision::Denied(reason) => (AuditSeverity::Warning, format!("deny: {}", reason)),
+        };
+        let record = self
+            .chain
+            .append(
+                timestamp,
+                severity,
+                ctx.principal(),
+                &format!("{:?}", ctx.purpose()),
+                owner,
+                artifact,
+                op.as_str(),
+                &detail,
+            )
+            .map_err(|e| e.to_string())?
+            .clone();
+
+        append_line(&self.anchor_path, &anchor_row(&record))?;
+        write_head(&self.head_path, &record.hash)?;
+        Ok(record)
+    }
+}
+
+fn append_line(path: &Path, line: &str) -> Result<(), String> {
+    let mut f = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    writeln!(f, "{}", line).map_err(|e| format!("{}: {}", path.display(), e))?;
+    f.sync_data().map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+/// Replace the head file atomically, so it never holds a partial hash.
+fn write_head(path: &Path, hash: &str) -> Result<(), String> {
+    let tmp = path.with_extension("head.tmp");
+    let write = || -> std::io::Result<()> {
+        let mut f = std::fs::File::create(&tmp)?;
+        writeln!(f, "{}", hash)?;
+        f.sync_data()?;
+        std::fs::rename(&tmp, path)
+    };
+    write().map_err(|e| format!("{}: {}", path.display(), e))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AuditViolation {
+    Malformed { line: usize },
+    /// Entry out of place: something was removed, inserted or reordered.
+    Sequence { line: usize, expected: u64, found: u64 },
+    BrokenLink { seq: u64 },
+    /// Entry contents no longer match its hash.
+    Edited { seq: u64 },
+    AnchorMismatch { seq: u64 },
+    /// Log and anchors disagree on length, or the head is not the expected one.
+    Truncated { log_entries: u64, anchors: u64 },
+    HeadMismatch { expected: String, found: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrailReport {
+    pub entries: u64,
+    pub head_hash: String,
+}
+
+/// Check a trail end to end. Pass the recorded head hash as `expected_head`
+/// to also catch truncation of the log and anchors together.
+pub fn verify_trail(log: &str, anchors: &str, expected_head: Option<&str>) -> Result<TrailReport, AuditViolation> {
+    let records = verify_log(log)?;
+    let anchor_rows = anchor_rows(anchors)?;
+    if anchor_rows.len() != records.len() {
+        return Err(AuditViolation::Truncated {
+            log_entries: records.len() as u64,
+            anchors: anchor_rows.len() as u64,
+        });
+    }
+    verify_anchors(&anchor_rows, &records)?;
+
+    let head = records.last().map_or(GENESIS_HASH, |r| r.hash.as_str()).to_string();
+    if let Some(expected) = expected_head {
+        if expected != head {
+            return Err(AuditViolation::HeadMismatch {
+                expected: expected.to_string(),
+                found: head,
+            });
+        }
+    }
+    Ok(TrailReport {
+        entries: records.len() as u64,
+        head_hash: head,
+    })
+}
+
+/// Parse the log, checking sequence numbers, links and entry hashes.
+fn verify_log(log: &str) -> Result<Vec<AuditRecord>, AuditViolation> {
+    let mut prev = GENESIS_HASH.to_string();
+    let mut records = Vec::new();
+    for (idx, line) in log.lines().filter(|l| !l.is_empty()).enumerate() {
+        let record: AuditRecord =
+            serde_json::from_str(line).map_err(|_| AuditViolation::Malformed { line: idx + 1 })?;
+        if record.body.seq != idx as u64 {
+            return Err(AuditViolation::Sequence {
+                line: idx + 1,
+                expected: idx as u64,
+                found: record.body.seq,
+            });
+        }
+        if record.prev_hash != prev {
+            return Err(AuditViolation::BrokenLink { seq: record.body.seq });
+        }
+        if record.computed_hash() != record.hash {
+            return Err(AuditViolation::Edited { seq: record.body.seq });
+        }
+        prev = record.hash.clone();
+        records.push(record);
+    }
+    Ok(records)
+}
+
+/// Anchor rows of the `.donutloop.aln` file, split with the shard parser.
+fn anchor_rows(anchors: &str) -> Result<Vec<Vec<String>>, AuditViolation> {
+    let mut rows = Vec::new();
+    for (idx, line) in anchors.lines().enumerate() {
+        let cells = split_cells(line, idx + 1).map_err(|_| AuditViolation::AnchorMismatch { seq: rows.len() as u64 })?;
+        let cells: Vec<String> = cells.into_iter().map(|c| c.text).collect();
+        if cells.starts_with(&ANCHOR_PREFIX.map(String::from)) {
+            rows.push(cells);
+        }
+    }
+    Ok(rows)
+}
+
+/// Each anchor row must match the log entry at the same position.
+fn verify_anchors(rows: &[Vec<String>], records: &[AuditRecord]) -> Result<(), AuditViolation> {
+    for (seq, (row, record)) in rows.iter().zip(records).enumerate() {
+        if row.get(4) != Some(&seq.to_string()) || row.get(5) != Some(&record.hash) {
+            return Err(AuditViolation::AnchorMismatch { seq: seq as u64 });
+        }
+    }
+    Ok(())
+}
+
+/// The last entry of a trail whose `record` stopped after the log write.
+struct Interrupted {
+    hash: String,
+    /// Anchor row still to append, if the crash came before it was written.
+    missing_anchor: Option<String>,
+}
+
+/// Accept a trail that is intact except that the head, and possibly the last
+/// anchor, lag the log by exactly one entry.
+fn interrupted_record(log: &str, anchors: &str, head: &str) -> Result<Interrupted, ()> {
+    let records = verify_log(log).map_err(|_| ())?;
+    let Some((last, earlier)) = records.split_last() else {
+        return Err(());
+    };
+    let prev_head = earlier.last().map_or(GENESIS_HASH, |r| r.hash.as_str());
+    if head != prev_head {
+        return Err(());
+    }
+    let rows = anchor_rows(anchors).map_err(|_| ())?;
+    if rows.len() != records.len() && rows.len() != earlier.len() {
+        return Err(());
+    }
+    verify_anchors(&rows, &records).map_err(|_| ())?;
+    Ok(Interrupted {
+        hash: last.hash.clone(),
+        missing_anchor: (rows.len() == earlier.len()).then(|| anchor_row(last)),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use neuroxfs_core::routes::Route;
+
+    fn scratch(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("neuroxfs-access-audit-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn ctx() -> CallerContext {
+        CallerContext::subject("subject-1", Route::Introspect)
+    }
+
+    fn three_entries(dir: &Path) {
+        let mut audit = AccessAudit::open(dir, "trail").unwrap();
+        for op in [AccessOp::Create, AccessOp::Read, AccessOp::Delete] {
+            audit.record(&ctx(), "subject-1", "notes", op, AccessDecision::Allowed).unwrap();
+        }
+    }
+
+    fn drop_last_line(path: &Path) {
+        let text = std::fs::read_to_string(path).unwrap();
+        let mut lines: Vec<&str> = text.lines().collect();
+        lines.pop();
+        std::fs::write(path, lines.iter().map(|l| format!("{}\n", l)).collect::<String>()).unwrap();
+    }
+
+    #[test]
+    fn reopened_trail_continues_the_chain() {
+        let dir = scratch("reopen");
+        three_entries(&dir);
+        let mut audit = AccessAudit::open(&dir, "trail").unwrap();
+        let record = audit
+            .record(&ctx(), "", "missing", AccessOp::Open, AccessDecision::Denied("no such file".into()))
+            .unwrap();
+        assert_eq!(record.body.seq, 3);
+        assert_eq!(record.body.detail, "deny: no such file");
+        assert!(AccessAudit::open(&dir, "trail").is_ok());
+    }
+
+    #[test]
+    fn truncating_log_and_anchors_together_is_detected() {
+        let dir = scratch("truncate");
+        three_entries(&dir);
+        drop_last_line(&dir.join("trail.ocpulog"));
+        drop_last_line(&dir.join("trail.donutloop.aln"));
+        let err = AccessAudit::open(&dir, "trail").unwrap_err();
+        assert!(err.contains("HeadMismatch"), "{}", err);
+
+        // Removing the head as well does not help: a non-empty log needs one.
+        std::fs::remove_file(dir.join("trail.ocpulog.head")).unwrap();
+        assert!(AccessAudit::open(&dir, "trail").is_err());
+    }
+
+    fn rewrite_log(dir: &Path, edit: impl FnOnce(&mut Vec<String>)) {
+        let path = dir.join("trail.ocpulog");
+        let mut lines: Vec<String> = std::fs::read_to_string(&path).unwrap().lines().map(String::from).collect();
+        edit(&mut lines);
+        std::fs::write(&path, lines.iter().map(|l| format!("{}\n", l)).collect::<String>()).unwrap();
+    }
+
+    #[test]
+    fn reordered_lines_are_detected() {
+        let dir = scratch("reorder");
+        three_entries(&dir);
+        rewrite_log(&dir, |lines| lines.swap(1, 2));
+        let err = AccessAudit::open(&dir, "trail").unwrap_err();
+        assert!(err.contains("Sequence"), "{}", err);
+    }
+
+    #[test]
+    fn edited_lines_are_detected() {
+        let dir = scratch("edit");
+        three_entries(&dir);
+        rewrite_log(&dir, |lines| lines[1] = lines[1].replace("\"read\"", "\"open\""));
+        let err = AccessAudit::open(&dir, "trail").unwrap_err();
+        assert!(err.contains("Edited { seq: 1 }"), "{}", err);
+    }
+
+    #[test]
+    fn a_record_interrupted_after_the_log_write_is_completed() {
+        let dir = scratch("interrupted");
+        three_entries(&dir);
+        let head = std::fs::read_to_string(dir.join("trail.ocpulog.head")).unwrap();
+        let anchors = std::fs::read_to_string(dir.join("trail.donutloop.aln")).unwrap();
+
+        // Crash before the anchor: the anchor and head both lag by one.
+        let mut audit = AccessAudit::open(&dir, "trail").unwrap();
+        let record = audit.record(&ctx(), "subject-1", "notes", AccessOp::Write, AccessDecision::Allowed).unwrap();
+        std::fs::write(dir.join("trail.donutloop.aln"), &anchors).unwrap();
+        std::fs::write(dir.join("trail.ocpulog.head"), &head).unwrap();
+        let audit = AccessAudit::open(&dir, "trail").unwrap();
+        assert_eq!(audit.head_hash(), record.hash);
+        let log = std::fs::read_to_string(dir.join("trail.ocpulog")).unwrap();
+        let anchors = std::fs::read_to_string(dir.join("trail.donutloop.aln")).unwrap();
+        assert_eq!(verify_trail(&log, &anchors, Some(&record.hash)).unwrap().entries, 4);
+
+        // Crash after the anchor: only the head lags.
+        let mut audit = audit;
+        let record = audit.record(&ctx(), "subject-1", "notes", AccessOp::Read, AccessDecision::Allowed).unwrap();
+        std::fs::write(dir.join("trail.ocpulog.head"), format!("{}\n", anchor_hash(&anchors, 3))).unwrap();
+        assert_eq!(AccessAudit::open(&dir, "trail").unwrap().head_hash(), record.hash);
+    }
+
+    #[test]
+    fn a_head_two_entries_behind_is_not_recovered() {
+        let dir = scratch("lag-two");
+        three_entries(&dir);
+        let anchors = std::fs::read_to_string(dir.join("trail.donutloop.aln")).unwrap();
+        std::fs::write(dir.join("trail.ocpulog.head"), format!("{}\n", anchor_hash(&anchors, 0))).unwrap();
+        let err = AccessAudit::open(&dir, "trail").unwrap_err();
+        assert!(err.contains("HeadMismatch"), "{}", err);
+    }
+
+    fn anchor_hash(anchors: &str, seq: usize) -> String {
+        anchor_rows(anchors).unwrap()[seq][5].clone()
+    }
+
+    #[test]
+    fn unreadable_files_are_errors_not_empty_trails() {
+        let dir = scratch("unreadable");
+        std::fs::create_dir_all(dir.join("trail.donutloop.aln")).unwrap();
+        let err = AccessAudit::open(&dir, "trail").unwrap_err();
+        assert!(err.contains("trail.donutloop.aln"), "{}", err);
+    }
+}
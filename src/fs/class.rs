@@ -17,6 +17,7 @@ pub fn classify(name: &str, ty: FileType) -> FileClass {
     if name.ends_with(".neurorights.json")
         || name.ends_with(".stake.aln")
         || name.ends_with("neuro-workspace.manifest.aln")
+        || name.ends_with(".rohmodel.aln")
     {
         FileClass::SovereignConfig
     } else if name.ends_with(".donutloop.aln")
@@ -38,6 +39,7 @@ pub fn classify(name: &str, ty: FileType) -> FileClass {
     } else if name.ends_with(".biospec.aln")
         || name.ends_with(".ocpuenv")
         || name.ends_with(".ocpulog")
+        || name.ends_with(".lifeforce.aln")
     {
         FileClass::Biospec
     } else {
@@ -8,14 +8,14 @@ pub struct RootEntry {
     pub block_count: u32,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct RootTable {
     entries: HashMap<String, RootEntry>,
 }
 
 impl RootTable {
     pub fn new() -> Self {
-        Self { entries: HashMap::new() }
+        Self::default()
     }
 
     pub fn create(&mut self, entry: RootEntry) -> Result<(), String> {
@@ -1,43 +1,91 @@
+use neuroxfs_core::caller::CallerContext;
+
 use crate::fs::types::{FileAttr};
 use crate::fs::root::RootTable;
 use crate::fs::class::{FileClass, classify};
+use crate::fs::audit::{AccessAudit, AccessDecision, AccessOp};
 
 pub struct FsHandle {
     pub root: RootTable,
+    pub audit: AccessAudit,
     // backing store (disk image, device, or Couchbase / IPFS mapping)
 }
 
 impl FsHandle {
-    pub fn create(&mut self, attr: FileAttr) -> Result<(), String> {
+    /// Record the outcome in .ocpulog & .donutloop.aln, then hand it back.
+    fn logged<T>(
+        &mut self,
+        ctx: &CallerContext,
+        owner: &str,
+        name: &str,
+        op: AccessOp,
+        result: Result<T, String>,
+    ) -> Result<T, String> {
+        let decision = match &result {
+            Ok(_) => AccessDecision::Allowed,
+            Err(reason) => AccessDecision::Denied(reason.clone()),
+        };
+        self.audit.record(ctx, owner, name, op, decision)?;
+        result
+    }
+
+    /// Owner recorded with an entry; empty for names not in the root table.
+    fn owner_of(&self, name: &str) -> String {
+        self.root.get(name).map(|e| e.attr.owner.clone()).unwrap_or_default()
+    }
+
+    pub fn create(&mut self, attr: FileAttr, ctx: &CallerContext) -> Result<(), String> {
         let class = classify(&attr.name, attr.file_type);
+        let name = attr.name.clone();
+        let owner = attr.owner.clone();
 
         // enforce neurorights and sovereignty invariants
         if let Some(neuro) = &attr.neurorights {
             if neuro.soulnontradeable && class == FileClass::NeuralModel {
-                return Err("Cannot store soulnontradeable data in a generic neural model file".into());
+                return self.logged(
+                    ctx,
+                    &owner,
+                    &name,
+                    AccessOp::Create,
+                    Err("Cannot store soulnontradeable data in a generic neural model file".into()),
+                );
             }
         }
 
         // allocate blocks, update root, data structures
         // ...
-        Ok(())
+        self.logged(ctx, &owner, &name, AccessOp::Create, Ok(()))
+    }
+
+    pub fn open(&mut self, name: &str, ctx: &CallerContext) -> Result<(), String> {
+        let found = self.root.get(name).map(|_| ()).ok_or_else(|| format!("{}: no such file", name));
+        self.logged(ctx, &self.owner_of(name), name, AccessOp::Open, found)
     }
 
-    pub fn read(&self, name: &str, offset_words: u32, len_words: u32) -> Result<Vec<u32>, String> {
+    pub fn read(&mut self, name: &str, _offset_words: u32, _len_words: u32, ctx: &CallerContext) -> Result<Vec<u32>, String> {
         // translate to block offsets like eXpFS, but with additional RoH checks before returning
         // ...
-        Ok(Vec::new())
+        let result = match self.root.get(name) {
+            Some(_) => Ok(Vec::new()),
+            None => Err(format!("{}: no such file", name)),
+        };
+        self.logged(ctx, &self.owner_of(name), name, AccessOp::Read, result)
     }
 
-    pub fn write(&mut self, name: &str, offset_words: u32, data: &[u32]) -> Result<(), String> {
-        // check RoH + Tsafe; log into .ocpulog & .donutloop.aln
+    pub fn write(&mut self, name: &str, _offset_words: u32, _data: &[u32], ctx: &CallerContext) -> Result<(), String> {
+        // check RoH + Tsafe
         // ...
-        Ok(())
+        let result = self.root.get(name).map(|_| ()).ok_or_else(|| format!("{}: no such file", name));
+        self.logged(ctx, &self.owner_of(name), name, AccessOp::Write, result)
     }
 
-    pub fn delete(&mut self, name: &str) -> Result<(), String> {
-        // update free list, donutloop, ocpu logs
+    pub fn delete(&mut self, name: &str, ctx: &CallerContext) -> Result<(), String> {
+        // Logged while the entry still exists: if the record cannot be written, nothing is removed.
+        let found = self.root.get(name).map(|_| ()).ok_or_else(|| format!("{}: no such file", name));
+        self.logged(ctx, &self.owner_of(name), name, AccessOp::Delete, found)?;
+        // update free list
         // ...
+        self.root.delete(name);
         Ok(())
     }
 }
@@ -0,0 +1,18 @@
+//! Sovereign filesystem layer: the syscall surface with its access audit
+//! trail (`fs`) and the NeuroFS layout and UI asset pipeline (`neurofs`).
+
+pub mod fs {
+    pub mod audit;
+    pub mod class;
+    pub mod root;
+    pub mod syscalls;
+    pub mod types;
+}
+
+pub mod neurofs {
+    pub mod layout;
+    pub mod pipeline;
+    pub mod spec;
+    pub mod ui_asset_guard;
+    pub mod ui_asset_registry;
+}